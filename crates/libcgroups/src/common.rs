@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     fmt::{Debug, Display},
     fs::{self, File},
     io::{BufRead, BufReader, Write},
@@ -11,8 +12,10 @@ use nix::{
     sys::statfs::{statfs, CGROUP2_SUPER_MAGIC, TMPFS_MAGIC},
     unistd::Pid,
 };
+#[cfg(any(feature = "v1", feature = "v2"))]
+use oci_spec::runtime::LinuxRdma;
 use oci_spec::runtime::LinuxResources;
-#[cfg(any(feature = "cgroupsv2_devices", feature = "v1"))]
+#[cfg(any(feature = "cgroupsv2_devices", feature = "v1", feature = "systemd"))]
 use oci_spec::runtime::{
     LinuxDevice, LinuxDeviceBuilder, LinuxDeviceCgroup, LinuxDeviceCgroupBuilder, LinuxDeviceType,
 };
@@ -24,6 +27,7 @@ use super::v1;
 #[cfg(feature = "v2")]
 use super::v2;
 
+use super::events::EventListener;
 use super::stats::Stats;
 
 pub const CGROUP_PROCS: &str = "cgroup.procs";
@@ -31,25 +35,56 @@ pub const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup";
 
 pub trait CgroupManager {
     /// Adds a task specified by its pid to the cgroup
-    fn add_task(&self, pid: Pid) -> Result<()>;
+    fn add_task(&self, pid: Pid) -> Result<(), CgroupError>;
 
     /// Applies resource restrictions to the cgroup
-    fn apply(&self, controller_opt: &ControllerOpt) -> Result<()>;
+    fn apply(&self, controller_opt: &ControllerOpt) -> Result<(), CgroupError>;
 
     /// Removes the cgroup
-    fn remove(&self) -> Result<()>;
+    fn remove(&self) -> Result<(), CgroupError>;
 
     /// Sets the freezer cgroup to the specified state
-    fn freeze(&self, state: FreezerState) -> Result<()>;
+    fn freeze(&self, state: FreezerState) -> Result<(), CgroupError>;
 
     /// Retrieve statistics for the cgroup
-    fn stats(&self) -> Result<Stats>;
+    fn stats(&self) -> Result<Stats, CgroupError>;
 
     /// Gets the PIDs inside the cgroup
-    fn get_all_pids(&self) -> Result<Vec<Pid>>;
+    fn get_all_pids(&self) -> Result<Vec<Pid>, CgroupError>;
+
+    /// Checks if any process is still alive inside the cgroup
+    fn is_populated(&self) -> Result<bool, CgroupError> {
+        Ok(!self.get_all_pids()?.is_empty())
+    }
+
+    /// Creates a listener for events (e.g. oom kills) of the cgroup
+    fn event_listener(&self) -> Result<Box<dyn EventListener>, CgroupError>;
 }
 
-#[derive(Debug)]
+/// Errors returned by the cgroup managers. Failures that callers are expected
+/// to handle have their own variant, all others are reported as
+/// [`CgroupError::Other`] together with their context.
+#[derive(Debug, thiserror::Error)]
+pub enum CgroupError {
+    /// The cgroup setup of the system could not be detected or is not supported
+    #[error("unsupported cgroup setup: {0}")]
+    UnsupportedSetup(String),
+    /// Managing the cgroup requires a feature that was not enabled
+    #[error("{0} feature is required, but was not enabled during compile time")]
+    FeatureDisabled(&'static str),
+    /// The systemd cgroup manager was requested, but systemd is not running
+    #[error(
+        "systemd cgroup flag passed, but systemd support for managing cgroups is not available"
+    )]
+    SystemdUnavailable,
+    /// The cgroup does not exist (anymore)
+    #[error("cgroup {0:?} does not exist")]
+    NotFound(PathBuf),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CgroupSetup {
     Hybrid,
     Legacy,
@@ -127,6 +162,24 @@ pub fn read_cgroup_file<P: AsRef<Path>>(path: P) -> Result<String> {
     fs::read_to_string(path).with_context(|| format!("failed to open {path:?}"))
 }
 
+/// Formats the rdma limits of a device as expected by rdma.max, which is the
+/// same for cgroup v1 and v2. Limits that are not specified are reset to max,
+/// the same way the kernel treats them.
+#[cfg(any(feature = "v1", feature = "v2"))]
+pub(crate) fn format_rdma_limits(device: &str, limits: &LinuxRdma) -> String {
+    let format_limit = |limit: Option<u32>| match limit {
+        Some(limit) => limit.to_string(),
+        None => "max".to_owned(),
+    };
+
+    format!(
+        "{} hca_handle={} hca_object={}",
+        device,
+        format_limit(limits.hca_handles()),
+        format_limit(limits.hca_objects())
+    )
+}
+
 /// Determines the cgroup setup of the system. Systems typically have one of
 /// three setups:
 /// - Unified: Pure cgroup v2 system.
@@ -135,7 +188,7 @@ pub fn read_cgroup_file<P: AsRef<Path>>(path: P) -> Result<String> {
 ///   an additional unified hierarchy which doesn't have any
 ///   controllers attached. Resource control can purely be achieved
 ///   through the cgroup v1 hierarchy, not through the cgroup v2 hierarchy.
-pub fn get_cgroup_setup() -> Result<CgroupSetup> {
+pub fn get_cgroup_setup() -> Result<CgroupSetup, CgroupError> {
     let default_root = Path::new(DEFAULT_CGROUP_ROOT);
     match default_root.exists() {
         true => {
@@ -166,45 +219,77 @@ pub fn get_cgroup_setup() -> Result<CgroupSetup> {
                 return Ok(CgroupSetup::Legacy);
             }
         }
-        false => bail!("non default cgroup root not supported"),
+        false => {
+            return Err(CgroupError::UnsupportedSetup(
+                "non default cgroup root not supported".to_owned(),
+            ))
+        }
     }
 
-    bail!("failed to detect cgroup setup");
+    Err(CgroupError::UnsupportedSetup(
+        "failed to detect cgroup setup".to_owned(),
+    ))
 }
 
 pub fn create_cgroup_manager<P: Into<PathBuf>>(
     cgroup_path: P,
     systemd_cgroup: bool,
     container_name: &str,
-) -> Result<Box<dyn CgroupManager>> {
+) -> Result<Box<dyn CgroupManager>, CgroupError> {
+    create_cgroup_manager_with_annotations(
+        cgroup_path,
+        systemd_cgroup,
+        container_name,
+        &HashMap::new(),
+    )
+}
+
+/// Creates a cgroup manager like [`create_cgroup_manager`], which in addition
+/// is configured by the annotations of the container. The systemd cgroup
+/// manager reads the properties of the slices it creates from annotations
+/// prefixed with `org.youki.systemd.slice.`, other managers ignore them.
+pub fn create_cgroup_manager_with_annotations<P: Into<PathBuf>>(
+    cgroup_path: P,
+    systemd_cgroup: bool,
+    container_name: &str,
+    annotations: &HashMap<String, String>,
+) -> Result<Box<dyn CgroupManager>, CgroupError> {
     let cgroup_setup = get_cgroup_setup()?;
     let cgroup_path = cgroup_path.into();
+    // ref https://github.com/opencontainers/runtime-spec/blob/main/config-linux.md#cgroups-path
+    let systemd_cgroup = systemd_cgroup && !cgroup_path.is_absolute();
 
     match cgroup_setup {
-        CgroupSetup::Legacy | CgroupSetup::Hybrid => create_v1_cgroup_manager(cgroup_path),
+        CgroupSetup::Legacy | CgroupSetup::Hybrid => {
+            // systemd does not delegate cgroup v1 controllers to unprivileged
+            // users, so rootless containers keep using the cgroup filesystem
+            if !systemd_cgroup || !nix::unistd::geteuid().is_root() {
+                return create_v1_cgroup_manager(cgroup_path);
+            }
+            create_systemd_cgroup_manager(cgroup_path, container_name, cgroup_setup, annotations)
+        }
         CgroupSetup::Unified => {
-            // ref https://github.com/opencontainers/runtime-spec/blob/main/config-linux.md#cgroups-path
-            if cgroup_path.is_absolute() || !systemd_cgroup {
+            if !systemd_cgroup {
                 return create_v2_cgroup_manager(cgroup_path);
             }
-            create_systemd_cgroup_manager(cgroup_path, container_name)
+            create_systemd_cgroup_manager(cgroup_path, container_name, cgroup_setup, annotations)
         }
     }
 }
 
 #[cfg(feature = "v1")]
-fn create_v1_cgroup_manager(cgroup_path: PathBuf) -> Result<Box<dyn CgroupManager>> {
+fn create_v1_cgroup_manager(cgroup_path: PathBuf) -> Result<Box<dyn CgroupManager>, CgroupError> {
     log::info!("cgroup manager V1 will be used");
     Ok(Box::new(v1::manager::Manager::new(cgroup_path)?))
 }
 
 #[cfg(not(feature = "v1"))]
-fn create_v1_cgroup_manager(_cgroup_path: PathBuf) -> Result<Box<dyn CgroupManager>> {
-    bail!("cgroup v1 feature is required, but was not enabled during compile time");
+fn create_v1_cgroup_manager(_cgroup_path: PathBuf) -> Result<Box<dyn CgroupManager>, CgroupError> {
+    Err(CgroupError::FeatureDisabled("cgroup v1"))
 }
 
 #[cfg(feature = "v2")]
-fn create_v2_cgroup_manager(cgroup_path: PathBuf) -> Result<Box<dyn CgroupManager>> {
+fn create_v2_cgroup_manager(cgroup_path: PathBuf) -> Result<Box<dyn CgroupManager>, CgroupError> {
     log::info!("cgroup manager V2 will be used");
     Ok(Box::new(v2::manager::Manager::new(
         DEFAULT_CGROUP_ROOT.into(),
@@ -213,32 +298,35 @@ fn create_v2_cgroup_manager(cgroup_path: PathBuf) -> Result<Box<dyn CgroupManage
 }
 
 #[cfg(not(feature = "v2"))]
-fn create_v2_cgroup_manager(_cgroup_path: PathBuf) -> Result<Box<dyn CgroupManager>> {
-    bail!("cgroup v2 feature is required, but was not enabled during compile time");
+fn create_v2_cgroup_manager(_cgroup_path: PathBuf) -> Result<Box<dyn CgroupManager>, CgroupError> {
+    Err(CgroupError::FeatureDisabled("cgroup v2"))
 }
 
 #[cfg(feature = "systemd")]
 fn create_systemd_cgroup_manager(
     cgroup_path: PathBuf,
     container_name: &str,
-) -> Result<Box<dyn CgroupManager>> {
+    cgroup_setup: CgroupSetup,
+    annotations: &HashMap<String, String>,
+) -> Result<Box<dyn CgroupManager>, CgroupError> {
     if !systemd::booted() {
-        bail!(
-            "systemd cgroup flag passed, but systemd support for managing cgroups is not available"
-        );
+        return Err(CgroupError::SystemdUnavailable);
     }
 
     let use_system = nix::unistd::geteuid().is_root();
 
     log::info!(
-        "systemd cgroup manager with system bus {} will be used",
-        use_system
+        "systemd cgroup manager with system bus {} will be used on {} cgroup setup",
+        use_system,
+        cgroup_setup
     );
     Ok(Box::new(systemd::manager::Manager::new(
         DEFAULT_CGROUP_ROOT.into(),
         cgroup_path,
         container_name.into(),
         use_system,
+        cgroup_setup,
+        annotations,
     )?))
 }
 
@@ -246,8 +334,10 @@ fn create_systemd_cgroup_manager(
 fn create_systemd_cgroup_manager(
     _cgroup_path: PathBuf,
     _container_name: &str,
-) -> Result<Box<dyn CgroupManager>> {
-    bail!("systemd cgroup feature is required, but was not enabled during compile time");
+    _cgroup_setup: CgroupSetup,
+    _annotations: &HashMap<String, String>,
+) -> Result<Box<dyn CgroupManager>, CgroupError> {
+    Err(CgroupError::FeatureDisabled("systemd cgroup"))
 }
 
 pub fn get_all_pids(path: &Path) -> Result<Vec<Pid>> {
@@ -300,7 +390,7 @@ impl PathBufExt for PathBuf {
     }
 }
 
-#[cfg(any(feature = "cgroupsv2_devices", feature = "v1"))]
+#[cfg(any(feature = "cgroupsv2_devices", feature = "v1", feature = "systemd"))]
 pub(crate) fn default_allow_devices() -> Vec<LinuxDeviceCgroup> {
     vec![
         LinuxDeviceCgroupBuilder::default()
@@ -352,7 +442,7 @@ pub(crate) fn default_allow_devices() -> Vec<LinuxDeviceCgroup> {
     ]
 }
 
-#[cfg(any(feature = "cgroupsv2_devices", feature = "v1"))]
+#[cfg(any(feature = "cgroupsv2_devices", feature = "v1", feature = "systemd"))]
 pub(crate) fn default_devices() -> Vec<LinuxDevice> {
     vec![
         LinuxDeviceBuilder::default()
@@ -432,3 +522,20 @@ pub(crate) fn delete_with_retry<P: AsRef<Path>, L: Into<Option<Duration>>>(
 
     bail!("could not delete {:?}", path)
 }
+
+#[cfg(all(test, any(feature = "v1", feature = "v2")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_rdma_limits_max() {
+        let limits = oci_spec::runtime::LinuxRdmaBuilder::default()
+            .hca_handles(3u32)
+            .build()
+            .unwrap();
+        assert_eq!(
+            format_rdma_limits("mlx4_0", &limits),
+            "mlx4_0 hca_handle=3 hca_object=max"
+        );
+    }
+}
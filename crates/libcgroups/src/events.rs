@@ -0,0 +1,105 @@
+//! Notifications about noteworthy events that happen inside of a cgroup, e.g. the
+//! oom killer being invoked or the pids limit being hit.
+use std::{
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::stats;
+
+/// Events that can be raised for a cgroup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CgroupEvent {
+    /// A process in the cgroup has been killed by the oom killer
+    Oom,
+    /// Forking a process failed because the cgroup reached its pids limit
+    PidsLimit,
+}
+
+/// Listens for events of a cgroup. The file descriptor returned by `as_raw_fd`
+/// becomes readable when new events may be available, so it can be used with
+/// poll(2) or an event loop.
+pub trait EventListener: AsRawFd {
+    /// Collects the events that occurred since the last call
+    fn read_events(&mut self) -> Result<Vec<CgroupEvent>>;
+}
+
+/// Keeps track of a counter in a flat keyed file such as `memory.events` or
+/// `pids.events` and reports by how much it increased since it was last read.
+#[derive(Debug)]
+pub(crate) struct EventCounter {
+    path: PathBuf,
+    key: &'static str,
+    last: u64,
+}
+
+impl EventCounter {
+    pub(crate) fn new(path: &Path, key: &'static str) -> Self {
+        let mut counter = Self {
+            path: path.to_owned(),
+            key,
+            last: 0,
+        };
+        counter.last = counter.read().unwrap_or_default();
+        counter
+    }
+
+    fn read(&self) -> Result<u64> {
+        let events = stats::parse_flat_keyed_data(&self.path)?;
+        Ok(events.get(self.key).copied().unwrap_or_default())
+    }
+
+    /// Returns the increase of the counter since the last call. Counters of
+    /// files that do not exist (e.g. because the controller is not enabled)
+    /// never increase.
+    pub(crate) fn delta(&mut self) -> u64 {
+        match self.read() {
+            Ok(current) => {
+                let delta = current.saturating_sub(self.last);
+                self.last = current;
+                delta
+            }
+            Err(_) => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{create_temp_dir, set_fixture};
+
+    #[test]
+    fn test_event_counter_delta() {
+        let tmp = create_temp_dir("test_event_counter_delta").unwrap();
+        let events = set_fixture(
+            &tmp,
+            "memory.events",
+            "low 0\nhigh 0\nmax 4\noom 1\noom_kill 1\n",
+        )
+        .unwrap();
+
+        let mut counter = EventCounter::new(&events, "oom_kill");
+        assert_eq!(counter.delta(), 0);
+
+        set_fixture(
+            &tmp,
+            "memory.events",
+            "low 0\nhigh 0\nmax 6\noom 3\noom_kill 3\n",
+        )
+        .unwrap();
+        assert_eq!(counter.delta(), 2);
+        assert_eq!(counter.delta(), 0);
+    }
+
+    #[test]
+    fn test_event_counter_missing_file() {
+        let tmp = create_temp_dir("test_event_counter_missing_file").unwrap();
+        let mut counter = EventCounter::new(&tmp.join("pids.events"), "max");
+        assert_eq!(counter.delta(), 0);
+    }
+}
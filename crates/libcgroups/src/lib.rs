@@ -12,6 +12,7 @@ extern crate mockall;
 mod test;
 
 pub mod common;
+pub mod events;
 pub mod stats;
 #[cfg(feature = "systemd")]
 pub mod systemd;
@@ -23,6 +23,10 @@ pub struct Stats {
     pub blkio: BlkioStats,
     /// Memory statistics for the cgroup
     pub memory: MemoryStats,
+    /// Rdma statistics for the cgroup
+    pub rdma: RdmaStats,
+    /// Misc statistics for the cgroup
+    pub misc: HashMap<String, MiscStats>,
 }
 
 /// Reports the cpu statistics for a cgroup
@@ -77,6 +81,8 @@ pub struct MemoryStats {
     pub kernel_tcp: MemoryData,
     /// Page cache in bytes
     pub cache: u64,
+    /// Number of processes killed by the oom killer
+    pub oom_kill: u64,
     /// Returns true if hierarchical accounting is enabled
     pub hierarchy: bool,
     /// Various memory statistics
@@ -107,6 +113,37 @@ pub struct PidStats {
     pub limit: u64,
 }
 
+/// Reports rdma stats for a cgroup
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RdmaStats {
+    /// Current usage of each rdma device
+    pub current: Vec<RdmaEntry>,
+    /// Configured limits of each rdma device
+    pub limit: Vec<RdmaEntry>,
+}
+
+/// Reports rdma resources of a single device
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, PartialOrd, Ord)]
+pub struct RdmaEntry {
+    /// Name of the rdma device
+    pub device: String,
+    /// Number of HCA handles (u64::MAX means no limit)
+    pub hca_handles: u64,
+    /// Number of HCA objects (u64::MAX means no limit)
+    pub hca_objects: u64,
+}
+
+/// Reports stats for a single resource of the misc controller
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MiscStats {
+    /// Current usage of the resource
+    pub usage: u64,
+    /// Usage limit of the resource (u64::MAX means no limit)
+    pub limit: u64,
+    /// Number of times usage hit the limit
+    pub fail_count: u64,
+}
+
 /// Reports block io stats for a cgroup
 #[derive(Debug, Default, PartialEq, Serialize)]
 pub struct BlkioStats {
@@ -353,6 +390,56 @@ pub fn pid_stats(cgroup_path: &Path) -> Result<PidStats> {
     Ok(stats)
 }
 
+/// Returns cgroup rdma statistics. The format of the rdma files is the same for
+/// cgroup v1 and v2, e.g. `mlx4_0 hca_handle=2 hca_object=max`
+pub fn rdma_stats(cgroup_path: &Path) -> Result<RdmaStats> {
+    let current_path = cgroup_path.join("rdma.current");
+    // The rdma controller is often not available, e.g. if there is no rdma capable
+    // device, so this should not prevent stats from being reported
+    if !current_path.exists() {
+        return Ok(RdmaStats::default());
+    }
+
+    Ok(RdmaStats {
+        current: parse_rdma_entries(&current_path)?,
+        limit: parse_rdma_entries(&cgroup_path.join("rdma.max"))?,
+    })
+}
+
+fn parse_rdma_entries(file_path: &Path) -> Result<Vec<RdmaEntry>> {
+    let mut entries = Vec::new();
+    let data = common::read_cgroup_file(file_path)?;
+    for line in data.lines() {
+        let mut fields = line.split_ascii_whitespace();
+        let device = match fields.next() {
+            Some(device) => device,
+            None => continue,
+        };
+
+        let mut entry = RdmaEntry {
+            device: device.to_owned(),
+            ..Default::default()
+        };
+        for field in fields {
+            let (key, value) = field.split_once('=').with_context(|| {
+                format!("invalid rdma entry {} in {}", line, file_path.display())
+            })?;
+            let value = match value {
+                "max" => u64::MAX,
+                v => parse_value(v)?,
+            };
+            match key {
+                "hca_handle" => entry.hca_handles = value,
+                "hca_object" => entry.hca_objects = value,
+                _ => continue,
+            }
+        }
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
 pub fn psi_stats(psi_file: &Path) -> Result<PSIStats> {
     let mut stats = PSIStats::default();
 
@@ -589,6 +676,61 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_rdma_stats() {
+        let tmp = create_temp_dir("test_rdma_stats").unwrap();
+        set_fixture(
+            &tmp,
+            "rdma.current",
+            "mlx4_0 hca_handle=2 hca_object=2000\nocrdma1 hca_handle=3 hca_object=0\n",
+        )
+        .unwrap();
+        set_fixture(
+            &tmp,
+            "rdma.max",
+            "mlx4_0 hca_handle=10 hca_object=max\nocrdma1 hca_handle=max hca_object=max\n",
+        )
+        .unwrap();
+
+        let stats = rdma_stats(&tmp).unwrap();
+        assert_eq!(
+            stats,
+            RdmaStats {
+                current: vec![
+                    RdmaEntry {
+                        device: "mlx4_0".to_owned(),
+                        hca_handles: 2,
+                        hca_objects: 2000,
+                    },
+                    RdmaEntry {
+                        device: "ocrdma1".to_owned(),
+                        hca_handles: 3,
+                        hca_objects: 0,
+                    },
+                ],
+                limit: vec![
+                    RdmaEntry {
+                        device: "mlx4_0".to_owned(),
+                        hca_handles: 10,
+                        hca_objects: u64::MAX,
+                    },
+                    RdmaEntry {
+                        device: "ocrdma1".to_owned(),
+                        hca_handles: u64::MAX,
+                        hca_objects: u64::MAX,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_rdma_stats_not_available() {
+        let tmp = create_temp_dir("test_rdma_stats_not_available").unwrap();
+        let stats = rdma_stats(&tmp).unwrap();
+        assert_eq!(stats, RdmaStats::default());
+    }
+
     #[test]
     fn test_parse_psi_full_stats() {
         let tmp = create_temp_dir("test_parse_psi_full_stats").unwrap();
@@ -3,6 +3,8 @@ use std::fmt::Display;
 pub enum ControllerType {
     Cpu,
     CpuSet,
+    Devices,
+    HugeTlb,
     Io,
     Memory,
     Pids,
@@ -13,6 +15,8 @@ impl Display for ControllerType {
         let print = match self {
             ControllerType::Cpu => "cpu",
             ControllerType::CpuSet => "cpuset",
+            ControllerType::Devices => "devices",
+            ControllerType::HugeTlb => "hugetlb",
             ControllerType::Io => "io",
             ControllerType::Memory => "memory",
             ControllerType::Pids => "pids",
@@ -27,6 +31,8 @@ impl AsRef<str> for ControllerType {
         match self {
             ControllerType::Cpu => "cpu",
             ControllerType::CpuSet => "cpuset",
+            ControllerType::Devices => "devices",
+            ControllerType::HugeTlb => "hugetlb",
             ControllerType::Io => "io",
             ControllerType::Memory => "memory",
             ControllerType::Pids => "pids",
@@ -37,6 +43,8 @@ impl AsRef<str> for ControllerType {
 pub const CONTROLLER_TYPES: &[ControllerType] = &[
     ControllerType::Cpu,
     ControllerType::CpuSet,
+    ControllerType::Devices,
+    ControllerType::HugeTlb,
     ControllerType::Io,
     ControllerType::Memory,
     ControllerType::Pids,
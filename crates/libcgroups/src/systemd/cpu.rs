@@ -43,6 +43,13 @@ impl Cpu {
             }
         }
 
+        Self::apply_quota(cpu, properties);
+        Ok(())
+    }
+
+    /// Sets the cpu quota and period, which systemd supports on both cgroup
+    /// versions
+    pub(super) fn apply_quota(cpu: &LinuxCpu, properties: &mut HashMap<&str, Box<dyn RefArg>>) {
         // if quota is unrestricted set to 'max'
         let mut quota = u64::MAX;
         if let Some(specified_quota) = cpu.quota() {
@@ -63,8 +70,6 @@ impl Cpu {
             }
         }
         properties.insert(CPU_PERIOD, Box::new(period));
-
-        Ok(())
     }
 
     fn is_realtime_requested(cpu: &LinuxCpu) -> bool {
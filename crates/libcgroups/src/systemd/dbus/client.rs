@@ -1,10 +1,24 @@
-use crate::systemd::dbus::systemd_api::OrgFreedesktopSystemd1Manager;
-use anyhow::{Context, Result};
+use crate::common::{get_cgroup_setup, CgroupSetup};
+use crate::systemd::dbus::systemd_api::{
+    OrgFreedesktopDBusProperties, OrgFreedesktopSystemd1Manager,
+    OrgFreedesktopSystemd1ManagerJobRemoved,
+};
+use anyhow::{bail, Context, Result};
 use dbus::arg::{RefArg, Variant};
 use dbus::blocking::{Connection, Proxy};
+use dbus::message::SignalArgs;
+use dbus::Message;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Error returned by systemd if a transient unit is started that exists already
+const UNIT_EXISTS_ERROR: &str = "org.freedesktop.systemd1.UnitExists";
+/// Object path of the systemd manager
+const MANAGER_PATH: &str = "/org/freedesktop/systemd1";
+/// How long to wait for systemd to finish starting or stopping a unit
+const JOB_TIMEOUT: Duration = Duration::from_secs(30);
 
 pub trait SystemdClient {
     fn is_system(&self) -> bool;
@@ -21,6 +35,14 @@ pub trait SystemdClient {
 
     fn stop_transient_unit(&self, unit_name: &str) -> Result<()>;
 
+    /// Creates the slice as transient unit, unless a unit with the same name
+    /// exists already
+    fn start_transient_slice(&self, slice_name: &str) -> Result<()>;
+
+    /// Checks if the unit is a transient unit without any processes, which
+    /// includes the processes of the units it contains
+    fn transient_unit_is_empty(&self, unit_name: &str) -> Result<bool>;
+
     fn set_unit_properties(
         &self,
         unit_name: &str,
@@ -56,39 +78,102 @@ impl Client {
     }
 
     fn create_proxy(&self) -> Proxy<&Connection> {
+        self.create_proxy_for(MANAGER_PATH)
+    }
+
+    fn create_proxy_for<'p, P: Into<dbus::Path<'p>>>(&self, path: P) -> Proxy<'p, &Connection> {
         self.conn.with_proxy(
             "org.freedesktop.systemd1",
-            "/org/freedesktop/systemd1",
+            path,
             Duration::from_millis(5000),
         )
     }
-}
 
-impl SystemdClient for Client {
-    fn is_system(&self) -> bool {
-        self.system
+    /// Runs a method that enqueues a job for the unit, e.g. StartTransientUnit, and
+    /// waits until systemd has finished the job. Errors returned by the method are
+    /// passed on as dbus::Error, so that callers can check their name.
+    fn run_job<F>(&self, unit_name: &str, method: F) -> Result<()>
+    where
+        F: FnOnce(&Proxy<&Connection>) -> Result<dbus::Path<'static>, dbus::Error>,
+    {
+        let proxy = self.create_proxy();
+
+        // systemd only emits job signals if there is a subscriber. Subscribing
+        // again fails, which does no harm.
+        if let Err(err) = proxy.subscribe() {
+            log::debug!("failed to subscribe to systemd signals: {}", err);
+        }
+
+        // signals are sent from the unique name of systemd, so the rule must
+        // not filter on the well known name
+        let rule = OrgFreedesktopSystemd1ManagerJobRemoved::match_rule(
+            None,
+            Some(&dbus::Path::from(MANAGER_PATH)),
+        )
+        .static_clone();
+        // the job may finish before the method call returns, so the results
+        // have to be collected before the path of the job is known
+        let results: Arc<Mutex<HashMap<dbus::Path<'static>, String>>> = Arc::default();
+        let token = {
+            let results = Arc::clone(&results);
+            self.conn
+                .add_match(
+                    rule,
+                    move |job: OrgFreedesktopSystemd1ManagerJobRemoved,
+                          _: &Connection,
+                          _: &Message| {
+                        results.lock().unwrap().insert(job.job, job.result);
+                        true
+                    },
+                )
+                .context("failed to subscribe to systemd jobs")?
+        };
+
+        let result = method(&proxy)
+            .map_err(anyhow::Error::from)
+            .and_then(|job| self.wait_for_job(unit_name, &job, &results));
+
+        if let Err(err) = self.conn.remove_match(token) {
+            log::debug!("failed to unsubscribe from systemd jobs: {}", err);
+        }
+
+        result
     }
 
-    fn transient_unit_exists(&self, unit_name: &str) -> bool {
-        let proxy = self.create_proxy();
-        proxy.get_unit(unit_name).is_ok()
+    fn wait_for_job(
+        &self,
+        unit_name: &str,
+        job: &dbus::Path<'static>,
+        results: &Mutex<HashMap<dbus::Path<'static>, String>>,
+    ) -> Result<()> {
+        let deadline = Instant::now() + JOB_TIMEOUT;
+        loop {
+            if let Some(result) = results.lock().unwrap().remove(job) {
+                return job_result(unit_name, &result);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                bail!(
+                    "timed out after {:?} waiting for job {} of unit {}",
+                    JOB_TIMEOUT,
+                    job,
+                    unit_name
+                );
+            }
+            self.conn
+                .process(deadline - now)
+                .context("failed to receive systemd signals")?;
+        }
     }
 
-    /// start_transient_unit is a higher level API for starting a unit
-    /// for a specific container under systemd.
-    /// See https://www.freedesktop.org/wiki/Software/systemd/dbus for more details.
-    fn start_transient_unit(
+    fn try_start_transient_unit(
         &self,
         container_name: &str,
         pid: u32,
         parent: &str,
         unit_name: &str,
     ) -> Result<()> {
-        // To view and introspect the methods under the 'org.freedesktop.systemd1' destination
-        // and object path under it use the following command:
-        // `gdbus introspect --system --dest org.freedesktop.systemd1 --object-path /org/freedesktop/systemd1`
-        let proxy = self.create_proxy();
-
         // To align with runc, youki will always add the following properties to its container units:
         // - CPUAccounting=true
         // - IOAccounting=true (BlockIOAccounting for cgroup v1)
@@ -111,30 +196,131 @@ impl SystemdClient for Client {
             properties.push(("Delegate", Variant(Box::new(true))));
         }
 
-        properties.push(("MemoryAccounting", Variant(Box::new(true))));
-        properties.push(("CPUAccounting", Variant(Box::new(true))));
-        properties.push(("IOAccounting", Variant(Box::new(true))));
-        properties.push(("TasksAccounting", Variant(Box::new(true))));
+        add_accounting_properties(&mut properties);
 
         properties.push(("DefaultDependencies", Variant(Box::new(false))));
         properties.push(("PIDs", Variant(Box::new(vec![pid]))));
 
         log::debug!("Starting transient unit: {:?}", properties);
-        proxy
-            .start_transient_unit(unit_name, "replace", properties, vec![])
-            .with_context(|| {
-                format!("failed to start transient unit {unit_name}, parent is {parent}")
-            })?;
-        Ok(())
+        self.run_job(unit_name, |proxy| {
+            proxy.start_transient_unit(unit_name, "replace", properties, vec![])
+        })
+    }
+}
+
+/// Checks the result systemd reports for a finished job
+fn job_result(unit_name: &str, result: &str) -> Result<()> {
+    match result {
+        "done" => Ok(()),
+        // e.g. failed, canceled, timeout, dependency or skipped
+        result => bail!("job for unit {} finished with result {}", unit_name, result),
+    }
+}
+
+fn is_unit_exists_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<dbus::Error>().and_then(|err| err.name()) == Some(UNIT_EXISTS_ERROR)
+}
+
+/// Adds the accounting properties youki sets for all of its units
+fn add_accounting_properties(properties: &mut Vec<(&str, Variant<Box<dyn RefArg>>)>) {
+    // IOAccounting is only supported on the unified hierarchy
+    let io_accounting = match get_cgroup_setup() {
+        Ok(CgroupSetup::Legacy | CgroupSetup::Hybrid) => "BlockIOAccounting",
+        _ => "IOAccounting",
+    };
+
+    properties.push(("MemoryAccounting", Variant(Box::new(true))));
+    properties.push(("CPUAccounting", Variant(Box::new(true))));
+    properties.push((io_accounting, Variant(Box::new(true))));
+    properties.push(("TasksAccounting", Variant(Box::new(true))));
+}
+
+impl SystemdClient for Client {
+    fn is_system(&self) -> bool {
+        self.system
+    }
+
+    fn transient_unit_exists(&self, unit_name: &str) -> bool {
+        let proxy = self.create_proxy();
+        proxy.get_unit(unit_name).is_ok()
+    }
+
+    /// start_transient_unit is a higher level API for starting a unit
+    /// for a specific container under systemd.
+    /// See https://www.freedesktop.org/wiki/Software/systemd/dbus for more details.
+    fn start_transient_unit(
+        &self,
+        container_name: &str,
+        pid: u32,
+        parent: &str,
+        unit_name: &str,
+    ) -> Result<()> {
+        // To view and introspect the methods under the 'org.freedesktop.systemd1' destination
+        // and object path under it use the following command:
+        // `gdbus introspect --system --dest org.freedesktop.systemd1 --object-path /org/freedesktop/systemd1`
+        match self.try_start_transient_unit(container_name, pid, parent, unit_name) {
+            // a unit that failed is kept loaded until it is reset, e.g. if the
+            // previous container with the same name was killed by the oom killer
+            Err(err) if is_unit_exists_error(&err) => {
+                log::debug!("resetting failed unit {} and starting it again", unit_name);
+                self.create_proxy()
+                    .reset_failed_unit(unit_name)
+                    .with_context(|| format!("failed to reset unit {unit_name}"))?;
+                self.try_start_transient_unit(container_name, pid, parent, unit_name)
+            }
+            result => result,
+        }
+        .with_context(|| format!("failed to start transient unit {unit_name}, parent is {parent}"))
     }
 
     fn stop_transient_unit(&self, unit_name: &str) -> Result<()> {
+        self.run_job(unit_name, |proxy| proxy.stop_unit(unit_name, "replace"))
+            .with_context(|| format!("failed to stop unit {unit_name}"))
+    }
+
+    fn start_transient_slice(&self, slice_name: &str) -> Result<()> {
+        // The parent of a slice is given by its name, so neither Slice= nor
+        // Wants= are required
+        let mut properties: Vec<(&str, Variant<Box<dyn RefArg>>)> = Vec::with_capacity(6);
+        properties.push((
+            "Description",
+            Variant(Box::new(format!("youki slice {slice_name}"))),
+        ));
+        add_accounting_properties(&mut properties);
+        properties.push(("DefaultDependencies", Variant(Box::new(false))));
+
+        log::debug!("Starting transient slice: {:?}", properties);
+        match self.run_job(slice_name, |proxy| {
+            proxy.start_transient_unit(slice_name, "replace", properties, vec![])
+        }) {
+            // the slice has been created for another container or by the admin
+            Err(err) if is_unit_exists_error(&err) => Ok(()),
+            result => {
+                result.with_context(|| format!("failed to start transient slice {slice_name}"))
+            }
+        }
+    }
+
+    fn transient_unit_is_empty(&self, unit_name: &str) -> Result<bool> {
         let proxy = self.create_proxy();
+        let unit_path = match proxy.get_unit(unit_name) {
+            Ok(unit_path) => unit_path,
+            // the unit is not loaded
+            Err(_) => return Ok(false),
+        };
 
-        proxy
-            .stop_unit(unit_name, "replace")
-            .with_context(|| format!("failed to stop unit {unit_name}"))?;
-        Ok(())
+        let transient: bool = self
+            .create_proxy_for(unit_path)
+            .get("org.freedesktop.systemd1.Unit", "Transient")
+            .with_context(|| format!("failed to check if {unit_name} is transient"))?;
+        if !transient {
+            return Ok(false);
+        }
+
+        let processes = proxy
+            .get_unit_processes(unit_name)
+            .with_context(|| format!("failed to get processes of {unit_name}"))?;
+        Ok(processes.is_empty())
     }
 
     fn set_unit_properties(
@@ -181,3 +367,34 @@ impl SystemdClient for Client {
             .with_context(|| format!("parse systemd control cgroup {cgroup_root} into path"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_result() {
+        assert!(job_result("docker-foo.scope", "done").is_ok());
+        for result in ["failed", "canceled", "timeout", "dependency", "skipped"] {
+            assert!(
+                job_result("docker-foo.scope", result).is_err(),
+                "{result} should be an error"
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_unit_exists_error() {
+        let err = anyhow::Error::from(dbus::Error::new_custom(
+            UNIT_EXISTS_ERROR,
+            "Unit docker-foo.scope already exists.",
+        ));
+        assert!(is_unit_exists_error(&err));
+
+        let err = anyhow::Error::from(dbus::Error::new_custom(
+            "org.freedesktop.systemd1.NoSuchUnit",
+            "Unit docker-foo.scope not loaded.",
+        ));
+        assert!(!is_unit_exists_error(&err));
+    }
+}
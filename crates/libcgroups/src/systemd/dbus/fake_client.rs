@@ -0,0 +1,249 @@
+#![cfg(test)]
+
+use std::{cell::RefCell, collections::HashMap, path::PathBuf, rc::Rc};
+
+use anyhow::{bail, Result};
+use dbus::arg::RefArg;
+
+use super::client::SystemdClient;
+use crate::systemd::slice::slice_hierarchy;
+
+/// Calls to systemd recorded by [`FakeSystemdClient`]
+#[derive(Debug)]
+pub(crate) enum Call {
+    StartTransientUnit {
+        unit_name: String,
+        parent: String,
+        pid: u32,
+    },
+    StartTransientSlice(String),
+    StopUnit(String),
+    SetUnitProperties {
+        unit_name: String,
+        properties: HashMap<String, Box<dyn RefArg>>,
+    },
+}
+
+/// A unit known to the fake systemd
+#[derive(Debug, Default)]
+pub(crate) struct FakeUnit {
+    pub transient: bool,
+    /// Processes that belong directly to the unit
+    pub pids: Vec<u32>,
+    /// All slices the unit is contained in
+    pub slices: Vec<String>,
+    pub properties: HashMap<String, Box<dyn RefArg>>,
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    units: HashMap<String, FakeUnit>,
+    calls: Vec<Call>,
+}
+
+/// In-process replacement for the dbus client, which emulates the units of
+/// systemd and records all calls that modify them. Clones share their state,
+/// so a clone can be kept to inspect the client after it has been handed to
+/// a manager.
+#[derive(Debug, Clone)]
+pub(crate) struct FakeSystemdClient {
+    system: bool,
+    version: u32,
+    control_cgroup_root: PathBuf,
+    state: Rc<RefCell<FakeState>>,
+}
+
+impl Default for FakeSystemdClient {
+    fn default() -> Self {
+        Self {
+            system: true,
+            version: 245,
+            control_cgroup_root: PathBuf::from("/"),
+            state: Rc::default(),
+        }
+    }
+}
+
+impl FakeSystemdClient {
+    /// Emulates the systemd user instance of an unprivileged user
+    pub fn session(uid: u32) -> Self {
+        Self {
+            system: false,
+            control_cgroup_root: PathBuf::from(format!(
+                "/user.slice/user-{uid}.slice/user@{uid}.service"
+            )),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Adds a slice that is defined by a unit file
+    pub fn with_slice(self, slice_name: &str) -> Self {
+        self.add_unit(slice_name, false, Vec::new(), parent_slices(slice_name));
+        self
+    }
+
+    /// Returns the recorded calls and clears them
+    pub fn take_calls(&self) -> Vec<Call> {
+        std::mem::take(&mut self.state.borrow_mut().calls)
+    }
+
+    /// Names of all units, sorted
+    pub fn unit_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.state.borrow().units.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Returns the value of a property of the unit as set by the last call
+    pub fn property(&self, unit_name: &str, property: &str) -> Option<Box<dyn RefArg>> {
+        self.state
+            .borrow()
+            .units
+            .get(unit_name)?
+            .properties
+            .get(property)
+            .map(|value| value.box_clone())
+    }
+
+    /// Emulates that all processes of the unit have exited
+    pub fn exit_processes(&self, unit_name: &str) {
+        if let Some(unit) = self.state.borrow_mut().units.get_mut(unit_name) {
+            unit.pids.clear();
+        }
+    }
+
+    fn add_unit(&self, unit_name: &str, transient: bool, pids: Vec<u32>, slices: Vec<String>) {
+        self.state.borrow_mut().units.insert(
+            unit_name.to_owned(),
+            FakeUnit {
+                transient,
+                pids,
+                slices,
+                properties: HashMap::new(),
+            },
+        );
+    }
+
+    fn record(&self, call: Call) {
+        self.state.borrow_mut().calls.push(call);
+    }
+}
+
+/// Slices that contain the slice, which are given by its name
+fn parent_slices(slice_name: &str) -> Vec<String> {
+    let mut slices = slice_hierarchy(slice_name);
+    slices.pop();
+    slices
+}
+
+impl SystemdClient for FakeSystemdClient {
+    fn is_system(&self) -> bool {
+        self.system
+    }
+
+    fn transient_unit_exists(&self, unit_name: &str) -> bool {
+        self.state.borrow().units.contains_key(unit_name)
+    }
+
+    fn start_transient_unit(
+        &self,
+        _container_name: &str,
+        pid: u32,
+        parent: &str,
+        unit_name: &str,
+    ) -> Result<()> {
+        self.record(Call::StartTransientUnit {
+            unit_name: unit_name.to_owned(),
+            parent: parent.to_owned(),
+            pid,
+        });
+        if self.transient_unit_exists(unit_name) {
+            bail!("unit {} already exists", unit_name);
+        }
+
+        let slices = if unit_name.ends_with(".slice") {
+            parent_slices(unit_name)
+        } else {
+            slice_hierarchy(parent)
+        };
+        self.add_unit(unit_name, true, vec![pid], slices);
+        Ok(())
+    }
+
+    fn stop_transient_unit(&self, unit_name: &str) -> Result<()> {
+        self.record(Call::StopUnit(unit_name.to_owned()));
+        let mut state = self.state.borrow_mut();
+        if state.units.remove(unit_name).is_none() {
+            bail!("unit {} not loaded", unit_name);
+        }
+        // stopping a slice stops all units it contains
+        state
+            .units
+            .retain(|_, unit| !unit.slices.iter().any(|slice| slice == unit_name));
+
+        Ok(())
+    }
+
+    fn start_transient_slice(&self, slice_name: &str) -> Result<()> {
+        self.record(Call::StartTransientSlice(slice_name.to_owned()));
+        if !self.transient_unit_exists(slice_name) {
+            self.add_unit(slice_name, true, Vec::new(), parent_slices(slice_name));
+        }
+
+        Ok(())
+    }
+
+    fn transient_unit_is_empty(&self, unit_name: &str) -> Result<bool> {
+        let state = self.state.borrow();
+        match state.units.get(unit_name) {
+            Some(unit) if unit.transient => {
+                let empty = unit.pids.is_empty()
+                    && state
+                        .units
+                        .values()
+                        .filter(|u| u.slices.iter().any(|slice| slice == unit_name))
+                        .all(|u| u.pids.is_empty());
+                Ok(empty)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn set_unit_properties(
+        &self,
+        unit_name: &str,
+        properties: &HashMap<&str, Box<dyn RefArg>>,
+    ) -> Result<()> {
+        let properties: HashMap<String, Box<dyn RefArg>> = properties
+            .iter()
+            .map(|(property, value)| (property.to_string(), value.box_clone()))
+            .collect();
+        self.record(Call::SetUnitProperties {
+            unit_name: unit_name.to_owned(),
+            properties: properties
+                .iter()
+                .map(|(property, value)| (property.clone(), value.box_clone()))
+                .collect(),
+        });
+
+        match self.state.borrow_mut().units.get_mut(unit_name) {
+            Some(unit) => unit.properties.extend(properties),
+            None => bail!("unit {} not loaded", unit_name),
+        }
+
+        Ok(())
+    }
+
+    fn systemd_version(&self) -> Result<u32> {
+        Ok(self.version)
+    }
+
+    fn control_cgroup_root(&self) -> Result<PathBuf> {
+        Ok(self.control_cgroup_root.clone())
+    }
+}
@@ -1,2 +1,3 @@
 pub(crate) mod client;
+pub(crate) mod fake_client;
 pub(crate) mod systemd_api;
@@ -0,0 +1,374 @@
+use std::{collections::HashMap, fmt, fs};
+
+use anyhow::{bail, Context, Result};
+use dbus::arg::RefArg;
+use oci_spec::runtime::{LinuxDeviceCgroup, LinuxDeviceType};
+
+use crate::common::{default_allow_devices, default_devices, ControllerOpt};
+
+use super::controller::Controller;
+
+pub const DEVICE_POLICY: &str = "DevicePolicy";
+pub const DEVICE_ALLOW: &str = "DeviceAllow";
+
+const PROC_DEVICES: &str = "/proc/devices";
+
+pub struct Devices {}
+
+impl Controller for Devices {
+    fn apply(
+        options: &ControllerOpt,
+        _: u32,
+        properties: &mut HashMap<&str, Box<dyn RefArg>>,
+    ) -> Result<()> {
+        // Without device rules the current restrictions are kept, e.g. when
+        // the resources of a running container are updated
+        if let Some(devices) = options.resources.devices() {
+            log::debug!("applying device resource restrictions");
+            let proc_devices = fs::read_to_string(PROC_DEVICES)
+                .with_context(|| format!("failed to read {PROC_DEVICES}"))?;
+            return Self::apply(devices, &proc_devices, properties)
+                .context("could not apply device resource restrictions");
+        }
+
+        Ok(())
+    }
+}
+
+impl Devices {
+    // systemd only supports allow lists, so the rules are evaluated here in the
+    // same way as for the cgroupfs drivers: rules of type 'a' switch to allow
+    // or deny all devices, later rules take precedence over earlier ones. The
+    // default devices are always allowed.
+    fn apply(
+        devices: &[LinuxDeviceCgroup],
+        proc_devices: &str,
+        properties: &mut HashMap<&str, Box<dyn RefArg>>,
+    ) -> Result<()> {
+        let rules: Vec<LinuxDeviceCgroup> = devices
+            .iter()
+            .cloned()
+            .chain(default_devices().iter().map(|d| d.into()))
+            .chain(default_allow_devices())
+            .collect();
+
+        let mut allow_all = false;
+        let mut allowed: Vec<(DeviceMatch, String, String)> = Vec::new();
+        for rule in &rules {
+            if rule.typ().unwrap_or_default() == LinuxDeviceType::A {
+                allow_all = rule.allow();
+                allowed.clear();
+                continue;
+            }
+
+            let access = match rule.access() {
+                Some(access) if !access.is_empty() => access,
+                // empty access matches nothing
+                _ => continue,
+            };
+            let device = DeviceMatch::new(rule)?;
+            if rule.allow() {
+                if allow_all {
+                    continue;
+                }
+                match device_name(&device, proc_devices) {
+                    Some(name) => allowed.push((device, name, access.to_owned())),
+                    None => log::warn!("skipping rule for unknown device {:?}", rule),
+                }
+            } else {
+                if allow_all {
+                    bail!(
+                        "systemd cannot deny access to {} while all devices are allowed",
+                        device
+                    );
+                }
+                // The denied permissions can only be removed from entries that are
+                // denied as a whole. Denying part of the devices of an entry would
+                // require to split it up, which DeviceAllow cannot express.
+                for (allowed_device, _, permissions) in allowed.iter_mut() {
+                    if !permissions.chars().any(|p| access.contains(p)) {
+                        continue;
+                    }
+                    if device.covers(allowed_device) {
+                        permissions.retain(|p| !access.contains(p));
+                    } else if device.overlaps(allowed_device) {
+                        bail!(
+                            "systemd cannot deny access to {} while {} is allowed",
+                            device,
+                            allowed_device
+                        );
+                    }
+                }
+                allowed.retain(|(_, _, permissions)| !permissions.is_empty());
+            }
+        }
+
+        if allow_all {
+            properties.insert(DEVICE_POLICY, Box::new("auto".to_owned()));
+            properties.insert(DEVICE_ALLOW, Box::<Vec<(String, String)>>::default());
+        } else {
+            let allowed: Vec<(String, String)> = allowed
+                .into_iter()
+                .map(|(_, name, permissions)| (name, permissions))
+                .collect();
+            properties.insert(DEVICE_POLICY, Box::new("strict".to_owned()));
+            properties.insert(DEVICE_ALLOW, Box::new(allowed));
+        }
+
+        Ok(())
+    }
+}
+
+/// The devices a rule applies to, a major or minor number of None matches any
+/// device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DeviceMatch {
+    kind: &'static str,
+    major: Option<i64>,
+    minor: Option<i64>,
+}
+
+impl DeviceMatch {
+    fn new(rule: &LinuxDeviceCgroup) -> Result<Self> {
+        let kind = match rule.typ().unwrap_or_default() {
+            LinuxDeviceType::C | LinuxDeviceType::U => "char",
+            LinuxDeviceType::B => "block",
+            typ => bail!("device type {:?} is not supported by systemd", typ),
+        };
+
+        Ok(Self {
+            kind,
+            major: rule.major().filter(|m| *m >= 0),
+            minor: rule.minor().filter(|m| *m >= 0),
+        })
+    }
+
+    /// Returns true if every device matched by other is matched by self as well
+    fn covers(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && (self.major.is_none() || self.major == other.major)
+            && (self.minor.is_none() || self.minor == other.minor)
+    }
+
+    /// Returns true if there is a device matched by both self and other
+    fn overlaps(&self, other: &Self) -> bool {
+        let matches = |a: Option<i64>, b: Option<i64>| a.is_none() || b.is_none() || a == b;
+        self.kind == other.kind
+            && matches(self.major, other.major)
+            && matches(self.minor, other.minor)
+    }
+}
+
+impl fmt::Display for DeviceMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let number = |n: Option<i64>| n.map_or_else(|| "*".to_owned(), |n| n.to_string());
+        write!(
+            f,
+            "{} {}:{}",
+            self.kind,
+            number(self.major),
+            number(self.minor)
+        )
+    }
+}
+
+/// Returns the name under which systemd knows the matched devices, see
+/// DeviceAllow= in systemd.resource-control(5). Devices with a wildcard minor
+/// number are referred to by the name of their group in /proc/devices, None is
+/// returned if there is no such group.
+fn device_name(device: &DeviceMatch, proc_devices: &str) -> Option<String> {
+    let kind = device.kind;
+    match (device.major, device.minor) {
+        (None, _) => Some(format!("{kind}-*")),
+        (Some(major), Some(minor)) => Some(format!("/dev/{kind}/{major}:{minor}")),
+        (Some(major), None) => {
+            device_group(proc_devices, kind, major).map(|group| format!("{kind}-{group}"))
+        }
+    }
+}
+
+/// Looks up the name of the group of the devices with the major number in the
+/// content of /proc/devices
+fn device_group(proc_devices: &str, kind: &str, major: i64) -> Option<String> {
+    let section = match kind {
+        "char" => "Character devices:",
+        _ => "Block devices:",
+    };
+
+    proc_devices
+        .lines()
+        .skip_while(|line| line.trim() != section)
+        .skip(1)
+        .take_while(|line| !line.trim().is_empty())
+        .filter_map(|line| line.trim().split_once(char::is_whitespace))
+        .find(|(number, _)| number.parse::<i64>().ok() == Some(major))
+        .map(|(_, group)| group.trim().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use oci_spec::runtime::LinuxDeviceCgroupBuilder;
+
+    use super::*;
+
+    const PROC_DEVICES_FIXTURE: &str = "Character devices:
+  1 mem
+  4 /dev/vc/0
+  4 tty
+136 pts
+226 drm
+
+Block devices:
+  8 sd
+259 blkext
+";
+
+    fn rule(
+        allow: bool,
+        typ: LinuxDeviceType,
+        major: Option<i64>,
+        minor: Option<i64>,
+        access: &str,
+    ) -> LinuxDeviceCgroup {
+        let mut builder = LinuxDeviceCgroupBuilder::default()
+            .allow(allow)
+            .typ(typ)
+            .access(access);
+        if let Some(major) = major {
+            builder = builder.major(major);
+        }
+        if let Some(minor) = minor {
+            builder = builder.minor(minor);
+        }
+        builder.build().unwrap()
+    }
+
+    fn apply(devices: &[LinuxDeviceCgroup]) -> Result<HashMap<&'static str, Box<dyn RefArg>>> {
+        let mut properties = HashMap::new();
+        Devices::apply(devices, PROC_DEVICES_FIXTURE, &mut properties)?;
+        Ok(properties)
+    }
+
+    fn allowed<'a>(properties: &'a HashMap<&str, Box<dyn RefArg>>) -> &'a Vec<(String, String)> {
+        (*properties[DEVICE_ALLOW])
+            .as_any()
+            .downcast_ref()
+            .expect("DeviceAllow is a list of devices")
+    }
+
+    #[test]
+    fn test_device_name() -> Result<()> {
+        let name = |rule: &LinuxDeviceCgroup| -> Result<Option<String>> {
+            Ok(device_name(&DeviceMatch::new(rule)?, PROC_DEVICES_FIXTURE))
+        };
+
+        assert_eq!(
+            name(&rule(true, LinuxDeviceType::C, Some(1), Some(3), "rwm"))?,
+            Some("/dev/char/1:3".to_owned())
+        );
+        assert_eq!(
+            name(&rule(true, LinuxDeviceType::B, Some(8), Some(0), "r"))?,
+            Some("/dev/block/8:0".to_owned())
+        );
+        assert_eq!(
+            name(&rule(true, LinuxDeviceType::C, Some(136), None, "rwm"))?,
+            Some("char-pts".to_owned())
+        );
+        assert_eq!(
+            name(&rule(true, LinuxDeviceType::B, Some(259), Some(-1), "r"))?,
+            Some("block-blkext".to_owned())
+        );
+        assert_eq!(
+            name(&rule(true, LinuxDeviceType::C, None, None, "m"))?,
+            Some("char-*".to_owned())
+        );
+        assert_eq!(
+            name(&rule(true, LinuxDeviceType::C, Some(8), None, "m"))?,
+            None
+        );
+        assert!(name(&rule(true, LinuxDeviceType::P, Some(1), Some(3), "m")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deny_all_allows_defaults() -> Result<()> {
+        let properties = apply(&[
+            rule(false, LinuxDeviceType::A, None, None, "rwm"),
+            rule(true, LinuxDeviceType::C, Some(226), None, "rw"),
+        ])?;
+
+        let policy = &properties[DEVICE_POLICY];
+        assert_eq!(policy.as_str(), Some("strict"));
+
+        let allowed = allowed(&properties);
+        assert!(allowed.contains(&("char-drm".to_owned(), "rw".to_owned())));
+        // /dev/null
+        assert!(allowed.contains(&("/dev/char/1:3".to_owned(), "rwm".to_owned())));
+        assert!(allowed.contains(&("char-pts".to_owned(), "rwm".to_owned())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deny_removes_permissions() -> Result<()> {
+        let properties = apply(&[
+            rule(false, LinuxDeviceType::A, None, None, "rwm"),
+            rule(true, LinuxDeviceType::B, Some(8), Some(0), "rwm"),
+            rule(true, LinuxDeviceType::B, Some(8), Some(16), "rw"),
+            rule(false, LinuxDeviceType::B, Some(8), Some(0), "w"),
+            rule(false, LinuxDeviceType::B, Some(8), Some(16), "rw"),
+        ])?;
+
+        let allowed = allowed(&properties);
+        assert!(allowed.contains(&("/dev/block/8:0".to_owned(), "rm".to_owned())));
+        assert!(!allowed.iter().any(|(name, _)| name == "/dev/block/8:16"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deny_part_of_allowed_devices() -> Result<()> {
+        // a deny covering an allowed group removes the permissions from it
+        let properties = apply(&[
+            rule(false, LinuxDeviceType::A, None, None, "rwm"),
+            rule(true, LinuxDeviceType::C, Some(226), None, "rw"),
+            rule(false, LinuxDeviceType::C, None, None, "w"),
+        ])?;
+        assert!(allowed(&properties).contains(&("char-drm".to_owned(), "r".to_owned())));
+
+        // a deny of a single device of an allowed group cannot be expressed
+        let result = apply(&[
+            rule(false, LinuxDeviceType::A, None, None, "rwm"),
+            rule(true, LinuxDeviceType::C, Some(226), None, "rw"),
+            rule(false, LinuxDeviceType::C, Some(226), Some(0), "w"),
+        ]);
+        assert!(result.is_err());
+
+        // unless it denies permissions that are not allowed anyway
+        let result = apply(&[
+            rule(false, LinuxDeviceType::A, None, None, "rwm"),
+            rule(true, LinuxDeviceType::C, Some(226), None, "rw"),
+            rule(false, LinuxDeviceType::C, Some(226), Some(0), "m"),
+        ]);
+        assert!(result.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_allow_all() -> Result<()> {
+        let properties = apply(&[rule(true, LinuxDeviceType::A, None, None, "rwm")])?;
+
+        assert_eq!(properties[DEVICE_POLICY].as_str(), Some("auto"));
+        assert!(allowed(&properties).is_empty());
+
+        let result = apply(&[
+            rule(true, LinuxDeviceType::A, None, None, "rwm"),
+            rule(false, LinuxDeviceType::B, Some(8), Some(0), "w"),
+        ]);
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
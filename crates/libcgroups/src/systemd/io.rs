@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use dbus::arg::RefArg;
+use oci_spec::runtime::{LinuxBlockIo, LinuxThrottleDevice};
+
+use crate::common::ControllerOpt;
+
+use super::controller::Controller;
+
+pub const IO_WEIGHT: &str = "IOWeight";
+pub const IO_DEVICE_WEIGHT: &str = "IODeviceWeight";
+pub const IO_READ_BANDWIDTH_MAX: &str = "IOReadBandwidthMax";
+pub const IO_WRITE_BANDWIDTH_MAX: &str = "IOWriteBandwidthMax";
+pub const IO_READ_IOPS_MAX: &str = "IOReadIOPSMax";
+pub const IO_WRITE_IOPS_MAX: &str = "IOWriteIOPSMax";
+
+pub struct Io {}
+
+impl Controller for Io {
+    fn apply(
+        options: &ControllerOpt,
+        _: u32,
+        properties: &mut HashMap<&str, Box<dyn RefArg>>,
+    ) -> Result<()> {
+        if let Some(block_io) = options.resources.block_io() {
+            log::debug!("applying io resource restrictions");
+            return Self::apply(block_io, properties)
+                .context("could not apply io resource restrictions");
+        }
+
+        Ok(())
+    }
+}
+
+impl Io {
+    fn apply(
+        block_io: &LinuxBlockIo,
+        properties: &mut HashMap<&str, Box<dyn RefArg>>,
+    ) -> Result<()> {
+        if let Some(leaf_weight) = block_io.leaf_weight() {
+            if leaf_weight > 0 {
+                bail!("cannot set leaf_weight with cgroupv2");
+            }
+        }
+
+        if let Some(weight) = block_io.weight() {
+            if weight > 0 {
+                properties.insert(IO_WEIGHT, Box::new(convert_blkio_weight_to_io(weight)));
+            }
+        }
+
+        if let Some(weight_device) = block_io.weight_device() {
+            // devices without a weight only have a leaf weight, which is not
+            // supported on cgroup v2
+            let weights: Vec<(String, u64)> = weight_device
+                .iter()
+                .filter_map(|device| {
+                    device.weight().map(|weight| {
+                        (
+                            block_device_path(device.major(), device.minor()),
+                            convert_blkio_weight_to_io(weight),
+                        )
+                    })
+                })
+                .collect();
+            if !weights.is_empty() {
+                properties.insert(IO_DEVICE_WEIGHT, Box::new(weights));
+            }
+        }
+
+        for (property, devices) in [
+            (IO_READ_BANDWIDTH_MAX, block_io.throttle_read_bps_device()),
+            (IO_WRITE_BANDWIDTH_MAX, block_io.throttle_write_bps_device()),
+            (IO_READ_IOPS_MAX, block_io.throttle_read_iops_device()),
+            (IO_WRITE_IOPS_MAX, block_io.throttle_write_iops_device()),
+        ] {
+            if let Some(devices) = devices {
+                properties.insert(property, Box::new(throttle_limits(devices)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// systemd identifies block devices by their path. The symlinks under
+/// /dev/block are created by udev for every block device.
+pub(super) fn block_device_path(major: i64, minor: i64) -> String {
+    format!("/dev/block/{major}:{minor}")
+}
+
+pub(super) fn throttle_limits(devices: &[LinuxThrottleDevice]) -> Vec<(String, u64)> {
+    devices
+        .iter()
+        .map(|device| {
+            (
+                block_device_path(device.major(), device.minor()),
+                device.rate(),
+            )
+        })
+        .collect()
+}
+
+// The runtime spec defines the weight in the range of the blkio controller of
+// cgroup v1 [10-1000], while systemd expects it in the range of the io
+// controller of cgroup v2 [1-10000], so it is converted linearly.
+fn convert_blkio_weight_to_io(weight: u16) -> u64 {
+    let weight = u64::from(weight.clamp(10, 1000));
+    1 + (weight - 10) * 9999 / 990
+}
+
+#[cfg(test)]
+mod tests {
+    use dbus::arg::ArgType;
+    use oci_spec::runtime::{
+        LinuxBlockIoBuilder, LinuxThrottleDeviceBuilder, LinuxWeightDeviceBuilder,
+    };
+
+    use super::*;
+
+    fn device_limits<'a>(
+        properties: &'a HashMap<&str, Box<dyn RefArg>>,
+        property: &str,
+    ) -> &'a Vec<(String, u64)> {
+        (*properties[property])
+            .as_any()
+            .downcast_ref()
+            .expect("property is a list of device limits")
+    }
+
+    #[test]
+    fn test_convert_blkio_weight_to_io() {
+        assert_eq!(convert_blkio_weight_to_io(10), 1);
+        assert_eq!(convert_blkio_weight_to_io(500), 4950);
+        assert_eq!(convert_blkio_weight_to_io(1000), 10000);
+        assert_eq!(convert_blkio_weight_to_io(5000), 10000);
+    }
+
+    #[test]
+    fn test_set_io_weight() -> Result<()> {
+        let block_io = LinuxBlockIoBuilder::default()
+            .weight(1000u16)
+            .weight_device(vec![
+                LinuxWeightDeviceBuilder::default()
+                    .major(8)
+                    .minor(0)
+                    .weight(10u16)
+                    .build()?,
+                LinuxWeightDeviceBuilder::default()
+                    .major(8)
+                    .minor(16)
+                    .build()?,
+            ])
+            .build()?;
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+        Io::apply(&block_io, &mut properties).context("apply io")?;
+
+        assert_eq!(properties.len(), 2);
+        let weight = &properties[IO_WEIGHT];
+        assert_eq!(weight.arg_type(), ArgType::UInt64);
+        assert_eq!(weight.as_u64(), Some(10000));
+        assert_eq!(&*properties[IO_DEVICE_WEIGHT].signature(), "a(st)");
+        assert_eq!(
+            device_limits(&properties, IO_DEVICE_WEIGHT),
+            &vec![("/dev/block/8:0".to_owned(), 1)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_io_throttle() -> Result<()> {
+        let device = |rate: u64| {
+            LinuxThrottleDeviceBuilder::default()
+                .major(8)
+                .minor(0)
+                .rate(rate)
+                .build()
+        };
+        let block_io = LinuxBlockIoBuilder::default()
+            .throttle_read_bps_device(vec![device(1024)?])
+            .throttle_write_bps_device(vec![device(2048)?])
+            .throttle_read_iops_device(vec![device(100)?])
+            .throttle_write_iops_device(vec![device(200)?])
+            .build()?;
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+        Io::apply(&block_io, &mut properties).context("apply io")?;
+
+        assert_eq!(properties.len(), 4);
+        for (property, rate) in [
+            (IO_READ_BANDWIDTH_MAX, 1024),
+            (IO_WRITE_BANDWIDTH_MAX, 2048),
+            (IO_READ_IOPS_MAX, 100),
+            (IO_WRITE_IOPS_MAX, 200),
+        ] {
+            assert_eq!(
+                device_limits(&properties, property),
+                &vec![("/dev/block/8:0".to_owned(), rate)]
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_leaf_weight_is_rejected() -> Result<()> {
+        let block_io = LinuxBlockIoBuilder::default().leaf_weight(100u16).build()?;
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+        assert!(Io::apply(&block_io, &mut properties).is_err());
+        Ok(())
+    }
+}
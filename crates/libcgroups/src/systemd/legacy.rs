@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use dbus::arg::RefArg;
+use oci_spec::runtime::{LinuxBlockIo, LinuxCpu, LinuxMemory};
+
+use crate::common::ControllerOpt;
+
+use super::{
+    controller::Controller,
+    cpu::Cpu,
+    io::{block_device_path, throttle_limits},
+};
+
+pub const CPU_SHARES: &str = "CPUShares";
+pub const MEMORY_LIMIT: &str = "MemoryLimit";
+pub const BLOCK_IO_WEIGHT: &str = "BlockIOWeight";
+pub const BLOCK_IO_DEVICE_WEIGHT: &str = "BlockIODeviceWeight";
+pub const BLOCK_IO_READ_BANDWIDTH: &str = "BlockIOReadBandwidth";
+pub const BLOCK_IO_WRITE_BANDWIDTH: &str = "BlockIOWriteBandwidth";
+
+/// Translates the cpu, memory and blkio restrictions into the unit properties
+/// that systemd supports on cgroup v1 hierarchies. Everything that has no
+/// property, e.g. swap or iops limits, is written to the cgroups of the unit
+/// by the cgroup v1 controllers instead.
+pub struct Legacy {}
+
+impl Controller for Legacy {
+    fn apply(
+        options: &ControllerOpt,
+        _: u32,
+        properties: &mut HashMap<&str, Box<dyn RefArg>>,
+    ) -> Result<()> {
+        if let Some(unified) = options.resources.unified() {
+            if !unified.is_empty() {
+                bail!("unified resource restrictions require cgroup v2");
+            }
+        }
+
+        if let Some(cpu) = options.resources.cpu() {
+            log::debug!("applying cpu resource restrictions");
+            Self::apply_cpu(cpu, properties);
+        }
+
+        if let Some(memory) = options.resources.memory() {
+            log::debug!("applying memory resource restrictions");
+            Self::apply_memory(memory, properties)
+                .context("could not apply memory resource restrictions")?;
+        }
+
+        if let Some(block_io) = options.resources.block_io() {
+            log::debug!("applying blkio resource restrictions");
+            Self::apply_block_io(block_io, properties);
+        }
+
+        Ok(())
+    }
+}
+
+impl Legacy {
+    fn apply_cpu(cpu: &LinuxCpu, properties: &mut HashMap<&str, Box<dyn RefArg>>) {
+        if let Some(shares) = cpu.shares() {
+            if shares != 0 {
+                properties.insert(CPU_SHARES, Box::new(shares));
+            }
+        }
+
+        Cpu::apply_quota(cpu, properties);
+    }
+
+    fn apply_memory(
+        memory: &LinuxMemory,
+        properties: &mut HashMap<&str, Box<dyn RefArg>>,
+    ) -> Result<()> {
+        if let Some(limit) = memory.limit() {
+            match limit {
+                1..=i64::MAX => {
+                    properties.insert(MEMORY_LIMIT, Box::new(limit as u64));
+                }
+                -1 => {
+                    properties.insert(MEMORY_LIMIT, Box::new(u64::MAX));
+                }
+                _ => bail!("invalid memory limit value: {}", limit),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_block_io(block_io: &LinuxBlockIo, properties: &mut HashMap<&str, Box<dyn RefArg>>) {
+        // the weights of cgroup v1 use the same range as the runtime spec
+        if let Some(weight) = block_io.weight() {
+            if weight > 0 {
+                properties.insert(BLOCK_IO_WEIGHT, Box::new(u64::from(weight)));
+            }
+        }
+
+        if let Some(weight_device) = block_io.weight_device() {
+            let weights: Vec<(String, u64)> = weight_device
+                .iter()
+                .filter_map(|device| {
+                    device.weight().map(|weight| {
+                        (
+                            block_device_path(device.major(), device.minor()),
+                            u64::from(weight),
+                        )
+                    })
+                })
+                .collect();
+            if !weights.is_empty() {
+                properties.insert(BLOCK_IO_DEVICE_WEIGHT, Box::new(weights));
+            }
+        }
+
+        for (property, devices) in [
+            (BLOCK_IO_READ_BANDWIDTH, block_io.throttle_read_bps_device()),
+            (
+                BLOCK_IO_WRITE_BANDWIDTH,
+                block_io.throttle_write_bps_device(),
+            ),
+        ] {
+            if let Some(devices) = devices {
+                properties.insert(property, Box::new(throttle_limits(devices)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dbus::arg::ArgType;
+    use oci_spec::runtime::{
+        LinuxBlockIoBuilder, LinuxCpuBuilder, LinuxMemoryBuilder, LinuxResourcesBuilder,
+        LinuxThrottleDeviceBuilder, LinuxWeightDeviceBuilder,
+    };
+
+    use super::*;
+    use crate::systemd::cpu::{CPU_PERIOD, CPU_QUOTA, CPU_WEIGHT};
+
+    #[test]
+    fn test_set_cpu() -> Result<()> {
+        let cpu = LinuxCpuBuilder::default()
+            .shares(1024u64)
+            .quota(50_000i64)
+            .build()?;
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+        Legacy::apply_cpu(&cpu, &mut properties);
+
+        // the shares are passed on without converting them to a weight
+        assert!(!properties.contains_key(CPU_WEIGHT));
+        let shares = &properties[CPU_SHARES];
+        assert_eq!(shares.arg_type(), ArgType::UInt64);
+        assert_eq!(shares.as_u64(), Some(1024));
+        assert_eq!(properties[CPU_QUOTA].as_u64(), Some(500_000));
+        assert_eq!(properties[CPU_PERIOD].as_u64(), Some(100_000));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_memory_limit() -> Result<()> {
+        for (limit, expected) in [(536870912, 536870912u64), (-1, u64::MAX)] {
+            let memory = LinuxMemoryBuilder::default()
+                .limit(limit)
+                .swap(limit)
+                .build()?;
+            let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+            Legacy::apply_memory(&memory, &mut properties).context("apply memory")?;
+
+            // swap has no property on cgroup v1
+            assert_eq!(properties.len(), 1);
+            assert_eq!(properties[MEMORY_LIMIT].as_u64(), Some(expected));
+        }
+
+        let memory = LinuxMemoryBuilder::default().limit(-2).build()?;
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+        assert!(Legacy::apply_memory(&memory, &mut properties).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_block_io() -> Result<()> {
+        let block_io = LinuxBlockIoBuilder::default()
+            .weight(500u16)
+            .weight_device(vec![LinuxWeightDeviceBuilder::default()
+                .major(8)
+                .minor(0)
+                .weight(100u16)
+                .build()?])
+            .throttle_read_bps_device(vec![LinuxThrottleDeviceBuilder::default()
+                .major(8)
+                .minor(0)
+                .rate(1024u64)
+                .build()?])
+            .throttle_read_iops_device(vec![LinuxThrottleDeviceBuilder::default()
+                .major(8)
+                .minor(0)
+                .rate(100u64)
+                .build()?])
+            .build()?;
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+        Legacy::apply_block_io(&block_io, &mut properties);
+
+        // iops limits have no property on cgroup v1
+        assert_eq!(properties.len(), 3);
+        assert_eq!(properties[BLOCK_IO_WEIGHT].as_u64(), Some(500));
+        for (property, value) in [
+            (BLOCK_IO_DEVICE_WEIGHT, 100),
+            (BLOCK_IO_READ_BANDWIDTH, 1024),
+        ] {
+            let devices: &Vec<(String, u64)> = (*properties[property])
+                .as_any()
+                .downcast_ref()
+                .expect("property is a list of device limits");
+            assert_eq!(devices, &vec![("/dev/block/8:0".to_owned(), value)]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unified_is_rejected() -> Result<()> {
+        let resources = LinuxResourcesBuilder::default()
+            .unified(HashMap::from([(
+                "memory.high".to_owned(),
+                "1024".to_owned(),
+            )]))
+            .build()?;
+        let options = ControllerOpt {
+            resources: &resources,
+            disable_oom_killer: false,
+            oom_score_adj: None,
+            freezer_state: None,
+        };
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+        assert!(<Legacy as Controller>::apply(&options, 245, &mut properties).is_err());
+        Ok(())
+    }
+}
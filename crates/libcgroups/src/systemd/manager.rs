@@ -16,18 +16,44 @@ use super::{
     cpu::Cpu,
     cpuset::CpuSet,
     dbus::client::{Client, SystemdClient},
+    devices::Devices,
+    io::Io,
+    legacy::Legacy,
     memory::Memory,
     pids::Pids,
+    slice::{self, SliceProperties},
 };
 use crate::{
-    common::{self, CgroupManager, ControllerOpt, FreezerState, PathBufExt},
+    common::{
+        self, CgroupError, CgroupManager, CgroupSetup, ControllerOpt, FreezerState, PathBufExt,
+    },
     systemd::unified::Unified,
 };
-use crate::{stats::Stats, v2::manager::Manager as FsManager};
+use crate::{
+    events::EventListener,
+    stats::Stats,
+    v1::{manager::Manager as V1Manager, ControllerType as V1ControllerType},
+    v2::{controller::Controller as FsController, hugetlb::HugeTlb, manager::Manager as V2Manager},
+};
 
 const CGROUP_CONTROLLERS: &str = "cgroup.controllers";
 const CGROUP_SUBTREE_CONTROL: &str = "cgroup.subtree_control";
 
+/// Controllers whose restrictions are written to the cgroup v1 hierarchies
+/// directly, because systemd either does not manage them on cgroup v1 or
+/// only supports some of their settings as unit properties
+const LEGACY_FS_CONTROLLERS: &[V1ControllerType] = &[
+    V1ControllerType::Cpu,
+    V1ControllerType::CpuSet,
+    V1ControllerType::Blkio,
+    V1ControllerType::Freezer,
+    V1ControllerType::HugeTlb,
+    V1ControllerType::Memory,
+    V1ControllerType::NetworkClassifier,
+    V1ControllerType::NetworkPriority,
+    V1ControllerType::Rdma,
+];
+
 pub struct Manager {
     /// Root path of the cgroup hierarchy e.g. /sys/fs/cgroup
     root_path: PathBuf,
@@ -43,11 +69,30 @@ pub struct Manager {
     /// Name of the systemd unit e.g. youki-569d5ce3afe1074769f67.scope
     unit_name: String,
     /// Client for communicating with systemd
-    client: Client,
-    /// Cgroup manager for the created transient unit
+    client: Box<dyn SystemdClient>,
+    /// Cgroup manager for the cgroups of the created transient unit
     fs_manager: FsManager,
     /// Last control group which is managed by systemd, e.g. /user.slice/user-1000/user@1000.service
     delegation_boundary: PathBuf,
+    /// Resource properties of the parent slices, set by annotations of the container
+    slice_properties: SliceProperties,
+}
+
+/// Manages the cgroups of the transient unit through the cgroup filesystem.
+/// On cgroup v2 the unit has a single cgroup, on cgroup v1 systemd creates a
+/// cgroup with the same path in the hierarchy of every controller it manages.
+enum FsManager {
+    Legacy(V1Manager),
+    Unified(V2Manager),
+}
+
+impl FsManager {
+    fn manager(&self) -> &dyn CgroupManager {
+        match self {
+            FsManager::Legacy(manager) => manager,
+            FsManager::Unified(manager) => manager,
+        }
+    }
 }
 
 /// Represents the systemd cgroups path:
@@ -122,6 +167,7 @@ impl Debug for Manager {
             .field("destructured_path", &self.destructured_path)
             .field("container_name", &self.container_name)
             .field("unit_name", &self.unit_name)
+            .field("slice_properties", &self.slice_properties)
             .finish()
     }
 }
@@ -132,23 +178,66 @@ impl Manager {
         cgroups_path: PathBuf,
         container_name: String,
         use_system: bool,
+        cgroup_setup: CgroupSetup,
+        annotations: &HashMap<String, String>,
     ) -> Result<Self> {
-        let mut destructured_path = cgroups_path
-            .as_path()
-            .try_into()
-            .with_context(|| format!("failed to destructure cgroups path {cgroups_path:?}"))?;
-        ensure_parent_unit(&mut destructured_path, use_system);
-
         let client = match use_system {
             true => Client::new_system().context("failed to create system dbus client")?,
             false => Client::new_session().context("failed to create session dbus client")?,
         };
 
+        Self::with_client(
+            root_path,
+            cgroups_path,
+            container_name,
+            cgroup_setup,
+            annotations,
+            Box::new(client),
+        )
+    }
+
+    /// Creates a manager that communicates with systemd through the client,
+    /// which allows to replace systemd in tests
+    pub(crate) fn with_client(
+        root_path: PathBuf,
+        cgroups_path: PathBuf,
+        container_name: String,
+        cgroup_setup: CgroupSetup,
+        annotations: &HashMap<String, String>,
+        client: Box<dyn SystemdClient>,
+    ) -> Result<Self> {
+        let mut destructured_path: CgroupsPath = cgroups_path
+            .as_path()
+            .try_into()
+            .with_context(|| format!("failed to destructure cgroups path {cgroups_path:?}"))?;
+        ensure_parent_unit(&mut destructured_path, client.is_system());
+
+        let slice_properties = slice::parse_slice_properties(annotations)
+            .context("failed to parse slice properties")?;
+        let parent_slices = slice::slice_hierarchy(&destructured_path.parent);
+        if let Some(unknown) = slice_properties
+            .keys()
+            .find(|name| !parent_slices.contains(name))
+        {
+            bail!(
+                "properties are set for slice {}, which is not a parent of {}",
+                unknown,
+                destructured_path
+            );
+        }
+
         let (cgroups_path, delegation_boundary) =
-            Self::construct_cgroups_path(&destructured_path, &client)
+            Self::construct_cgroups_path(&destructured_path, client.as_ref())
                 .context("failed to construct cgroups path")?;
         let full_path = root_path.join_safely(&cgroups_path)?;
-        let fs_manager = FsManager::new(root_path.clone(), cgroups_path.clone())?;
+        let fs_manager = match cgroup_setup {
+            CgroupSetup::Unified => {
+                FsManager::Unified(V2Manager::new(root_path.clone(), cgroups_path.clone())?)
+            }
+            CgroupSetup::Legacy | CgroupSetup::Hybrid => {
+                FsManager::Legacy(V1Manager::new(cgroups_path.clone())?)
+            }
+        };
 
         Ok(Manager {
             root_path,
@@ -160,6 +249,7 @@ impl Manager {
             client,
             fs_manager,
             delegation_boundary,
+            slice_properties,
         })
     }
 
@@ -224,6 +314,51 @@ impl Manager {
         Ok(Path::new(&path).to_path_buf())
     }
 
+    /// Creates the parent slices of the unit that do not exist yet as transient
+    /// units, so that they can be removed again once they are empty, and sets
+    /// the properties of the slices
+    fn ensure_parent_slices(&self) -> Result<()> {
+        for slice in slice::slice_hierarchy(&self.destructured_path.parent) {
+            self.client
+                .start_transient_slice(&slice)
+                .with_context(|| format!("failed to create slice {slice}"))?;
+
+            if let Some(slice_properties) = self.slice_properties.get(&slice) {
+                let properties: HashMap<&str, Box<dyn RefArg>> = slice_properties
+                    .iter()
+                    .map(|(property, value)| (*property, Box::new(*value) as Box<dyn RefArg>))
+                    .collect();
+                log::debug!("setting properties of {}: {:?}", slice, properties);
+                self.client
+                    .set_unit_properties(&slice, &properties)
+                    .with_context(|| format!("failed to set properties of slice {slice}"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the parent slices that have been created for containers and
+    /// are empty now, starting with the innermost one
+    fn remove_parent_slices(&self) -> Result<()> {
+        for slice in slice::slice_hierarchy(&self.destructured_path.parent)
+            .iter()
+            .rev()
+        {
+            // the outer slices contain this one, so they cannot be empty either
+            if !self.client.transient_unit_is_empty(slice)? {
+                break;
+            }
+
+            log::debug!("removing empty slice {}", slice);
+            self.client
+                .stop_transient_unit(slice)
+                .with_context(|| format!("failed to remove slice {slice}"))?;
+        }
+
+        Ok(())
+    }
+
     /// ensures that each level in the downward path from the delegation boundary down to
     /// the scope or slice of the transient unit has all available controllers enabled
     fn ensure_controllers_attached(&self) -> Result<()> {
@@ -267,6 +402,104 @@ impl Manager {
         Ok(())
     }
 
+    fn apply_unified(&self, controller_opt: &ControllerOpt, systemd_version: u32) -> Result<()> {
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+        for controller in CONTROLLER_TYPES {
+            match controller {
+                ControllerType::Cpu => {
+                    Cpu::apply(controller_opt, systemd_version, &mut properties)?
+                }
+
+                ControllerType::CpuSet => {
+                    CpuSet::apply(controller_opt, systemd_version, &mut properties)?
+                }
+
+                ControllerType::Pids => {
+                    Pids::apply(controller_opt, systemd_version, &mut properties)?
+                }
+                ControllerType::Memory => {
+                    Memory::apply(controller_opt, systemd_version, &mut properties)?
+                }
+                ControllerType::Io => Io::apply(controller_opt, systemd_version, &mut properties)?,
+                ControllerType::Devices => {
+                    Devices::apply(controller_opt, systemd_version, &mut properties)?
+                }
+                // there are no unit properties for hugetlb, see below
+                ControllerType::HugeTlb => {}
+            };
+        }
+
+        Unified::apply(controller_opt, systemd_version, &mut properties)?;
+        log::debug!("{:?}", properties);
+
+        let hugetlb = controller_opt
+            .resources
+            .hugepage_limits()
+            .as_ref()
+            .map_or(false, |limits| !limits.is_empty());
+
+        if !properties.is_empty() || hugetlb {
+            self.ensure_controllers_attached()
+                .context("failed to attach controllers")?;
+        }
+
+        if !properties.is_empty() {
+            self.client
+                .set_unit_properties(&self.unit_name, &properties)
+                .context("could not apply resource restrictions")?;
+        }
+
+        // systemd does not support the hugetlb controller, so the limits are
+        // written to the cgroup of the unit directly. As systemd does not
+        // manage them, it does not reset them either.
+        if hugetlb {
+            <HugeTlb as FsController>::apply(controller_opt, &self.full_path)
+                .context("failed to apply hugetlb resource restrictions")?;
+        }
+
+        Ok(())
+    }
+
+    fn apply_legacy(
+        &self,
+        controller_opt: &ControllerOpt,
+        systemd_version: u32,
+        fs_manager: &V1Manager,
+    ) -> Result<()> {
+        self.set_legacy_properties(controller_opt, systemd_version)?;
+
+        // The properties make systemd restore the restrictions it knows about,
+        // e.g. after a daemon reload. Everything else is written to the cgroups
+        // of the unit, which rewrites some of the values set by systemd with
+        // the same value.
+        fs_manager
+            .apply_controllers(controller_opt, LEGACY_FS_CONTROLLERS)
+            .context("failed to apply cgroup v1 resource restrictions")?;
+
+        Ok(())
+    }
+
+    fn set_legacy_properties(
+        &self,
+        controller_opt: &ControllerOpt,
+        systemd_version: u32,
+    ) -> Result<()> {
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+        Legacy::apply(controller_opt, systemd_version, &mut properties)?;
+        Pids::apply(controller_opt, systemd_version, &mut properties)?;
+        Devices::apply(controller_opt, systemd_version, &mut properties)?;
+        log::debug!("{:?}", properties);
+
+        if !properties.is_empty() {
+            self.client
+                .set_unit_properties(&self.unit_name, &properties)
+                .context("could not apply resource restrictions")?;
+        }
+
+        Ok(())
+    }
+
     fn get_available_controllers<P: AsRef<Path>>(
         &self,
         cgroups_path: P,
@@ -283,6 +516,8 @@ impl Manager {
         for controller in fs::read_to_string(controllers_path)?.split_whitespace() {
             match controller {
                 "cpu" => controllers.push(ControllerType::Cpu),
+                "hugetlb" => controllers.push(ControllerType::HugeTlb),
+                "io" => controllers.push(ControllerType::Io),
                 "memory" => controllers.push(ControllerType::Memory),
                 "pids" => controllers.push(ControllerType::Pids),
                 _ => continue,
@@ -302,12 +537,14 @@ impl Manager {
 }
 
 impl CgroupManager for Manager {
-    fn add_task(&self, pid: Pid) -> Result<()> {
+    fn add_task(&self, pid: Pid) -> Result<(), CgroupError> {
         // Dont attach any pid to the cgroup if -1 is specified as a pid
         if pid.as_raw() == -1 {
             return Ok(());
         }
 
+        self.ensure_parent_slices()?;
+
         log::debug!("Starting {:?}", self.unit_name);
         self.client
             .start_transient_unit(
@@ -323,52 +560,32 @@ impl CgroupManager for Manager {
                 )
             })?;
 
+        // On cgroup v1 systemd only moves the process into the hierarchies of
+        // the controllers it manages, the others are joined directly
+        if let FsManager::Legacy(fs_manager) = &self.fs_manager {
+            fs_manager.add_task(pid)?;
+        }
+
         Ok(())
     }
 
-    fn apply(&self, controller_opt: &ControllerOpt) -> Result<()> {
-        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+    fn apply(&self, controller_opt: &ControllerOpt) -> Result<(), CgroupError> {
         let systemd_version = self
             .client
             .systemd_version()
             .context("could not retrieve systemd version")?;
 
-        for controller in CONTROLLER_TYPES {
-            match controller {
-                ControllerType::Cpu => {
-                    Cpu::apply(controller_opt, systemd_version, &mut properties)?
-                }
-
-                ControllerType::CpuSet => {
-                    CpuSet::apply(controller_opt, systemd_version, &mut properties)?
-                }
-
-                ControllerType::Pids => {
-                    Pids::apply(controller_opt, systemd_version, &mut properties)?
-                }
-                ControllerType::Memory => {
-                    Memory::apply(controller_opt, systemd_version, &mut properties)?
-                }
-                _ => {}
-            };
-        }
-
-        Unified::apply(controller_opt, systemd_version, &mut properties)?;
-        log::debug!("{:?}", properties);
-
-        if !properties.is_empty() {
-            self.ensure_controllers_attached()
-                .context("failed to attach controllers")?;
-
-            self.client
-                .set_unit_properties(&self.unit_name, &properties)
-                .context("could not apply resource restrictions")?;
+        match &self.fs_manager {
+            FsManager::Legacy(fs_manager) => {
+                self.apply_legacy(controller_opt, systemd_version, fs_manager)?
+            }
+            FsManager::Unified(_) => self.apply_unified(controller_opt, systemd_version)?,
         }
 
         Ok(())
     }
 
-    fn remove(&self) -> Result<()> {
+    fn remove(&self) -> Result<(), CgroupError> {
         log::debug!("remove {}", self.unit_name);
         if self.client.transient_unit_exists(&self.unit_name) {
             self.client
@@ -378,68 +595,109 @@ impl CgroupManager for Manager {
                 })?;
         }
 
+        // systemd only removes the cgroups in the hierarchies it manages
+        if let FsManager::Legacy(fs_manager) = &self.fs_manager {
+            fs_manager.remove()?;
+        }
+
+        // the container is gone, so failing to clean up its slices is no error
+        if let Err(err) = self.remove_parent_slices() {
+            log::warn!(
+                "failed to remove parent slices of {}: {:?}",
+                self.unit_name,
+                err
+            );
+        }
+
         Ok(())
     }
 
-    fn freeze(&self, state: FreezerState) -> Result<()> {
-        self.fs_manager.freeze(state)
+    fn freeze(&self, state: FreezerState) -> Result<(), CgroupError> {
+        self.fs_manager.manager().freeze(state)
+    }
+
+    fn stats(&self) -> Result<Stats, CgroupError> {
+        self.fs_manager.manager().stats()
+    }
+
+    fn get_all_pids(&self) -> Result<Vec<Pid>, CgroupError> {
+        self.fs_manager.manager().get_all_pids()
     }
 
-    fn stats(&self) -> Result<Stats> {
-        self.fs_manager.stats()
+    fn is_populated(&self) -> Result<bool, CgroupError> {
+        self.fs_manager.manager().is_populated()
     }
 
-    fn get_all_pids(&self) -> Result<Vec<Pid>> {
-        common::get_all_pids(&self.full_path)
+    fn event_listener(&self) -> Result<Box<dyn EventListener>, CgroupError> {
+        self.fs_manager.manager().event_listener()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::systemd::dbus::client::SystemdClient;
+    use oci_spec::runtime::{
+        LinuxBlockIoBuilder, LinuxCpuBuilder, LinuxDeviceCgroupBuilder, LinuxDeviceType,
+        LinuxMemoryBuilder, LinuxPidsBuilder, LinuxResources, LinuxResourcesBuilder,
+    };
 
     use super::*;
+    use crate::{
+        systemd::{
+            cpu::{convert_shares_to_cgroup2, CPU_PERIOD, CPU_QUOTA, CPU_WEIGHT},
+            cpuset::{ALLOWED_CPUS, ALLOWED_NODES},
+            dbus::fake_client::{Call, FakeSystemdClient},
+            devices::{DEVICE_ALLOW, DEVICE_POLICY},
+            io::IO_WEIGHT,
+            legacy::{BLOCK_IO_WEIGHT, CPU_SHARES, MEMORY_LIMIT},
+            memory::{MEMORY_HIGH, MEMORY_LOW, MEMORY_MAX, MEMORY_SWAP},
+            pids::TASKS_MAX,
+        },
+        test::{create_temp_dir, set_fixture, TempDir},
+    };
+
+    /// Creates a cgroup root that allows the manager to enable controllers
+    fn cgroup_root(test_name: &str) -> Result<TempDir> {
+        let tmp = create_temp_dir(test_name)?;
+        set_fixture(&tmp, CGROUP_CONTROLLERS, "cpu cpuset io memory pids")?;
+        set_fixture(&tmp, CGROUP_SUBTREE_CONTROL, "")?;
+        Ok(tmp)
+    }
 
-    struct TestSystemdClient {}
-
-    impl SystemdClient for TestSystemdClient {
-        fn is_system(&self) -> bool {
-            true
-        }
-
-        fn transient_unit_exists(&self, _: &str) -> bool {
-            true
-        }
-
-        fn start_transient_unit(
-            &self,
-            _container_name: &str,
-            _pid: u32,
-            _parent: &str,
-            _unit_name: &str,
-        ) -> Result<()> {
-            Ok(())
-        }
-
-        fn stop_transient_unit(&self, _unit_name: &str) -> Result<()> {
-            Ok(())
-        }
+    fn manager(
+        root: &Path,
+        cgroups_path: &str,
+        annotations: &HashMap<String, String>,
+        client: &FakeSystemdClient,
+    ) -> Result<Manager> {
+        Manager::with_client(
+            root.to_path_buf(),
+            PathBuf::from(cgroups_path),
+            "foo".to_owned(),
+            CgroupSetup::Unified,
+            annotations,
+            Box::new(client.clone()),
+        )
+    }
 
-        fn set_unit_properties(
-            &self,
-            _unit_name: &str,
-            _properties: &HashMap<&str, Box<dyn RefArg>>,
-        ) -> Result<()> {
-            Ok(())
+    fn controller_opt(resources: &LinuxResources) -> ControllerOpt {
+        ControllerOpt {
+            resources,
+            disable_oom_killer: false,
+            oom_score_adj: None,
+            freezer_state: None,
         }
+    }
 
-        fn systemd_version(&self) -> Result<u32> {
-            Ok(245)
-        }
+    fn u64_property(client: &FakeSystemdClient, unit_name: &str, property: &str) -> Option<u64> {
+        client
+            .property(unit_name, property)
+            .and_then(|value| value.as_u64())
+    }
 
-        fn control_cgroup_root(&self) -> Result<PathBuf> {
-            Ok(PathBuf::from("/"))
-        }
+    fn str_property(client: &FakeSystemdClient, unit_name: &str, property: &str) -> Option<String> {
+        client
+            .property(unit_name, property)
+            .and_then(|value| value.as_str().map(str::to_owned))
     }
 
     #[test]
@@ -459,7 +717,7 @@ mod tests {
             .context("construct path")?;
 
         assert_eq!(
-            Manager::construct_cgroups_path(&cgroups_path, &TestSystemdClient {})?.0,
+            Manager::construct_cgroups_path(&cgroups_path, &FakeSystemdClient::default())?.0,
             PathBuf::from("/test.slice/test-a.slice/test-a-b.slice/docker-foo.scope"),
         );
 
@@ -473,7 +731,7 @@ mod tests {
             .context("construct path")?;
 
         assert_eq!(
-            Manager::construct_cgroups_path(&cgroups_path, &TestSystemdClient {})?.0,
+            Manager::construct_cgroups_path(&cgroups_path, &FakeSystemdClient::default())?.0,
             PathBuf::from("/machine.slice/libpod-foo.scope"),
         );
 
@@ -488,10 +746,290 @@ mod tests {
         ensure_parent_unit(&mut cgroups_path, true);
 
         assert_eq!(
-            Manager::construct_cgroups_path(&cgroups_path, &TestSystemdClient {})?.0,
+            Manager::construct_cgroups_path(&cgroups_path, &FakeSystemdClient::default())?.0,
             PathBuf::from("/system.slice/docker-foo.scope"),
         );
 
         Ok(())
     }
+
+    #[test]
+    fn test_cgroups_path_of_rootless_container() -> Result<()> {
+        let client = FakeSystemdClient::session(1000);
+        let manager = manager(
+            Path::new("/sys/fs/cgroup"),
+            ":youki:foo",
+            &HashMap::new(),
+            &client,
+        )?;
+
+        assert_eq!(manager.unit_name, "youki-foo.scope");
+        assert_eq!(manager.destructured_path.parent, "user.slice");
+        assert_eq!(
+            manager.delegation_boundary,
+            PathBuf::from("/user.slice/user-1000.slice/user@1000.service")
+        );
+        assert_eq!(
+            manager.cgroups_path,
+            PathBuf::from(
+                "/user.slice/user-1000.slice/user@1000.service/user.slice/youki-foo.scope"
+            )
+        );
+        assert_eq!(
+            manager.full_path,
+            PathBuf::from(
+                "/sys/fs/cgroup/user.slice/user-1000.slice/user@1000.service/user.slice/youki-foo.scope"
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_task_starts_scope() -> Result<()> {
+        let client = FakeSystemdClient::default().with_slice("system.slice");
+        let manager = manager(
+            Path::new("/sys/fs/cgroup"),
+            ":docker:foo",
+            &HashMap::new(),
+            &client,
+        )?;
+
+        manager.add_task(Pid::from_raw(-1))?;
+        client.take_calls();
+        assert!(!client.transient_unit_exists("docker-foo.scope"));
+
+        manager.add_task(Pid::from_raw(1234))?;
+        let calls = client.take_calls();
+        assert!(matches!(
+            calls.as_slice(),
+            [
+                Call::StartTransientSlice(slice),
+                Call::StartTransientUnit { unit_name, parent, pid: 1234 },
+            ] if slice == "system.slice" && unit_name == "docker-foo.scope" && parent == "system.slice"
+        ));
+        assert_eq!(
+            client.unit_names(),
+            vec!["docker-foo.scope", "system.slice"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_translates_resources() -> Result<()> {
+        let tmp = cgroup_root("test_systemd_apply_translates_resources")?;
+        let client = FakeSystemdClient::default().with_slice("system.slice");
+        let manager = manager(&tmp, "system.slice:youki:foo", &HashMap::new(), &client)?;
+        manager.add_task(Pid::from_raw(1234))?;
+
+        let resources = LinuxResourcesBuilder::default()
+            .cpu(
+                LinuxCpuBuilder::default()
+                    .shares(1024u64)
+                    .quota(50_000i64)
+                    .period(100_000u64)
+                    .cpus("0-3")
+                    .mems("0")
+                    .build()?,
+            )
+            .memory(
+                LinuxMemoryBuilder::default()
+                    .reservation(1024i64)
+                    .limit(4096i64)
+                    .swap(8192i64)
+                    .build()?,
+            )
+            .pids(LinuxPidsBuilder::default().limit(100i64).build()?)
+            .block_io(LinuxBlockIoBuilder::default().weight(1000u16).build()?)
+            .devices(vec![LinuxDeviceCgroupBuilder::default()
+                .allow(false)
+                .typ(LinuxDeviceType::A)
+                .access("rwm")
+                .build()?])
+            .unified(HashMap::from([(
+                "memory.high".to_owned(),
+                "2048".to_owned(),
+            )]))
+            .build()?;
+        manager.apply(&controller_opt(&resources))?;
+
+        let unit = "youki-foo.scope";
+        assert_eq!(
+            u64_property(&client, unit, CPU_WEIGHT),
+            Some(convert_shares_to_cgroup2(1024))
+        );
+        assert_eq!(u64_property(&client, unit, CPU_QUOTA), Some(500_000));
+        assert_eq!(u64_property(&client, unit, CPU_PERIOD), Some(100_000));
+        assert!(client.property(unit, ALLOWED_CPUS).is_some());
+        assert!(client.property(unit, ALLOWED_NODES).is_some());
+        assert_eq!(u64_property(&client, unit, MEMORY_LOW), Some(1024));
+        assert_eq!(u64_property(&client, unit, MEMORY_MAX), Some(4096));
+        assert_eq!(u64_property(&client, unit, MEMORY_SWAP), Some(4096));
+        assert_eq!(u64_property(&client, unit, MEMORY_HIGH), Some(2048));
+        assert_eq!(u64_property(&client, unit, TASKS_MAX), Some(100));
+        assert_eq!(u64_property(&client, unit, IO_WEIGHT), Some(10000));
+        assert_eq!(
+            str_property(&client, unit, DEVICE_POLICY).as_deref(),
+            Some("strict")
+        );
+        assert!(client.property(unit, DEVICE_ALLOW).is_some());
+
+        // the controllers have been enabled for the unit
+        let subtree_control = fs::read_to_string(tmp.join(CGROUP_SUBTREE_CONTROL))?;
+        assert!(!subtree_control.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_requires_recent_systemd_for_cpuset() -> Result<()> {
+        let tmp = cgroup_root("test_systemd_apply_requires_recent_systemd")?;
+        let client = FakeSystemdClient::default()
+            .with_slice("system.slice")
+            .with_version(243);
+        let manager = manager(&tmp, "system.slice:youki:foo", &HashMap::new(), &client)?;
+        manager.add_task(Pid::from_raw(1234))?;
+
+        let resources = LinuxResourcesBuilder::default()
+            .cpu(LinuxCpuBuilder::default().cpus("0-3").build()?)
+            .build()?;
+        assert!(manager.apply(&controller_opt(&resources)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_properties() -> Result<()> {
+        let client = FakeSystemdClient::default().with_slice("system.slice");
+        let manager = manager(
+            Path::new("/sys/fs/cgroup"),
+            "system.slice:youki:foo",
+            &HashMap::new(),
+            &client,
+        )?;
+        manager.add_task(Pid::from_raw(1234))?;
+
+        let resources = LinuxResourcesBuilder::default()
+            .cpu(LinuxCpuBuilder::default().shares(1024u64).build()?)
+            .memory(LinuxMemoryBuilder::default().limit(4096i64).build()?)
+            .pids(LinuxPidsBuilder::default().limit(100i64).build()?)
+            .block_io(LinuxBlockIoBuilder::default().weight(500u16).build()?)
+            .build()?;
+        manager.set_legacy_properties(&controller_opt(&resources), 245)?;
+
+        let unit = "youki-foo.scope";
+        assert_eq!(u64_property(&client, unit, CPU_SHARES), Some(1024));
+        assert_eq!(u64_property(&client, unit, MEMORY_LIMIT), Some(4096));
+        assert_eq!(u64_property(&client, unit, BLOCK_IO_WEIGHT), Some(500));
+        assert_eq!(u64_property(&client, unit, TASKS_MAX), Some(100));
+        assert!(client.property(unit, CPU_WEIGHT).is_none());
+        assert!(client.property(unit, MEMORY_MAX).is_none());
+        assert!(client.property(unit, IO_WEIGHT).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parent_slices_are_created_and_removed() -> Result<()> {
+        let annotations = HashMap::from([(
+            "org.youki.systemd.slice.tenant-a.slice.CPUWeight".to_owned(),
+            "200".to_owned(),
+        )]);
+        let client = FakeSystemdClient::default();
+        let first = manager(
+            Path::new("/sys/fs/cgroup"),
+            "tenant-a.slice:youki:first",
+            &annotations,
+            &client,
+        )?;
+        let second = manager(
+            Path::new("/sys/fs/cgroup"),
+            "tenant-a.slice:youki:second",
+            &HashMap::new(),
+            &client,
+        )?;
+
+        first.add_task(Pid::from_raw(1234))?;
+        second.add_task(Pid::from_raw(1235))?;
+        assert_eq!(
+            client.unit_names(),
+            vec![
+                "tenant-a.slice",
+                "tenant.slice",
+                "youki-first.scope",
+                "youki-second.scope"
+            ]
+        );
+        assert_eq!(
+            u64_property(&client, "tenant-a.slice", CPU_WEIGHT),
+            Some(200)
+        );
+        assert!(client.take_calls().iter().any(|call| matches!(
+            call,
+            Call::SetUnitProperties { unit_name, properties }
+                if unit_name == "tenant-a.slice" && properties.len() == 1
+        )));
+        assert!(client.property("tenant.slice", CPU_WEIGHT).is_none());
+
+        // the slices are still used by the second container
+        client.exit_processes("youki-first.scope");
+        first.remove()?;
+        assert_eq!(
+            client.unit_names(),
+            vec!["tenant-a.slice", "tenant.slice", "youki-second.scope"]
+        );
+
+        client.exit_processes("youki-second.scope");
+        second.remove()?;
+        assert!(client.unit_names().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_existing_slices_are_kept() -> Result<()> {
+        let client = FakeSystemdClient::default().with_slice("tenant.slice");
+        let manager = manager(
+            Path::new("/sys/fs/cgroup"),
+            "tenant-a.slice:youki:foo",
+            &HashMap::new(),
+            &client,
+        )?;
+
+        manager.add_task(Pid::from_raw(1234))?;
+        client.exit_processes("youki-foo.scope");
+        manager.remove()?;
+
+        // only the slice that has been created for the container is removed
+        assert_eq!(client.unit_names(), vec!["tenant.slice"]);
+        let calls = client.take_calls();
+        let stopped: Vec<&str> = calls
+            .iter()
+            .filter_map(|call| match call {
+                Call::StopUnit(unit_name) => Some(unit_name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(stopped, vec!["youki-foo.scope", "tenant-a.slice"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_slice_properties_require_parent_slice() {
+        let annotations = HashMap::from([(
+            "org.youki.systemd.slice.other.slice.CPUWeight".to_owned(),
+            "200".to_owned(),
+        )]);
+        let client = FakeSystemdClient::default();
+
+        assert!(manager(
+            Path::new("/sys/fs/cgroup"),
+            "tenant-a.slice:youki:foo",
+            &annotations,
+            &client,
+        )
+        .is_err());
+    }
 }
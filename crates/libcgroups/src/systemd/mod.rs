@@ -5,9 +5,13 @@ pub mod controller_type;
 mod cpu;
 mod cpuset;
 mod dbus;
+mod devices;
+mod io;
+mod legacy;
 pub mod manager;
 mod memory;
 mod pids;
+pub mod slice;
 mod unified;
 
 /// Checks if the system was booted with systemd
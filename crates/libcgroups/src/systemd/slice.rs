@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+
+use super::{cpu, io, legacy, memory, pids};
+
+/// Prefix of the annotations that set resource properties of the slices a
+/// container is placed in, e.g. `org.youki.systemd.slice.tenant-a.slice.CPUWeight`
+pub const SLICE_PROPERTY_ANNOTATION: &str = "org.youki.systemd.slice.";
+
+const SLICE_SUFFIX: &str = ".slice";
+
+/// Properties that can be set for slices. All of them take either an
+/// integer or `infinity` as value.
+const SLICE_PROPERTIES: &[&str] = &[
+    cpu::CPU_WEIGHT,
+    cpu::CPU_QUOTA,
+    cpu::CPU_PERIOD,
+    io::IO_WEIGHT,
+    memory::MEMORY_MIN,
+    memory::MEMORY_LOW,
+    memory::MEMORY_HIGH,
+    memory::MEMORY_MAX,
+    memory::MEMORY_SWAP,
+    pids::TASKS_MAX,
+    legacy::CPU_SHARES,
+    legacy::MEMORY_LIMIT,
+    legacy::BLOCK_IO_WEIGHT,
+];
+
+/// Resource properties per slice name
+pub(crate) type SliceProperties = HashMap<String, Vec<(&'static str, u64)>>;
+
+/// Collects the slice properties from the annotations of a container
+pub(crate) fn parse_slice_properties(
+    annotations: &HashMap<String, String>,
+) -> Result<SliceProperties> {
+    let mut slice_properties = SliceProperties::new();
+    for (key, value) in annotations {
+        let slice_property = match key.strip_prefix(SLICE_PROPERTY_ANNOTATION) {
+            Some(slice_property) => slice_property,
+            None => continue,
+        };
+
+        // slice names contain dots, property names do not
+        let (slice, property) = match slice_property.rsplit_once('.') {
+            Some((slice, property)) if slice.ends_with(SLICE_SUFFIX) => (slice, property),
+            _ => bail!("invalid slice property annotation {}", key),
+        };
+        let property = SLICE_PROPERTIES
+            .iter()
+            .find(|p| **p == property)
+            .copied()
+            .with_context(|| format!("property {property} cannot be set for slices"))?;
+        let value = match value.trim() {
+            "infinity" => u64::MAX,
+            v => v
+                .parse::<u64>()
+                .with_context(|| format!("invalid value {v} for {property} of {slice}"))?,
+        };
+
+        slice_properties
+            .entry(slice.to_owned())
+            .or_default()
+            .push((property, value));
+    }
+
+    Ok(slice_properties)
+}
+
+/// Returns the names of the slice and all of its parent slices except the root
+/// slice, starting with the outermost one. For example, `tenant-a-batch.slice`
+/// returns `tenant.slice`, `tenant-a.slice` and `tenant-a-batch.slice`.
+pub(crate) fn slice_hierarchy(slice: &str) -> Vec<String> {
+    let name = match slice.strip_suffix(SLICE_SUFFIX) {
+        Some(name) if !name.is_empty() && name != "-" => name,
+        _ => return Vec::new(),
+    };
+
+    let mut slices = Vec::new();
+    let mut prefix = String::new();
+    for component in name.split('-') {
+        prefix.push_str(component);
+        slices.push(format!("{prefix}{SLICE_SUFFIX}"));
+        prefix.push('-');
+    }
+
+    slices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_hierarchy() {
+        assert_eq!(
+            slice_hierarchy("tenant-a-batch.slice"),
+            vec!["tenant.slice", "tenant-a.slice", "tenant-a-batch.slice"]
+        );
+        assert_eq!(slice_hierarchy("system.slice"), vec!["system.slice"]);
+        assert!(slice_hierarchy("-.slice").is_empty());
+    }
+
+    #[test]
+    fn test_parse_slice_properties() -> Result<()> {
+        let annotations = HashMap::from([
+            (
+                "org.youki.systemd.slice.tenant-a.slice.CPUWeight".to_owned(),
+                "200".to_owned(),
+            ),
+            (
+                "org.youki.systemd.slice.tenant-a.slice.MemoryMax".to_owned(),
+                "infinity".to_owned(),
+            ),
+            (
+                "org.youki.systemd.slice.tenant.slice.TasksMax".to_owned(),
+                "1000".to_owned(),
+            ),
+            ("run.oci.handler".to_owned(), "wasm".to_owned()),
+        ]);
+
+        let mut slice_properties = parse_slice_properties(&annotations)?;
+        assert_eq!(slice_properties.len(), 2);
+        let tenant_a = slice_properties.get_mut("tenant-a.slice").unwrap();
+        tenant_a.sort();
+        assert_eq!(
+            tenant_a,
+            &vec![(cpu::CPU_WEIGHT, 200), (memory::MEMORY_MAX, u64::MAX)]
+        );
+        assert_eq!(
+            slice_properties["tenant.slice"],
+            vec![(pids::TASKS_MAX, 1000)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_slice_properties() {
+        for (key, value) in [
+            ("org.youki.systemd.slice.tenant.CPUWeight", "200"),
+            (
+                "org.youki.systemd.slice.tenant.slice.DevicePolicy",
+                "strict",
+            ),
+            ("org.youki.systemd.slice.tenant.slice.CPUWeight", "-1"),
+        ] {
+            let annotations = HashMap::from([(key.to_owned(), value.to_owned())]);
+            assert!(
+                parse_slice_properties(&annotations).is_err(),
+                "{key}={value} should be rejected"
+            );
+        }
+    }
+}
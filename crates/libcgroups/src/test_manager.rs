@@ -1,10 +1,10 @@
 use std::cell::RefCell;
 
-use anyhow::Result;
 use nix::unistd::Pid;
 
 use crate::{
-    common::{CgroupManager, ControllerOpt, FreezerState},
+    common::{CgroupError, CgroupManager, ControllerOpt, FreezerState},
+    events::EventListener,
     stats::Stats,
 };
 
@@ -24,30 +24,34 @@ impl Default for TestManager {
 }
 
 impl CgroupManager for TestManager {
-    fn add_task(&self, pid: Pid) -> Result<()> {
+    fn add_task(&self, pid: Pid) -> Result<(), CgroupError> {
         self.add_task_args.borrow_mut().push(pid);
         Ok(())
     }
 
     // NOTE: The argument cannot be stored due to lifetime.
-    fn apply(&self, _controller_opt: &ControllerOpt) -> Result<()> {
+    fn apply(&self, _controller_opt: &ControllerOpt) -> Result<(), CgroupError> {
         *self.apply_called.borrow_mut() = true;
         Ok(())
     }
 
-    fn remove(&self) -> Result<()> {
+    fn remove(&self) -> Result<(), CgroupError> {
         unimplemented!()
     }
 
-    fn freeze(&self, _state: FreezerState) -> Result<()> {
+    fn freeze(&self, _state: FreezerState) -> Result<(), CgroupError> {
         unimplemented!()
     }
 
-    fn stats(&self) -> anyhow::Result<Stats> {
+    fn stats(&self) -> Result<Stats, CgroupError> {
         unimplemented!()
     }
 
-    fn get_all_pids(&self) -> Result<Vec<Pid>> {
+    fn get_all_pids(&self) -> Result<Vec<Pid>, CgroupError> {
+        unimplemented!()
+    }
+
+    fn event_listener(&self) -> Result<Box<dyn EventListener>, CgroupError> {
         unimplemented!()
     }
 }
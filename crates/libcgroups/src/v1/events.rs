@@ -0,0 +1,111 @@
+use std::{
+    fs::File,
+    io::{ErrorKind, Read},
+    os::unix::io::{AsRawFd, FromRawFd, RawFd},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use nix::sys::eventfd::{eventfd, EfdFlags};
+
+use crate::{
+    common,
+    events::{CgroupEvent, EventCounter, EventListener},
+};
+
+const CGROUP_EVENT_CONTROL: &str = "cgroup.event_control";
+const CGROUP_MEMORY_OOM_CONTROL: &str = "memory.oom_control";
+const CGROUP_PIDS_EVENTS: &str = "pids.events";
+
+/// Registers an eventfd for `memory.oom_control` of a cgroup v1, which is
+/// signaled by the kernel every time the cgroup runs out of memory. There is
+/// no notification mechanism for `pids.events`, so its counter is checked
+/// whenever the listener is woken up.
+pub struct Listener {
+    eventfd: File,
+    _oom_control: Option<File>,
+    pids_max: Option<EventCounter>,
+}
+
+impl Listener {
+    pub fn new(memory_path: Option<&Path>, pids_path: Option<&Path>) -> Result<Self> {
+        let efd = eventfd(0, EfdFlags::EFD_CLOEXEC | EfdFlags::EFD_NONBLOCK)
+            .context("failed to create eventfd")?;
+        // SAFETY: the fd has just been created and is not owned by anything else
+        let eventfd = unsafe { File::from_raw_fd(efd) };
+
+        let oom_control = match memory_path {
+            Some(memory_path) => {
+                let oom_control_path = memory_path.join(CGROUP_MEMORY_OOM_CONTROL);
+                let oom_control = File::open(&oom_control_path)
+                    .with_context(|| format!("failed to open {oom_control_path:?}"))?;
+                common::write_cgroup_file(
+                    memory_path.join(CGROUP_EVENT_CONTROL),
+                    format!("{} {}", eventfd.as_raw_fd(), oom_control.as_raw_fd()),
+                )
+                .context("failed to register oom notification")?;
+                Some(oom_control)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            eventfd,
+            _oom_control: oom_control,
+            pids_max: pids_path.map(|p| EventCounter::new(&p.join(CGROUP_PIDS_EVENTS), "max")),
+        })
+    }
+}
+
+impl AsRawFd for Listener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.eventfd.as_raw_fd()
+    }
+}
+
+impl EventListener for Listener {
+    fn read_events(&mut self) -> Result<Vec<CgroupEvent>> {
+        let mut events = Vec::new();
+
+        // The eventfd holds the number of oom notifications since it was last read
+        let mut buf = [0u8; 8];
+        match self.eventfd.read(&mut buf) {
+            Ok(_) => {
+                for _ in 0..u64::from_ne_bytes(buf) {
+                    events.push(CgroupEvent::Oom);
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {}
+            Err(err) => return Err(err).context("failed to read oom eventfd"),
+        }
+
+        if let Some(pids_max) = &mut self.pids_max {
+            for _ in 0..pids_max.delta() {
+                events.push(CgroupEvent::PidsLimit);
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{create_temp_dir, set_fixture};
+
+    #[test]
+    fn test_listener_reports_pids_limit() {
+        let tmp = create_temp_dir("test_listener_reports_pids_limit").unwrap();
+        set_fixture(&tmp, CGROUP_PIDS_EVENTS, "max 0\n").unwrap();
+
+        let mut listener = Listener::new(None, Some(tmp.path())).unwrap();
+        assert!(listener.read_events().unwrap().is_empty());
+
+        set_fixture(&tmp, CGROUP_PIDS_EVENTS, "max 1\n").unwrap();
+        assert_eq!(
+            listener.read_events().unwrap(),
+            vec![CgroupEvent::PidsLimit]
+        );
+    }
+}
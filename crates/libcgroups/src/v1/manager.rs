@@ -4,7 +4,7 @@ use std::time::Duration;
 use std::{collections::HashMap, path::PathBuf};
 
 use anyhow::bail;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use nix::unistd::Pid;
 
 use procfs::process::Process;
@@ -12,12 +12,15 @@ use procfs::process::Process;
 use super::ControllerType as CtrlType;
 use super::{
     blkio::Blkio, controller_type::CONTROLLERS, cpu::Cpu, cpuacct::CpuAcct, cpuset::CpuSet,
-    devices::Devices, freezer::Freezer, hugetlb::HugeTlb, memory::Memory,
+    devices::Devices, events::Listener, freezer::Freezer, hugetlb::HugeTlb, memory::Memory,
     network_classifier::NetworkClassifier, network_priority::NetworkPriority,
-    perf_event::PerfEvent, pids::Pids, util, Controller,
+    perf_event::PerfEvent, pids::Pids, rdma::Rdma, util, Controller,
 };
 
-use crate::common::{self, CgroupManager, ControllerOpt, FreezerState, PathBufExt, CGROUP_PROCS};
+use crate::common::{
+    self, CgroupError, CgroupManager, ControllerOpt, FreezerState, PathBufExt, CGROUP_PROCS,
+};
+use crate::events::EventListener;
 use crate::stats::{Stats, StatsProvider};
 
 pub struct Manager {
@@ -82,6 +85,7 @@ impl Manager {
                     NetworkClassifier::needs_to_handle(controller_opt).is_some()
                 }
                 CtrlType::Freezer => Freezer::needs_to_handle(controller_opt).is_some(),
+                CtrlType::Rdma => Rdma::needs_to_handle(controller_opt).is_some(),
             };
 
             if required {
@@ -95,18 +99,53 @@ impl Manager {
 
         Ok(required_controllers)
     }
+
+    /// Applies the resource restrictions of the given controllers only, e.g.
+    /// those that are not managed by systemd
+    pub(crate) fn apply_controllers(
+        &self,
+        controller_opt: &ControllerOpt,
+        controllers: &[CtrlType],
+    ) -> Result<()> {
+        for subsys in self.get_required_controllers(controller_opt)? {
+            if !controllers.contains(subsys.0) {
+                continue;
+            }
+
+            match subsys.0 {
+                CtrlType::Cpu => Cpu::apply(controller_opt, subsys.1)?,
+                CtrlType::CpuAcct => CpuAcct::apply(controller_opt, subsys.1)?,
+                CtrlType::CpuSet => CpuSet::apply(controller_opt, subsys.1)?,
+                CtrlType::Devices => Devices::apply(controller_opt, subsys.1)?,
+                CtrlType::HugeTlb => HugeTlb::apply(controller_opt, subsys.1)?,
+                CtrlType::Memory => Memory::apply(controller_opt, subsys.1)?,
+                CtrlType::Pids => Pids::apply(controller_opt, subsys.1)?,
+                CtrlType::PerfEvent => PerfEvent::apply(controller_opt, subsys.1)?,
+                CtrlType::Blkio => Blkio::apply(controller_opt, subsys.1)?,
+                CtrlType::NetworkPriority => NetworkPriority::apply(controller_opt, subsys.1)?,
+                CtrlType::NetworkClassifier => NetworkClassifier::apply(controller_opt, subsys.1)?,
+                CtrlType::Freezer => Freezer::apply(controller_opt, subsys.1)?,
+                CtrlType::Rdma => Rdma::apply(controller_opt, subsys.1)?,
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl CgroupManager for Manager {
-    fn get_all_pids(&self) -> Result<Vec<Pid>> {
-        let devices = self.subsystems.get(&CtrlType::Devices);
-        if let Some(p) = devices {
-            common::get_all_pids(p)
-        } else {
-            bail!("subsystem does not exist")
+    fn get_all_pids(&self) -> Result<Vec<Pid>, CgroupError> {
+        let devices = self
+            .subsystems
+            .get(&CtrlType::Devices)
+            .context("subsystem does not exist")?;
+        if !devices.exists() {
+            return Err(CgroupError::NotFound(devices.clone()));
         }
+
+        Ok(common::get_all_pids(devices)?)
     }
-    fn add_task(&self, pid: Pid) -> Result<()> {
+    fn add_task(&self, pid: Pid) -> Result<(), CgroupError> {
         for subsys in &self.subsystems {
             match subsys.0 {
                 CtrlType::Cpu => Cpu::add_task(pid, subsys.1)?,
@@ -121,42 +160,29 @@ impl CgroupManager for Manager {
                 CtrlType::NetworkPriority => NetworkPriority::add_task(pid, subsys.1)?,
                 CtrlType::NetworkClassifier => NetworkClassifier::add_task(pid, subsys.1)?,
                 CtrlType::Freezer => Freezer::add_task(pid, subsys.1)?,
+                CtrlType::Rdma => Rdma::add_task(pid, subsys.1)?,
             }
         }
 
         Ok(())
     }
 
-    fn apply(&self, controller_opt: &ControllerOpt) -> Result<()> {
-        for subsys in self.get_required_controllers(controller_opt)? {
-            match subsys.0 {
-                CtrlType::Cpu => Cpu::apply(controller_opt, subsys.1)?,
-                CtrlType::CpuAcct => CpuAcct::apply(controller_opt, subsys.1)?,
-                CtrlType::CpuSet => CpuSet::apply(controller_opt, subsys.1)?,
-                CtrlType::Devices => Devices::apply(controller_opt, subsys.1)?,
-                CtrlType::HugeTlb => HugeTlb::apply(controller_opt, subsys.1)?,
-                CtrlType::Memory => Memory::apply(controller_opt, subsys.1)?,
-                CtrlType::Pids => Pids::apply(controller_opt, subsys.1)?,
-                CtrlType::PerfEvent => PerfEvent::apply(controller_opt, subsys.1)?,
-                CtrlType::Blkio => Blkio::apply(controller_opt, subsys.1)?,
-                CtrlType::NetworkPriority => NetworkPriority::apply(controller_opt, subsys.1)?,
-                CtrlType::NetworkClassifier => NetworkClassifier::apply(controller_opt, subsys.1)?,
-                CtrlType::Freezer => Freezer::apply(controller_opt, subsys.1)?,
-            }
-        }
-
-        Ok(())
+    fn apply(&self, controller_opt: &ControllerOpt) -> Result<(), CgroupError> {
+        Ok(self.apply_controllers(controller_opt, CONTROLLERS)?)
     }
 
-    fn remove(&self) -> Result<()> {
+    fn remove(&self) -> Result<(), CgroupError> {
         for cgroup_path in &self.subsystems {
             if cgroup_path.1.exists() {
                 log::debug!("remove cgroup {:?}", cgroup_path.1);
                 let procs_path = cgroup_path.1.join(CGROUP_PROCS);
-                let procs = fs::read_to_string(procs_path)?;
+                let procs = fs::read_to_string(&procs_path)
+                    .with_context(|| format!("failed to read {procs_path:?}"))?;
 
                 for line in procs.lines() {
-                    let pid: i32 = line.parse()?;
+                    let pid: i32 = line
+                        .parse()
+                        .with_context(|| format!("invalid pid {line} in {procs_path:?}"))?;
                     let _ = nix::sys::signal::kill(Pid::from_raw(pid), nix::sys::signal::SIGKILL);
                 }
 
@@ -167,20 +193,20 @@ impl CgroupManager for Manager {
         Ok(())
     }
 
-    fn freeze(&self, state: FreezerState) -> Result<()> {
+    fn freeze(&self, state: FreezerState) -> Result<(), CgroupError> {
         let controller_opt = ControllerOpt {
             resources: &Default::default(),
             freezer_state: Some(state),
             oom_score_adj: None,
             disable_oom_killer: false,
         };
-        Freezer::apply(
+        Ok(Freezer::apply(
             &controller_opt,
             self.subsystems.get(&CtrlType::Freezer).unwrap(),
-        )
+        )?)
     }
 
-    fn stats(&self) -> Result<Stats> {
+    fn stats(&self) -> Result<Stats, CgroupError> {
         let mut stats = Stats::default();
 
         for subsystem in &self.subsystems {
@@ -191,10 +217,18 @@ impl CgroupManager for Manager {
                 CtrlType::HugeTlb => stats.hugetlb = HugeTlb::stats(subsystem.1)?,
                 CtrlType::Blkio => stats.blkio = Blkio::stats(subsystem.1)?,
                 CtrlType::Memory => stats.memory = Memory::stats(subsystem.1)?,
+                CtrlType::Rdma => stats.rdma = Rdma::stats(subsystem.1)?,
                 _ => continue,
             }
         }
 
         Ok(stats)
     }
+
+    fn event_listener(&self) -> Result<Box<dyn EventListener>, CgroupError> {
+        Ok(Box::new(Listener::new(
+            self.subsystems.get(&CtrlType::Memory).map(|p| p.as_path()),
+            self.subsystems.get(&CtrlType::Pids).map(|p| p.as_path()),
+        )?))
+    }
 }
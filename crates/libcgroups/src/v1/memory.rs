@@ -116,6 +116,7 @@ impl StatsProvider for Memory {
         let kernel_tcp = Self::get_memory_data(cgroup_path, MEMORY_KERNEL_TCP_PREFIX)?;
         let hierarchy = Self::hierarchy_enabled(cgroup_path)?;
         let stats = Self::get_stat_data(cgroup_path)?;
+        let oom_kill = Self::get_oom_kill_count(cgroup_path)?;
 
         Ok(MemoryStats {
             memory,
@@ -123,6 +124,7 @@ impl StatsProvider for Memory {
             kernel,
             kernel_tcp,
             cache: stats["cache"],
+            oom_kill,
             hierarchy,
             stats,
             ..Default::default()
@@ -162,6 +164,13 @@ impl Memory {
         stats::parse_flat_keyed_data(&cgroup_path.join(MEMORY_STAT))
     }
 
+    /// The number of oom kills is reported by kernels since 4.13
+    fn get_oom_kill_count(cgroup_path: &Path) -> Result<u64> {
+        let oom_control =
+            stats::parse_flat_keyed_data(&cgroup_path.join(CGROUP_MEMORY_OOM_CONTROL))?;
+        Ok(oom_control.get("oom_kill").copied().unwrap_or_default())
+    }
+
     fn get_memory_usage(cgroup_root: &Path) -> Result<u64> {
         let path = cgroup_root.join(CGROUP_MEMORY_USAGE);
         let mut contents = String::new();
@@ -585,6 +594,30 @@ mod tests {
         assert!(!enabled)
     }
 
+    #[test]
+    fn test_stat_oom_kill_count() {
+        let tmp = create_temp_dir("test_stat_oom_kill_count").expect("create test directory");
+        set_fixture(
+            &tmp,
+            CGROUP_MEMORY_OOM_CONTROL,
+            "oom_kill_disable 0\nunder_oom 0\noom_kill 2\n",
+        )
+        .unwrap();
+
+        let oom_kill = Memory::get_oom_kill_count(&tmp).expect("get oom kill count");
+        assert_eq!(oom_kill, 2);
+
+        // older kernels do not report oom kills
+        set_fixture(
+            &tmp,
+            CGROUP_MEMORY_OOM_CONTROL,
+            "oom_kill_disable 0\nunder_oom 0\n",
+        )
+        .unwrap();
+        let oom_kill = Memory::get_oom_kill_count(&tmp).expect("get oom kill count");
+        assert_eq!(oom_kill, 0);
+    }
+
     #[test]
     fn test_stat_memory_stats() {
         let tmp = create_temp_dir("test_stat_memory_stats").expect("create test directory");
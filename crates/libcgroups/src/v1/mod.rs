@@ -5,6 +5,7 @@ mod cpu;
 mod cpuacct;
 mod cpuset;
 mod devices;
+pub mod events;
 mod freezer;
 mod hugetlb;
 pub mod manager;
@@ -13,6 +14,7 @@ mod network_classifier;
 mod network_priority;
 pub mod perf_event;
 mod pids;
+mod rdma;
 pub mod util;
 pub use controller::Controller;
 pub use controller_type::ControllerType;
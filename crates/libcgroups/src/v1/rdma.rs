@@ -0,0 +1,99 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{Context, Result};
+
+use super::Controller;
+use crate::{
+    common::{self, ControllerOpt},
+    stats::{self, RdmaStats, StatsProvider},
+};
+use oci_spec::runtime::LinuxRdma;
+
+// Contains the rdma resource limits of each device
+const CGROUP_RDMA_MAX: &str = "rdma.max";
+
+pub struct Rdma {}
+
+impl Controller for Rdma {
+    type Resource = HashMap<String, LinuxRdma>;
+
+    fn apply(controller_opt: &ControllerOpt, cgroup_root: &Path) -> Result<()> {
+        log::debug!("Apply rdma cgroup config");
+
+        if let Some(rdma) = Self::needs_to_handle(controller_opt) {
+            Self::apply(cgroup_root, rdma).context("failed to apply rdma resource restrictions")?;
+        }
+
+        Ok(())
+    }
+
+    fn needs_to_handle<'a>(controller_opt: &'a ControllerOpt) -> Option<&'a Self::Resource> {
+        controller_opt.resources.rdma().as_ref()
+    }
+}
+
+impl StatsProvider for Rdma {
+    type Stats = RdmaStats;
+
+    fn stats(cgroup_path: &Path) -> Result<Self::Stats> {
+        stats::rdma_stats(cgroup_path)
+    }
+}
+
+impl Rdma {
+    fn apply(root_path: &Path, rdma: &HashMap<String, LinuxRdma>) -> Result<()> {
+        for (device, limits) in rdma {
+            common::write_cgroup_file_str(
+                root_path.join(CGROUP_RDMA_MAX),
+                &common::format_rdma_limits(device, limits),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{create_temp_dir, set_fixture};
+    use oci_spec::runtime::LinuxRdmaBuilder;
+
+    #[test]
+    fn test_set_rdma() {
+        let tmp = create_temp_dir("test_set_rdma").expect("create temp directory for test");
+        set_fixture(&tmp, CGROUP_RDMA_MAX, "").expect("set fixture for rdma.max");
+
+        let mut rdma = HashMap::new();
+        rdma.insert(
+            "mlx4_0".to_owned(),
+            LinuxRdmaBuilder::default()
+                .hca_objects(1000u32)
+                .build()
+                .unwrap(),
+        );
+
+        Rdma::apply(&tmp, &rdma).expect("apply rdma");
+        let content = std::fs::read_to_string(tmp.join(CGROUP_RDMA_MAX)).expect("read rdma.max");
+        assert_eq!(content, "mlx4_0 hca_handle=max hca_object=1000");
+    }
+
+    #[test]
+    fn test_stat_rdma() {
+        let tmp = create_temp_dir("test_stat_rdma").expect("create temp directory for test");
+        set_fixture(&tmp, "rdma.current", "mlx4_0 hca_handle=1 hca_object=20\n").unwrap();
+        set_fixture(
+            &tmp,
+            CGROUP_RDMA_MAX,
+            "mlx4_0 hca_handle=max hca_object=1000\n",
+        )
+        .unwrap();
+
+        let stats = Rdma::stats(&tmp).expect("get cgroup stats");
+        assert_eq!(stats.current.len(), 1);
+        assert_eq!(stats.current[0].hca_handles, 1);
+        assert_eq!(stats.current[0].hca_objects, 20);
+        assert_eq!(stats.limit[0].hca_handles, u64::MAX);
+        assert_eq!(stats.limit[0].hca_objects, 1000);
+    }
+}
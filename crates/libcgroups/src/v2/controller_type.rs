@@ -8,6 +8,8 @@ pub enum ControllerType {
     Memory,
     HugeTlb,
     Pids,
+    Rdma,
+    Misc,
 }
 
 impl Display for ControllerType {
@@ -19,6 +21,8 @@ impl Display for ControllerType {
             Self::Memory => "memory",
             Self::HugeTlb => "hugetlb",
             Self::Pids => "pids",
+            Self::Rdma => "rdma",
+            Self::Misc => "misc",
         };
 
         write!(f, "{print}")
@@ -32,6 +36,8 @@ pub const CONTROLLER_TYPES: &[ControllerType] = &[
     ControllerType::Io,
     ControllerType::Memory,
     ControllerType::Pids,
+    ControllerType::Rdma,
+    ControllerType::Misc,
 ];
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -0,0 +1,108 @@
+use std::{
+    os::unix::io::{AsRawFd, RawFd},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use nix::{
+    errno::Errno,
+    sys::inotify::{AddWatchFlags, InitFlags, Inotify},
+};
+
+use crate::events::{CgroupEvent, EventCounter, EventListener};
+
+const MEMORY_EVENTS: &str = "memory.events";
+const PIDS_EVENTS: &str = "pids.events";
+
+/// Watches `memory.events` and `pids.events` of a cgroup v2. The kernel
+/// generates a modification event for these files whenever one of their
+/// counters changes.
+pub struct Listener {
+    inotify: Inotify,
+    oom_kill: EventCounter,
+    pids_max: EventCounter,
+}
+
+impl Listener {
+    pub fn new(cgroup_path: &Path) -> Result<Self> {
+        let inotify = Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)
+            .context("failed to initialize inotify")?;
+
+        for file in [MEMORY_EVENTS, PIDS_EVENTS] {
+            let path = cgroup_path.join(file);
+            if path.exists() {
+                inotify
+                    .add_watch(&path, AddWatchFlags::IN_MODIFY)
+                    .with_context(|| format!("failed to watch {path:?}"))?;
+            }
+        }
+
+        Ok(Self {
+            inotify,
+            oom_kill: EventCounter::new(&cgroup_path.join(MEMORY_EVENTS), "oom_kill"),
+            pids_max: EventCounter::new(&cgroup_path.join(PIDS_EVENTS), "max"),
+        })
+    }
+}
+
+impl AsRawFd for Listener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inotify.as_raw_fd()
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = nix::unistd::close(self.inotify.as_raw_fd());
+    }
+}
+
+impl EventListener for Listener {
+    fn read_events(&mut self) -> Result<Vec<CgroupEvent>> {
+        // Drain the queued notifications, the counters tell what actually happened
+        loop {
+            match self.inotify.read_events() {
+                Ok(events) if !events.is_empty() => continue,
+                Ok(_) | Err(Errno::EAGAIN) => break,
+                Err(err) => return Err(err).context("failed to read inotify events"),
+            }
+        }
+
+        let mut events = Vec::new();
+        for _ in 0..self.oom_kill.delta() {
+            events.push(CgroupEvent::Oom);
+        }
+        for _ in 0..self.pids_max.delta() {
+            events.push(CgroupEvent::PidsLimit);
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{create_temp_dir, set_fixture};
+
+    #[test]
+    fn test_listener_reports_events() {
+        let tmp = create_temp_dir("v2_test_listener_reports_events").unwrap();
+        set_fixture(&tmp, MEMORY_EVENTS, "oom 0\noom_kill 0\n").unwrap();
+        set_fixture(&tmp, PIDS_EVENTS, "max 0\n").unwrap();
+
+        let mut listener = Listener::new(&tmp).unwrap();
+        assert!(listener.read_events().unwrap().is_empty());
+
+        set_fixture(&tmp, MEMORY_EVENTS, "oom 1\noom_kill 1\n").unwrap();
+        set_fixture(&tmp, PIDS_EVENTS, "max 2\n").unwrap();
+        assert_eq!(
+            listener.read_events().unwrap(),
+            vec![
+                CgroupEvent::Oom,
+                CgroupEvent::PidsLimit,
+                CgroupEvent::PidsLimit
+            ]
+        );
+    }
+}
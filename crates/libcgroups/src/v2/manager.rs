@@ -18,16 +18,22 @@ use super::{
     },
     cpu::Cpu,
     cpuset::CpuSet,
+    events::Listener,
     freezer::Freezer,
     hugetlb::HugeTlb,
     io::Io,
     memory::Memory,
+    misc::Misc,
     pids::Pids,
+    rdma::Rdma,
     unified::Unified,
     util::{self, CGROUP_SUBTREE_CONTROL},
 };
 use crate::{
-    common::{self, CgroupManager, ControllerOpt, FreezerState, PathBufExt, CGROUP_PROCS},
+    common::{
+        self, CgroupError, CgroupManager, ControllerOpt, FreezerState, PathBufExt, CGROUP_PROCS,
+    },
+    events::EventListener,
     stats::{Stats, StatsProvider},
 };
 
@@ -94,12 +100,12 @@ impl Manager {
 }
 
 impl CgroupManager for Manager {
-    fn add_task(&self, pid: Pid) -> Result<()> {
+    fn add_task(&self, pid: Pid) -> Result<(), CgroupError> {
         self.create_unified_cgroup(pid)?;
         Ok(())
     }
 
-    fn apply(&self, controller_opt: &ControllerOpt) -> Result<()> {
+    fn apply(&self, controller_opt: &ControllerOpt) -> Result<(), CgroupError> {
         for controller in CONTROLLER_TYPES {
             match controller {
                 ControllerType::Cpu => Cpu::apply(controller_opt, &self.full_path)?,
@@ -108,6 +114,8 @@ impl CgroupManager for Manager {
                 ControllerType::Io => Io::apply(controller_opt, &self.full_path)?,
                 ControllerType::Memory => Memory::apply(controller_opt, &self.full_path)?,
                 ControllerType::Pids => Pids::apply(controller_opt, &self.full_path)?,
+                ControllerType::Rdma => Rdma::apply(controller_opt, &self.full_path)?,
+                ControllerType::Misc => Misc::apply(controller_opt, &self.full_path)?,
             }
         }
 
@@ -127,7 +135,7 @@ impl CgroupManager for Manager {
         Ok(())
     }
 
-    fn remove(&self) -> Result<()> {
+    fn remove(&self) -> Result<(), CgroupError> {
         if self.full_path.exists() {
             log::debug!("remove cgroup {:?}", self.full_path);
             let kill_file = self.full_path.join(CGROUP_KILL);
@@ -135,10 +143,13 @@ impl CgroupManager for Manager {
                 fs::write(kill_file, "1").context("failed to kill cgroup")?;
             } else {
                 let procs_path = self.full_path.join(CGROUP_PROCS);
-                let procs = fs::read_to_string(procs_path)?;
+                let procs = fs::read_to_string(&procs_path)
+                    .with_context(|| format!("failed to read {procs_path:?}"))?;
 
                 for line in procs.lines() {
-                    let pid: i32 = line.parse()?;
+                    let pid: i32 = line
+                        .parse()
+                        .with_context(|| format!("invalid pid {line} in {procs_path:?}"))?;
                     let _ = nix::sys::signal::kill(Pid::from_raw(pid), nix::sys::signal::SIGKILL);
                 }
             }
@@ -149,17 +160,17 @@ impl CgroupManager for Manager {
         Ok(())
     }
 
-    fn freeze(&self, state: FreezerState) -> Result<()> {
+    fn freeze(&self, state: FreezerState) -> Result<(), CgroupError> {
         let controller_opt = ControllerOpt {
             resources: &Default::default(),
             freezer_state: Some(state),
             oom_score_adj: None,
             disable_oom_killer: false,
         };
-        Freezer::apply(&controller_opt, &self.full_path)
+        Ok(Freezer::apply(&controller_opt, &self.full_path)?)
     }
 
-    fn stats(&self) -> Result<Stats> {
+    fn stats(&self) -> Result<Stats, CgroupError> {
         let mut stats = Stats::default();
 
         for subsystem in CONTROLLER_TYPES {
@@ -169,6 +180,8 @@ impl CgroupManager for Manager {
                 ControllerType::Pids => stats.pids = Pids::stats(&self.full_path)?,
                 ControllerType::Memory => stats.memory = Memory::stats(&self.full_path)?,
                 ControllerType::Io => stats.blkio = Io::stats(&self.full_path)?,
+                ControllerType::Rdma => stats.rdma = Rdma::stats(&self.full_path)?,
+                ControllerType::Misc => stats.misc = Misc::stats(&self.full_path)?,
                 _ => continue,
             }
         }
@@ -176,7 +189,44 @@ impl CgroupManager for Manager {
         Ok(stats)
     }
 
-    fn get_all_pids(&self) -> Result<Vec<Pid>> {
-        common::get_all_pids(&self.full_path)
+    fn get_all_pids(&self) -> Result<Vec<Pid>, CgroupError> {
+        if !self.full_path.exists() {
+            return Err(CgroupError::NotFound(self.full_path.clone()));
+        }
+
+        Ok(common::get_all_pids(&self.full_path)?)
+    }
+
+    fn is_populated(&self) -> Result<bool, CgroupError> {
+        Ok(util::is_populated(&self.full_path)?)
+    }
+
+    fn event_listener(&self) -> Result<Box<dyn EventListener>, CgroupError> {
+        Ok(Box::new(Listener::new(&self.full_path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{create_temp_dir, set_fixture};
+
+    #[test]
+    fn test_get_all_pids_not_found() -> Result<()> {
+        let tmp = create_temp_dir("test_get_all_pids_not_found")?;
+        let manager = Manager::new(tmp.path().to_path_buf(), PathBuf::from("/youki"))?;
+        let cgroup = tmp.path().join("youki");
+        assert!(matches!(
+            manager.get_all_pids(),
+            Err(CgroupError::NotFound(path)) if path == cgroup
+        ));
+
+        fs::create_dir(&cgroup)?;
+        set_fixture(&cgroup, CGROUP_PROCS, "1\n2\n")?;
+        assert_eq!(
+            manager.get_all_pids()?,
+            vec![Pid::from_raw(1), Pid::from_raw(2)]
+        );
+        Ok(())
     }
 }
@@ -15,6 +15,7 @@ const CGROUP_MEMORY_MAX: &str = "memory.max";
 const CGROUP_MEMORY_LOW: &str = "memory.low";
 const MEMORY_STAT: &str = "memory.stat";
 const MEMORY_PSI: &str = "memory.pressure";
+const MEMORY_EVENTS: &str = "memory.events";
 
 pub struct Memory {}
 
@@ -36,6 +37,7 @@ impl StatsProvider for Memory {
         let stats = MemoryStats {
             memory: Self::get_memory_data(cgroup_path, "memory", "oom")?,
             memswap: Self::get_memory_data(cgroup_path, "memory.swap", "fail")?,
+            oom_kill: Self::get_oom_kill_count(cgroup_path)?,
             hierarchy: true,
             stats: stats::parse_flat_keyed_data(&cgroup_path.join(MEMORY_STAT))?,
             psi: stats::psi_stats(&cgroup_path.join(MEMORY_PSI))
@@ -75,6 +77,11 @@ impl Memory {
         })
     }
 
+    fn get_oom_kill_count(cgroup_path: &Path) -> Result<u64> {
+        let events = stats::parse_flat_keyed_data(&cgroup_path.join(MEMORY_EVENTS))?;
+        Ok(events.get("oom_kill").copied().unwrap_or_default())
+    }
+
     fn set<P: AsRef<Path>>(path: P, val: i64) -> Result<()> {
         if val == 0 {
             Ok(())
@@ -351,4 +358,14 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_get_oom_kill_count() {
+        let tmp = create_temp_dir("test_get_oom_kill_count").expect("create test directory");
+        let events = ["low 0", "high 0", "max 4", "oom 2", "oom_kill 1"].join("\n");
+        set_fixture(&tmp, MEMORY_EVENTS, &events).unwrap();
+
+        let oom_kill = Memory::get_oom_kill_count(&tmp).expect("get oom kill count");
+        assert_eq!(oom_kill, 1);
+    }
 }
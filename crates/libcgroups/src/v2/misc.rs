@@ -0,0 +1,193 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    common::{self, ControllerOpt},
+    stats::{self, MiscStats, StatsProvider},
+};
+
+use super::controller::Controller;
+
+pub(super) const CGROUP_MISC_MAX: &str = "misc.max";
+const CGROUP_MISC_CURRENT: &str = "misc.current";
+const CGROUP_MISC_EVENTS: &str = "misc.events";
+
+/// The misc controller limits scalar resources that cannot be abstracted like
+/// the other controllers, e.g. AMD SEV ASIDs. The runtime spec has no field for
+/// these resources, so the limits are taken from the misc.max entry of the
+/// unified resources, which contains one "<resource> <limit>" pair per line.
+pub struct Misc {}
+
+impl Controller for Misc {
+    fn apply(controller_opt: &ControllerOpt, cgroup_root: &Path) -> Result<()> {
+        if let Some(misc_max) = controller_opt
+            .resources
+            .unified()
+            .as_ref()
+            .and_then(|unified| unified.get(CGROUP_MISC_MAX))
+        {
+            log::debug!("Apply misc cgroup v2 config");
+            Self::apply(cgroup_root, misc_max)
+                .context("failed to apply misc resource restrictions")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl StatsProvider for Misc {
+    type Stats = HashMap<String, MiscStats>;
+
+    fn stats(cgroup_path: &Path) -> Result<Self::Stats> {
+        let mut misc_stats = HashMap::new();
+        let current_path = cgroup_path.join(CGROUP_MISC_CURRENT);
+        if !current_path.exists() {
+            return Ok(misc_stats);
+        }
+
+        let limits = Self::parse_limits(&cgroup_path.join(CGROUP_MISC_MAX))?;
+        // misc.events contains a "<resource>.max" counter for each resource
+        let events_path = cgroup_path.join(CGROUP_MISC_EVENTS);
+        let events = if events_path.exists() {
+            stats::parse_flat_keyed_data(&events_path)?
+        } else {
+            HashMap::new()
+        };
+
+        for (resource, usage) in stats::parse_flat_keyed_data(&current_path)? {
+            let stats = MiscStats {
+                usage,
+                limit: limits.get(&resource).copied().unwrap_or(u64::MAX),
+                fail_count: events
+                    .get(&format!("{resource}.max"))
+                    .copied()
+                    .unwrap_or_default(),
+            };
+            misc_stats.insert(resource, stats);
+        }
+
+        Ok(misc_stats)
+    }
+}
+
+impl Misc {
+    fn apply(root_path: &Path, misc_max: &str) -> Result<()> {
+        // the kernel only accepts the limit of a single resource per write
+        for (resource, limit) in Self::parse_max_entries(misc_max)? {
+            common::write_cgroup_file_str(
+                root_path.join(CGROUP_MISC_MAX),
+                &format!("{resource} {limit}"),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn parse_max_entries(misc_max: &str) -> Result<Vec<(&str, &str)>> {
+        let mut entries = Vec::new();
+        for line in misc_max.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(resource), Some(limit), None) => {
+                    if limit != "max" {
+                        limit
+                            .parse::<u64>()
+                            .with_context(|| format!("invalid limit {limit} for {resource}"))?;
+                    }
+                    entries.push((resource, limit));
+                }
+                _ => bail!(
+                    "invalid misc limit {:?}, expected \"<resource> <limit>\"",
+                    line
+                ),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn parse_limits(path: &Path) -> Result<HashMap<String, u64>> {
+        let mut limits = HashMap::new();
+        if !path.exists() {
+            return Ok(limits);
+        }
+
+        for line in common::read_cgroup_file(path)?.lines() {
+            if let Some((resource, limit)) = line.split_once(' ') {
+                let limit = match limit.trim() {
+                    "max" => u64::MAX,
+                    l => stats::parse_value(l)?,
+                };
+                limits.insert(resource.to_owned(), limit);
+            }
+        }
+
+        Ok(limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{create_temp_dir, set_fixture};
+
+    #[test]
+    fn test_set_misc() {
+        let tmp = create_temp_dir("v2_test_set_misc").expect("create temp directory for test");
+        set_fixture(&tmp, CGROUP_MISC_MAX, "").expect("set fixture for misc.max");
+
+        Misc::apply(&tmp, "sev 5\n").expect("apply misc");
+        let content = std::fs::read_to_string(tmp.join(CGROUP_MISC_MAX)).expect("read misc.max");
+        assert_eq!(content, "sev 5");
+    }
+
+    #[test]
+    fn test_parse_misc_max_entries() {
+        assert_eq!(
+            Misc::parse_max_entries("sev 5\n sev_es max\n\n").expect("parse misc.max"),
+            vec![("sev", "5"), ("sev_es", "max")]
+        );
+
+        for misc_max in ["sev", "sev 5 6", "sev -1", "sev unlimited"] {
+            assert!(
+                Misc::parse_max_entries(misc_max).is_err(),
+                "{misc_max:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_stat_misc() {
+        let tmp = create_temp_dir("v2_test_stat_misc").expect("create temp directory for test");
+        set_fixture(&tmp, CGROUP_MISC_CURRENT, "sev 2\nsev_es 0\n").unwrap();
+        set_fixture(&tmp, CGROUP_MISC_MAX, "sev 5\nsev_es max\n").unwrap();
+        set_fixture(&tmp, CGROUP_MISC_EVENTS, "sev.max 1\nsev_es.max 0\n").unwrap();
+
+        let stats = Misc::stats(&tmp).expect("get cgroup stats");
+        assert_eq!(
+            stats.get("sev"),
+            Some(&MiscStats {
+                usage: 2,
+                limit: 5,
+                fail_count: 1,
+            })
+        );
+        assert_eq!(
+            stats.get("sev_es"),
+            Some(&MiscStats {
+                usage: 0,
+                limit: u64::MAX,
+                fail_count: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_stat_misc_not_available() {
+        let tmp = create_temp_dir("v2_test_stat_misc_not_available")
+            .expect("create temp directory for test");
+        let stats = Misc::stats(&tmp).expect("get cgroup stats");
+        assert!(stats.is_empty());
+    }
+}
@@ -1,14 +1,17 @@
-mod controller;
+pub(crate) mod controller;
 pub mod controller_type;
 mod cpu;
 mod cpuset;
 #[cfg(feature = "cgroupsv2_devices")]
 pub mod devices;
+pub mod events;
 mod freezer;
-mod hugetlb;
+pub(crate) mod hugetlb;
 mod io;
 pub mod manager;
 mod memory;
+mod misc;
 mod pids;
+mod rdma;
 mod unified;
 pub mod util;
@@ -0,0 +1,72 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    common::{self, ControllerOpt},
+    stats::{self, RdmaStats, StatsProvider},
+};
+
+use super::controller::Controller;
+use oci_spec::runtime::LinuxRdma;
+
+const CGROUP_RDMA_MAX: &str = "rdma.max";
+
+pub struct Rdma {}
+
+impl Controller for Rdma {
+    fn apply(controller_opt: &ControllerOpt, cgroup_root: &Path) -> Result<()> {
+        log::debug!("Apply rdma cgroup v2 config");
+        if let Some(rdma) = controller_opt.resources.rdma() {
+            Self::apply(cgroup_root, rdma).context("failed to apply rdma resource restrictions")?;
+        }
+        Ok(())
+    }
+}
+
+impl StatsProvider for Rdma {
+    type Stats = RdmaStats;
+
+    fn stats(cgroup_path: &Path) -> Result<Self::Stats> {
+        stats::rdma_stats(cgroup_path)
+    }
+}
+
+impl Rdma {
+    fn apply(root_path: &Path, rdma: &HashMap<String, LinuxRdma>) -> Result<()> {
+        for (device, limits) in rdma {
+            common::write_cgroup_file_str(
+                root_path.join(CGROUP_RDMA_MAX),
+                &common::format_rdma_limits(device, limits),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{create_temp_dir, set_fixture};
+    use oci_spec::runtime::LinuxRdmaBuilder;
+
+    #[test]
+    fn test_set_rdma() {
+        let tmp = create_temp_dir("v2_test_set_rdma").expect("create temp directory for test");
+        set_fixture(&tmp, CGROUP_RDMA_MAX, "").expect("set fixture for rdma.max");
+
+        let mut rdma = HashMap::new();
+        rdma.insert(
+            "mlx5_1".to_owned(),
+            LinuxRdmaBuilder::default()
+                .hca_handles(3u32)
+                .hca_objects(10000u32)
+                .build()
+                .unwrap(),
+        );
+
+        Rdma::apply(&tmp, &rdma).expect("apply rdma");
+        let content = std::fs::read_to_string(tmp.join(CGROUP_RDMA_MAX)).expect("read rdma.max");
+        assert_eq!(content, "mlx5_1 hca_handle=3 hca_object=10000");
+    }
+}
@@ -2,7 +2,7 @@ use std::{collections::HashMap, path::Path};
 
 use anyhow::{Context, Result};
 
-use super::controller_type::ControllerType;
+use super::{controller_type::ControllerType, misc::CGROUP_MISC_MAX};
 use crate::common::{self, ControllerOpt};
 
 pub struct Unified {}
@@ -28,7 +28,12 @@ impl Unified {
     ) -> Result<()> {
         {
             log::debug!("Apply unified cgroup config");
-            for (cgroup_file, value) in unified {
+            // misc.max may hold several limits, which are written one by one by
+            // the misc controller
+            for (cgroup_file, value) in unified
+                .iter()
+                .filter(|(cgroup_file, _)| *cgroup_file != CGROUP_MISC_MAX)
+            {
                 common::write_cgroup_file_str(cgroup_path.join(cgroup_file), value).map_err(
                     |e| {
                         let (subsystem, _) = cgroup_file
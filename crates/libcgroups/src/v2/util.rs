@@ -38,9 +38,47 @@ pub fn get_available_controllers<P: AsRef<Path>>(root_path: P) -> Result<Vec<Con
             "io" => controllers.push(ControllerType::Io),
             "memory" => controllers.push(ControllerType::Memory),
             "pids" => controllers.push(ControllerType::Pids),
+            "rdma" => controllers.push(ControllerType::Rdma),
+            "misc" => controllers.push(ControllerType::Misc),
             tpe => log::warn!("Controller {} is not yet implemented.", tpe),
         }
     }
 
     Ok(controllers)
 }
+
+pub const CGROUP_EVENTS: &str = "cgroup.events";
+
+/// Checks if the cgroup or any of its descendants contain a live process,
+/// which is reported by the `populated` key of `cgroup.events`
+pub fn is_populated<P: AsRef<Path>>(cgroup_path: P) -> Result<bool> {
+    let events = common::read_cgroup_file(cgroup_path.as_ref().join(CGROUP_EVENTS))?;
+    for line in events.lines() {
+        if let Some(populated) = line.strip_prefix("populated ") {
+            return Ok(populated.trim() == "1");
+        }
+    }
+
+    bail!("no populated key in {:?}", cgroup_path.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{create_temp_dir, set_fixture};
+
+    #[test]
+    fn test_is_populated() -> Result<()> {
+        let tmp = create_temp_dir("test_is_populated")?;
+
+        set_fixture(&tmp, CGROUP_EVENTS, "populated 1\nfrozen 0\n")?;
+        assert!(is_populated(&*tmp)?);
+
+        set_fixture(&tmp, CGROUP_EVENTS, "populated 0\nfrozen 0\n")?;
+        assert!(!is_populated(&*tmp)?);
+
+        set_fixture(&tmp, CGROUP_EVENTS, "frozen 0\n")?;
+        assert!(is_populated(&*tmp).is_err());
+        Ok(())
+    }
+}
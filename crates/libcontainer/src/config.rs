@@ -7,7 +7,7 @@ use std::{
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use oci_spec::runtime::{Hooks, Spec};
+use oci_spec::runtime::{Hooks, LinuxResources, Spec};
 
 use crate::utils;
 
@@ -20,6 +20,9 @@ const YOUKI_CONFIG_NAME: &str = "youki_config.json";
 pub struct YoukiConfig {
     pub hooks: Option<Hooks>,
     pub cgroup_path: PathBuf,
+    /// Resource limits currently in effect. Starts out as the resources of the spec
+    /// and is kept up to date by `update`.
+    pub resources: Option<LinuxResources>,
 }
 
 impl<'a> YoukiConfig {
@@ -34,6 +37,10 @@ impl<'a> YoukiConfig {
                 container_id,
                 rootless,
             ),
+            resources: spec
+                .linux()
+                .as_ref()
+                .and_then(|linux| linux.resources().clone()),
         })
     }
 
@@ -84,4 +91,18 @@ mod tests {
         assert_eq!(act, config);
         Ok(())
     }
+
+    #[test]
+    fn test_config_load_without_resources() -> Result<()> {
+        let tmp =
+            create_temp_dir("test_config_load_without_resources").expect("create test directory");
+        fs::write(
+            tmp.join(YOUKI_CONFIG_NAME),
+            r#"{"hooks":null,"cgroup_path":"sample"}"#,
+        )?;
+        let config = YoukiConfig::load(&tmp)?;
+        assert_eq!(config.cgroup_path, PathBuf::from("sample"));
+        assert!(config.resources.is_none());
+        Ok(())
+    }
 }
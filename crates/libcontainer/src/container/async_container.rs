@@ -0,0 +1,272 @@
+//! Asynchronous facade over the container lifecycle
+//!
+//! The methods of [`Container`] block the calling thread, e.g. while waiting
+//! for the processes of a container to stop or for cgroup events. The types in
+//! this module run them on a dedicated thread instead and return futures that
+//! complete once the operation has finished, so they can be awaited from any
+//! executor (tokio, async-std, ...) without blocking it. Dropping a future does
+//! not cancel the operation, it still runs to completion in the background.
+use std::{
+    path::PathBuf,
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+use futures::{
+    channel::{mpsc, oneshot},
+    executor::block_on,
+    SinkExt, Stream,
+};
+use libcgroups::stats::Stats;
+use nix::unistd::Pid;
+
+use super::{Container, Event, ExitStatus};
+use crate::{error::LibcontainerError, signal::Signal};
+
+/// Number of events that are buffered if the consumer of an
+/// [`AsyncEventStream`] does not keep up
+const EVENT_BUFFER: usize = 16;
+
+/// Runs `f` on a new thread and returns its result once it has finished. This
+/// can be used to run anything that is not covered by [`AsyncContainer`],
+/// e.g. a tenant container builder.
+pub async fn spawn_blocking<T, F>(f: F) -> Result<T, LibcontainerError>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    thread::Builder::new()
+        .name("youki-blocking".to_owned())
+        .spawn(move || {
+            // the future might have been dropped in the meantime
+            let _ = sender.send(f());
+        })
+        .context("failed to spawn thread")?;
+
+    receiver
+        .await
+        .map_err(|_| anyhow!("blocking operation panicked").into())
+}
+
+/// A [`Container`] whose lifecycle operations can be awaited
+///
+/// # Example
+///
+/// ```no_run
+/// use libcontainer::container::async_container::AsyncContainer;
+/// use libcontainer::container::builder::ContainerBuilder;
+/// use libcontainer::syscall::syscall::create_syscall;
+/// use nix::sys::signal::Signal;
+///
+/// # async fn run() -> anyhow::Result<()> {
+/// let mut container = AsyncContainer::create(|| {
+///     ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+///         .as_init("/var/run/docker/bundle")
+///         .build()
+/// })
+/// .await?;
+///
+/// container.start().await?;
+/// container.kill(Signal::SIGTERM, false).await?;
+/// let exit_status = container.wait().await?;
+/// container.delete(false).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AsyncContainer {
+    container: Container,
+}
+
+impl From<Container> for AsyncContainer {
+    fn from(container: Container) -> Self {
+        Self { container }
+    }
+}
+
+impl AsyncContainer {
+    /// Creates a new container. The builder has to be set up by `build`,
+    /// because it borrows the syscall interface, which cannot be sent to the
+    /// thread the container is created on.
+    pub async fn create<F>(build: F) -> Result<Self, LibcontainerError>
+    where
+        F: FnOnce() -> Result<Container, LibcontainerError> + Send + 'static,
+    {
+        spawn_blocking(build).await?.map(Self::from)
+    }
+
+    /// Loads an existing container, see [`Container::load`]
+    pub async fn load(container_root: PathBuf) -> Result<Self, LibcontainerError> {
+        spawn_blocking(move || Container::load(container_root))
+            .await?
+            .map(Self::from)
+    }
+
+    /// Returns the container as it was after the last completed operation
+    pub fn container(&self) -> &Container {
+        &self.container
+    }
+
+    pub fn into_inner(self) -> Container {
+        self.container
+    }
+
+    /// See [`Container::start`]
+    pub async fn start(&mut self) -> Result<(), LibcontainerError> {
+        self.run(|container| container.start()).await
+    }
+
+    /// See [`Container::kill`]
+    pub async fn kill<S: Into<Signal>>(
+        &mut self,
+        signal: S,
+        all: bool,
+    ) -> Result<(), LibcontainerError> {
+        let signal = signal.into();
+        self.run(move |container| container.kill(signal, all)).await
+    }
+
+    /// See [`Container::stop`]
+    pub async fn stop(
+        &mut self,
+        signal: Option<Signal>,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Pid>, LibcontainerError> {
+        self.run(move |container| container.stop(signal, timeout))
+            .await
+    }
+
+    /// See [`Container::delete`]
+    pub async fn delete(&mut self, force: bool) -> Result<(), LibcontainerError> {
+        self.run(move |container| container.delete(force)).await
+    }
+
+    /// See [`Container::wait`]
+    pub async fn wait(&mut self) -> Result<Option<ExitStatus>, LibcontainerError> {
+        self.run(|container| container.wait()).await
+    }
+
+    /// See [`Container::stats`]
+    pub async fn stats(&self) -> Result<Stats, LibcontainerError> {
+        let container = self.container.clone();
+        spawn_blocking(move || container.stats()).await?
+    }
+
+    /// Returns a stream of the events of the running container, see
+    /// [`Container::event_stream`]. The events are collected on a separate
+    /// thread, which exits after the stream has been dropped and the next
+    /// event occurred.
+    pub fn event_stream(&self, interval: Duration) -> Result<AsyncEventStream, LibcontainerError> {
+        let (mut sender, receiver) = mpsc::channel(EVENT_BUFFER);
+        let mut container = self.container.clone();
+        thread::Builder::new()
+            .name("youki-events".to_owned())
+            .spawn(move || {
+                let events = match container.event_stream(interval) {
+                    Ok(events) => events,
+                    Err(err) => {
+                        let _ = block_on(sender.send(Err(err)));
+                        return;
+                    }
+                };
+
+                for event in events {
+                    let event = event.map_err(LibcontainerError::from);
+                    if block_on(sender.send(event)).is_err() {
+                        // the stream has been dropped
+                        break;
+                    }
+                }
+            })
+            .context("failed to spawn event thread")?;
+
+        Ok(AsyncEventStream { receiver })
+    }
+
+    /// Runs the operation on a copy of the container and stores the updated
+    /// container once it completed
+    async fn run<T, F>(&mut self, operation: F) -> Result<T, LibcontainerError>
+    where
+        F: FnOnce(&mut Container) -> Result<T, LibcontainerError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let mut container = self.container.clone();
+        let (result, container) = spawn_blocking(move || {
+            let result = operation(&mut container);
+            (result, container)
+        })
+        .await?;
+
+        self.container = container;
+        result
+    }
+}
+
+/// Stream of the events of a running container, created by
+/// [`AsyncContainer::event_stream`]. The stream ends after the `Stopped`
+/// event or the first error.
+pub struct AsyncEventStream {
+    receiver: mpsc::Receiver<Result<Event, LibcontainerError>>,
+}
+
+impl Stream for AsyncEventStream {
+    type Item = Result<Event, LibcontainerError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::ContainerStatus;
+    use futures::StreamExt;
+
+    #[test]
+    fn test_spawn_blocking() -> anyhow::Result<()> {
+        let result = block_on(spawn_blocking(|| {
+            thread::current().name().map(str::to_owned)
+        }))?;
+        assert_eq!(result.as_deref(), Some("youki-blocking"));
+
+        let result = block_on(spawn_blocking(|| panic!("expected panic")));
+        assert!(matches!(result, Err(LibcontainerError::Other(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_operation_updates_container() {
+        let mut container = AsyncContainer::from(Container::default());
+        container.container.set_status(ContainerStatus::Created);
+
+        // the container has no init process, so it is found to be stopped
+        let result = block_on(container.start());
+        assert!(matches!(
+            result,
+            Err(LibcontainerError::InvalidStatus {
+                status: ContainerStatus::Stopped,
+                operation: "started",
+                ..
+            })
+        ));
+        assert_eq!(container.container().status(), ContainerStatus::Stopped);
+    }
+
+    #[test]
+    fn test_event_stream_error() -> anyhow::Result<()> {
+        let container = AsyncContainer::from(Container::default());
+        let mut events = container.event_stream(Duration::from_secs(1))?;
+        let event = block_on(events.next());
+        assert!(matches!(
+            event,
+            Some(Err(LibcontainerError::InvalidStatus { .. }))
+        ));
+        assert!(block_on(events.next()).is_none());
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
-use crate::{syscall::Syscall, utils::PathBufExt};
-use anyhow::{anyhow, Context, Result};
+use crate::{
+    error::LibcontainerError,
+    syscall::Syscall,
+    utils::PathBufExt,
+    workload::{Executor, ExecutorRegistry},
+};
+use anyhow::{Context, Result};
 use std::path::PathBuf;
 
 use super::{init_builder::InitContainerBuilder, tenant_builder::TenantContainerBuilder};
@@ -18,6 +23,8 @@ pub struct ContainerBuilder<'a> {
     pub(super) console_socket: Option<PathBuf>,
     /// File descriptors to be passed into the container process
     pub(super) preserve_fds: i32,
+    /// Executors which are able to run the container workload
+    pub(super) executors: ExecutorRegistry,
 }
 
 /// Builder that can be used to configure the common properties of
@@ -57,6 +64,7 @@ impl<'a> ContainerBuilder<'a> {
             pid_file: None,
             console_socket: None,
             preserve_fds: 0,
+            executors: ExecutorRegistry::default(),
         }
     }
 
@@ -77,18 +85,18 @@ impl<'a> ContainerBuilder<'a> {
     ///
     /// In addition, IDs that can't be used to represent a file name
     /// (such as . or ..) are rejected.
-    pub fn validate_id(self) -> Result<Self> {
+    pub fn validate_id(self) -> Result<Self, LibcontainerError> {
         let container_id = self.container_id.clone();
         if container_id.is_empty() {
-            return Err(anyhow!("invalid container ID format: {:?}", container_id));
+            return Err(LibcontainerError::InvalidId(container_id));
         }
         if container_id == "." || container_id == ".." {
-            return Err(anyhow!("invalid container ID format: {:?}", container_id));
+            return Err(LibcontainerError::InvalidId(container_id));
         }
         for c in container_id.chars() {
             match c {
                 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '+' | '-' | '.' => (),
-                _ => return Err(anyhow!("invalid container ID format: {:?}", container_id)),
+                _ => return Err(LibcontainerError::InvalidId(container_id)),
             }
         }
         Ok(self)
@@ -138,7 +146,7 @@ impl<'a> ContainerBuilder<'a> {
     /// ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
     /// .with_root_path("/run/containers/youki").expect("invalid root path");
     /// ```
-    pub fn with_root_path<P: Into<PathBuf>>(mut self, path: P) -> Result<Self> {
+    pub fn with_root_path<P: Into<PathBuf>>(mut self, path: P) -> Result<Self, LibcontainerError> {
         let path = path.into();
         self.root_path = path
             .canonicalize_safely()
@@ -158,7 +166,10 @@ impl<'a> ContainerBuilder<'a> {
     /// ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
     /// .with_pid_file(Some("/var/run/docker.pid")).expect("invalid pid file");
     /// ```
-    pub fn with_pid_file<P: Into<PathBuf>>(mut self, path: Option<P>) -> Result<Self> {
+    pub fn with_pid_file<P: Into<PathBuf>>(
+        mut self,
+        path: Option<P>,
+    ) -> Result<Self, LibcontainerError> {
         self.pid_file = match path {
             Some(path) => {
                 let p = path.into();
@@ -204,11 +215,31 @@ impl<'a> ContainerBuilder<'a> {
         self.preserve_fds = preserved_fds;
         self
     }
+
+    /// Registers an additional executor for the container workload. Executors
+    /// are tried in the order of their priority, the first one that is able to
+    /// handle the workload executes it. The built-in wasm executors have priority
+    /// `WASM_PRIORITY`, the default executor comes last.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use libcontainer::container::builder::ContainerBuilder;
+    /// # use libcontainer::syscall::syscall::create_syscall;
+    /// # use libcontainer::workload::{default::DefaultExecutor, WASM_PRIORITY};
+    ///
+    /// ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+    /// .with_executor(WASM_PRIORITY + 1, DefaultExecutor {});
+    /// ```
+    pub fn with_executor<E: Executor + 'static>(mut self, priority: i32, executor: E) -> Self {
+        self.executors.register(priority, Box::new(executor));
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::container::builder::ContainerBuilder;
+    use crate::error::LibcontainerError;
     use crate::syscall::syscall::create_syscall;
     use crate::utils::TempDir;
     use anyhow::{Context, Result};
@@ -266,7 +297,7 @@ mod tests {
         let syscall = create_syscall();
         // validate container_id
         let result = ContainerBuilder::new("$#".to_owned(), syscall.as_ref()).validate_id();
-        assert!(result.is_err());
+        assert!(matches!(result, Err(LibcontainerError::InvalidId(id)) if id == "$#"));
 
         let result = ContainerBuilder::new(".".to_owned(), syscall.as_ref()).validate_id();
         assert!(result.is_err());
@@ -1,14 +1,15 @@
 use super::{Container, ContainerStatus};
 use crate::{
-    hooks,
     notify_socket::NotifyListener,
     process::{
         self,
         args::{ContainerArgs, ContainerType},
+        pidfd,
     },
     rootless::Rootless,
     syscall::Syscall,
     utils,
+    workload::ExecutorRegistry,
 };
 use anyhow::{bail, Context, Result};
 use nix::unistd::Pid;
@@ -43,6 +44,8 @@ pub(super) struct ContainerBuilderImpl<'a> {
     pub preserve_fds: i32,
     /// If the container is to be run in detached mode
     pub detached: bool,
+    /// Executors which are able to run the container workload
+    pub executors: ExecutorRegistry,
 }
 
 impl<'a> ContainerBuilderImpl<'a> {
@@ -65,18 +68,19 @@ impl<'a> ContainerBuilderImpl<'a> {
             &self.container_id,
             self.rootless.is_some(),
         );
-        let cmanager = libcgroups::common::create_cgroup_manager(
+        let annotations = self.spec.annotations().clone().unwrap_or_default();
+        let cmanager = libcgroups::common::create_cgroup_manager_with_annotations(
             &cgroups_path,
             self.use_systemd || self.rootless.is_some(),
             &self.container_id,
+            &annotations,
         )?;
         let process = self.spec.process().as_ref().context("No process in spec")?;
-
-        if matches!(self.container_type, ContainerType::InitContainer) {
-            if let Some(hooks) = self.spec.hooks() {
-                hooks::run_hooks(hooks.create_runtime().as_ref(), self.container.as_ref())?
-            }
-        }
+        let executor = self
+            .executors
+            .select(self.spec)
+            .context("failed to select executor for the workload")?;
+        let executor_name = executor.name().to_owned();
 
         // Need to create the notify socket before we pivot root, since the unix
         // domain socket used here is outside of the rootfs of container. During
@@ -126,6 +130,7 @@ impl<'a> ContainerBuilderImpl<'a> {
             rootless: &self.rootless,
             cgroup_manager: cmanager,
             detached: self.detached,
+            executor,
         };
 
         let (intermediate, init_pid) =
@@ -137,11 +142,18 @@ impl<'a> ContainerBuilderImpl<'a> {
         }
 
         if let Some(container) = &mut self.container {
+            // The start time together with the pid identifies the init process,
+            // so that a process that reuses the pid later on is not mistaken for it.
+            let init_process_start = pidfd::process_start_time(init_pid)
+                .context("failed to get start time of init process")?;
+
             // update status and pid of the container process
             container
                 .set_status(ContainerStatus::Created)
                 .set_creator(nix::unistd::geteuid().as_raw())
                 .set_pid(init_pid.as_raw())
+                .set_init_process_start(init_process_start)
+                .set_executor(executor_name)
                 .save()
                 .context("Failed to save container state")?;
         }
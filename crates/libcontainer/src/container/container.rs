@@ -2,8 +2,9 @@ use std::collections::HashMap;
 use std::ffi::OsString;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use chrono::DateTime;
 use nix::unistd::Pid;
 
@@ -11,9 +12,10 @@ use chrono::Utc;
 use procfs::process::Process;
 
 use crate::config::YoukiConfig;
+use crate::error::LibcontainerError;
 use crate::syscall::syscall::create_syscall;
 
-use crate::container::{ContainerStatus, State};
+use crate::container::{container_wait::read_exit_status, ContainerStatus, ExitStatus, State};
 
 /// Structure representing the container data
 #[derive(Debug, Clone)]
@@ -96,6 +98,15 @@ impl Container {
         self
     }
 
+    pub fn init_process_start(&self) -> Option<u64> {
+        self.state.init_process_start
+    }
+
+    pub fn set_init_process_start(&mut self, start_time: u64) -> &mut Self {
+        self.state.init_process_start = Some(start_time);
+        self
+    }
+
     pub fn created(&self) -> Option<DateTime<Utc>> {
         self.state.created
     }
@@ -126,6 +137,49 @@ impl Container {
         self
     }
 
+    pub fn executor(&self) -> Option<&str> {
+        self.state.executor.as_deref()
+    }
+
+    pub fn set_executor<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.state.executor = Some(name.into());
+        self
+    }
+
+    /// Returns how the init process terminated, if its exit has been
+    /// observed and the status could be collected
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        match (self.state.exit_status, &self.state.exit_signal) {
+            (_, Some(signal)) => signal.parse().ok().map(ExitStatus::Signaled),
+            (Some(code), None) => Some(ExitStatus::Exited(code)),
+            (None, None) => None,
+        }
+    }
+
+    /// Records that the init process has terminated now with the exit status
+    pub fn set_exit_status(&mut self, exit_status: Option<ExitStatus>) -> &mut Self {
+        self.state.exit_status = exit_status.as_ref().map(ExitStatus::code);
+        self.state.exit_signal = match exit_status {
+            Some(ExitStatus::Signaled(signal)) => Some(signal.as_str().to_owned()),
+            _ => None,
+        };
+        self.state.finished = Some(Utc::now());
+        self
+    }
+
+    pub fn finished(&self) -> Option<DateTime<Utc>> {
+        self.state.finished
+    }
+
+    pub fn oom_killed(&self) -> Option<bool> {
+        self.state.oom_killed
+    }
+
+    pub fn set_oom_killed(&mut self, oom_killed: Option<bool>) -> &mut Self {
+        self.state.oom_killed = oom_killed;
+        self
+    }
+
     pub fn status(&self) -> ContainerStatus {
         self.state.status
     }
@@ -151,8 +205,16 @@ impl Container {
                 if let Ok(proc) = Process::new(pid.as_raw()) {
                     use procfs::process::ProcState;
 
-                    match proc.stat()?.state()? {
+                    let stat = proc.stat()?;
+                    match stat.state()? {
                         ProcState::Zombie | ProcState::Dead => ContainerStatus::Stopped,
+                        // the init process has exited and its pid has been reused
+                        _ if self
+                            .init_process_start()
+                            .map_or(false, |start| start != stat.starttime) =>
+                        {
+                            ContainerStatus::Stopped
+                        }
                         _ => match self.status() {
                             ContainerStatus::Creating
                             | ContainerStatus::Created
@@ -167,6 +229,16 @@ impl Container {
             None => ContainerStatus::Stopped,
         };
 
+        // The first time the init process is found to have exited, record how
+        // it exited. Only a zombie still has its exit status, so it might be
+        // unknown, but the cgroup can tell if the oom killer was involved.
+        if new_status == ContainerStatus::Stopped && self.finished().is_none() {
+            if let Some(pid) = self.pid() {
+                let exit_status = read_exit_status(pid, self.init_process_start());
+                self.record_exit(exit_status);
+            }
+        }
+
         self.set_status(new_status);
         Ok(())
     }
@@ -178,13 +250,35 @@ impl Container {
         Ok(self)
     }
 
-    pub fn load(container_root: PathBuf) -> Result<Self> {
+    /// Loads the container from its state directory. Returns
+    /// [`LibcontainerError::NotFound`] if the container does not exist.
+    pub fn load(container_root: PathBuf) -> Result<Self, LibcontainerError> {
+        if !State::file_path(&container_root).exists() {
+            let id = container_root
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            return Err(LibcontainerError::NotFound(id));
+        }
+
         let state = State::load(&container_root)?;
         let mut container = Self {
             state,
             root: container_root,
         };
+        let finished = container.finished();
         container.refresh_status()?;
+        // Persist the exit if it has just been observed. Commands that only
+        // read the state might not be allowed to write it, which is fine.
+        if container.finished() != finished {
+            if let Err(err) = container.save() {
+                log::debug!(
+                    "failed to record the exit of container {}: {:?}",
+                    container.id(),
+                    err
+                );
+            }
+        }
         Ok(container)
     }
 
@@ -201,12 +295,60 @@ impl Container {
 
 /// Checkpoint parameter structure
 pub struct CheckpointOptions {
+    pub auto_dedup: bool,
+    pub empty_ns: bool,
     pub ext_unix_sk: bool,
     pub file_locks: bool,
     pub image_path: PathBuf,
+    pub lazy_pages: bool,
     pub leave_running: bool,
+    pub manage_cgroups_mode: Option<ManageCgroupsMode>,
+    pub page_server: Option<String>,
+    pub parent_path: Option<PathBuf>,
+    pub pre_dump: bool,
+    pub shell_job: bool,
+    pub status_fd: Option<i32>,
+    pub tcp_established: bool,
+    pub work_path: Option<PathBuf>,
+}
+
+/// How CRIU should handle the cgroups of the checkpointed container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManageCgroupsMode {
+    /// Only restore the cgroup properties if the cgroup was newly created
+    Soft,
+    /// Always restore all cgroup properties
+    Full,
+    /// Restore all properties and fail if a cgroup already exists
+    Strict,
+    /// Do not dump or restore cgroups at all
+    Ignore,
+}
+
+impl FromStr for ManageCgroupsMode {
+    type Err = anyhow::Error;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "soft" => Ok(ManageCgroupsMode::Soft),
+            "full" => Ok(ManageCgroupsMode::Full),
+            "strict" => Ok(ManageCgroupsMode::Strict),
+            "ignore" => Ok(ManageCgroupsMode::Ignore),
+            _ => bail!("invalid manage cgroups mode: {}", mode),
+        }
+    }
+}
+
+/// Restore parameter structure
+pub struct RestoreOptions {
+    pub bundle: PathBuf,
+    pub ext_unix_sk: bool,
+    pub file_locks: bool,
+    pub image_path: PathBuf,
+    pub pid_file: Option<PathBuf>,
     pub shell_job: bool,
     pub tcp_established: bool,
+    pub use_systemd: bool,
     pub work_path: Option<PathBuf>,
 }
 
@@ -215,6 +357,7 @@ mod tests {
     use super::*;
     use crate::utils::create_temp_dir;
     use anyhow::Context;
+    use nix::sys::signal::Signal;
     use serial_test::serial;
 
     #[test]
@@ -285,6 +428,52 @@ mod tests {
         assert_eq!(container.creator(), Some(OsString::from("youki")));
     }
 
+    #[test]
+    fn test_get_set_exit_status() {
+        let mut container = Container::default();
+        assert_eq!(container.exit_status(), None);
+        assert_eq!(container.finished(), None);
+
+        container.set_exit_status(Some(ExitStatus::Exited(3)));
+        assert_eq!(container.exit_status(), Some(ExitStatus::Exited(3)));
+        assert_eq!(container.state.exit_signal, None);
+        assert!(container.finished().is_some());
+
+        container.set_exit_status(Some(ExitStatus::Signaled(Signal::SIGKILL)));
+        assert_eq!(
+            container.exit_status(),
+            Some(ExitStatus::Signaled(Signal::SIGKILL))
+        );
+        assert_eq!(container.state.exit_status, Some(137));
+        assert_eq!(container.state.exit_signal.as_deref(), Some("SIGKILL"));
+
+        // the exit has been observed, but the status is unknown
+        container.set_exit_status(None);
+        assert_eq!(container.exit_status(), None);
+        assert!(container.finished().is_some());
+    }
+
+    #[test]
+    fn test_parse_manage_cgroups_mode() {
+        assert_eq!(
+            "soft".parse::<ManageCgroupsMode>().unwrap(),
+            ManageCgroupsMode::Soft
+        );
+        assert_eq!(
+            "full".parse::<ManageCgroupsMode>().unwrap(),
+            ManageCgroupsMode::Full
+        );
+        assert_eq!(
+            "strict".parse::<ManageCgroupsMode>().unwrap(),
+            ManageCgroupsMode::Strict
+        );
+        assert_eq!(
+            "ignore".parse::<ManageCgroupsMode>().unwrap(),
+            ManageCgroupsMode::Ignore
+        );
+        assert!("props".parse::<ManageCgroupsMode>().is_err());
+    }
+
     #[test]
     #[serial]
     fn test_refresh_load_save_state() -> Result<()> {
@@ -310,6 +499,14 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_load_not_found() -> Result<()> {
+        let tmp_dir = create_temp_dir("test_load_not_found")?;
+        let err = Container::load(tmp_dir.path().join("missing")).unwrap_err();
+        assert!(matches!(err, LibcontainerError::NotFound(id) if id == "missing"));
+        Ok(())
+    }
+
     #[test]
     #[serial]
     fn test_get_spec() -> Result<()> {
@@ -363,4 +560,25 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_refresh_status_detects_pid_reuse() -> Result<()> {
+        let pid = nix::unistd::getpid();
+        let start_time = Process::myself()?.stat()?.starttime;
+
+        let mut container = Container::default();
+        container
+            .set_pid(pid.as_raw())
+            .set_init_process_start(start_time)
+            .set_status(ContainerStatus::Running);
+        container.refresh_status()?;
+        assert_eq!(container.status(), ContainerStatus::Running);
+
+        // the pid now belongs to a process that has been started at another time
+        container.set_init_process_start(start_time + 1);
+        container.refresh_status()?;
+        assert_eq!(container.status(), ContainerStatus::Stopped);
+
+        Ok(())
+    }
 }
@@ -1,20 +1,24 @@
+use super::criu::{self, CriuOpts};
 use super::{Container, ContainerStatus};
 use crate::container::container::CheckpointOptions;
+use crate::error::LibcontainerError;
 use anyhow::{bail, Context, Result};
 
 use libcgroups::common::CgroupSetup::{Hybrid, Legacy};
 #[cfg(feature = "v1")]
 use libcgroups::common::DEFAULT_CGROUP_ROOT;
-use oci_spec::runtime::Spec;
+use nix::sched::CloneFlags;
+use oci_spec::runtime::{LinuxNamespaceType, Spec};
 use std::fs::{self, File};
 use std::io::Write;
 use std::os::unix::io::AsRawFd;
 
 const CRIU_CHECKPOINT_LOG_FILE: &str = "dump.log";
-const DESCRIPTORS_JSON: &str = "descriptors.json";
+const CRIU_PRE_DUMP_LOG_FILE: &str = "pre-dump.log";
+pub(super) const DESCRIPTORS_JSON: &str = "descriptors.json";
 
 impl Container {
-    pub fn checkpoint(&mut self, opts: &CheckpointOptions) -> Result<()> {
+    pub fn checkpoint(&mut self, opts: &CheckpointOptions) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
 
@@ -22,22 +26,28 @@ impl Container {
         // checkpoitning. is_running() would make more sense here, but let's
         // just reuse existing functions.
         if !self.can_pause() {
-            bail!(
-                "{} could not be checkpointed because it was {:?}",
+            return Err(LibcontainerError::invalid_status(
                 self.id(),
-                self.status()
-            );
+                self.status(),
+                "checkpointed",
+            ));
         }
 
-        let mut criu = rust_criu::Criu::new().unwrap();
+        Ok(self.do_checkpoint(opts)?)
+    }
+
+    fn do_checkpoint(&mut self, opts: &CheckpointOptions) -> Result<()> {
+        let source_spec_path = self.bundle().join("config.json");
+        let spec = Spec::load(source_spec_path)?;
+        validate_options(opts, &spec).context("invalid checkpoint options")?;
+
+        let mut criu_opts = CriuOpts::default();
 
         // We need to tell CRIU that all bind mounts are external. CRIU will fail checkpointing
         // if it does not know that these bind mounts are coming from the outside of the container.
         // This information is needed during restore again. The external location of the bind
         // mounts can change and CRIU will just mount whatever we tell it to mount based on
         // information found in 'config.json'.
-        let source_spec_path = self.bundle().join("config.json");
-        let spec = Spec::load(source_spec_path)?;
         let mounts = spec.mounts().clone();
         for m in mounts.unwrap() {
             match m.typ().as_deref() {
@@ -48,31 +58,13 @@ impl Container {
                         .into_os_string()
                         .into_string()
                         .expect("failed to convert mount destination");
-                    criu.set_external_mount(dest.clone(), dest);
+                    criu_opts.external_mounts.push((dest.clone(), dest));
                 }
                 Some("cgroup") => {
-                    match libcgroups::common::get_cgroup_setup()
-                        .context("failed to determine cgroup setup")?
-                    {
-                        // For v1 it is necessary to list all cgroup mounts as external mounts
-                        Legacy | Hybrid => {
-                            #[cfg(not(feature = "v1"))]
-                            panic!("libcontainer can't run in a Legacy or Hybrid cgroup setup without the v1 feature");
-                            #[cfg(feature = "v1")]
-                            for mp in libcgroups::v1::util::list_subsystem_mount_points()
-                                .context("failed to get subsystem mount points")?
-                            {
-                                let cgroup_mount = mp
-                                    .clone()
-                                    .into_os_string()
-                                    .into_string()
-                                    .expect("failed to convert mount point");
-                                if cgroup_mount.starts_with(DEFAULT_CGROUP_ROOT) {
-                                    criu.set_external_mount(cgroup_mount.clone(), cgroup_mount);
-                                }
-                            }
-                        }
-                        _ => (),
+                    for cgroup_mount in external_cgroup_mounts()? {
+                        criu_opts
+                            .external_mounts
+                            .push((cgroup_mount.clone(), cgroup_mount));
                     }
                 }
                 _ => (),
@@ -81,14 +73,14 @@ impl Container {
 
         let directory = std::fs::File::open(&opts.image_path)
             .with_context(|| format!("failed to open {:?}", opts.image_path))?;
-        criu.set_images_dir_fd(directory.as_raw_fd());
+        criu_opts.images_dir_fd = directory.as_raw_fd();
 
         // It seems to be necessary to be defined outside of 'if' to
         // keep the FD open until CRIU uses it.
         let work_dir: std::fs::File;
         if let Some(wp) = &opts.work_path {
             work_dir = std::fs::File::open(wp)?;
-            criu.set_work_dir_fd(work_dir.as_raw_fd());
+            criu_opts.work_dir_fd = Some(work_dir.as_raw_fd());
         }
 
         let pid: i32 = self.pid().unwrap().into();
@@ -106,24 +98,58 @@ impl Container {
         let mut descriptors_json = File::create(descriptors_json_path)?;
         write!(descriptors_json, "{}", serde_json::to_string(&descriptors)?)?;
 
-        criu.set_log_file(CRIU_CHECKPOINT_LOG_FILE.to_string());
-        criu.set_log_level(4);
-        criu.set_pid(pid);
-        criu.set_leave_running(opts.leave_running);
-        criu.set_ext_unix_sk(opts.ext_unix_sk);
-        criu.set_shell_job(opts.shell_job);
-        criu.set_tcp_established(opts.tcp_established);
-        criu.set_file_locks(opts.file_locks);
-        criu.set_orphan_pts_master(true);
-        criu.set_manage_cgroups(true);
-        criu.set_root(
+        let log_file = if opts.pre_dump {
+            CRIU_PRE_DUMP_LOG_FILE
+        } else {
+            CRIU_CHECKPOINT_LOG_FILE
+        };
+        criu_opts.log_file = Some(log_file.to_string());
+        criu_opts.log_level = Some(4);
+        criu_opts.pid = Some(pid);
+        criu_opts.leave_running = opts.leave_running;
+        criu_opts.ext_unix_sk = opts.ext_unix_sk;
+        criu_opts.shell_job = opts.shell_job;
+        criu_opts.tcp_established = opts.tcp_established;
+        criu_opts.file_locks = opts.file_locks;
+        criu_opts.orphan_pts_master = true;
+        criu_opts.auto_dedup = opts.auto_dedup;
+        criu_opts.root = Some(
             self.bundle()
                 .clone()
                 .into_os_string()
                 .into_string()
                 .unwrap(),
         );
-        if let Err(e) = criu.dump() {
+
+        criu_opts.manage_cgroups = true;
+        criu_opts.manage_cgroups_mode = opts.manage_cgroups_mode;
+
+        // Memory changes have to be tracked for iterative migration, both when
+        // pre-dumping and when dumping on top of a previous pre-dump.
+        criu_opts.track_mem = opts.pre_dump || opts.parent_path.is_some();
+        criu_opts.parent_img = opts.parent_path.clone().map(|parent_path| {
+            parent_path
+                .into_os_string()
+                .into_string()
+                .expect("failed to convert parent path")
+        });
+
+        if let Some(page_server) = &opts.page_server {
+            criu_opts.page_server = Some(parse_page_server(page_server)?);
+        }
+        criu_opts.lazy_pages = opts.lazy_pages;
+        criu_opts.status_fd = opts.status_fd;
+
+        if opts.empty_ns {
+            criu_opts.empty_ns = Some(CloneFlags::CLONE_NEWNET.bits() as u32);
+        }
+
+        let result = if opts.pre_dump {
+            criu::pre_dump(&criu_opts)
+        } else {
+            criu::dump(&criu_opts)
+        };
+        if let Err(e) = result {
             bail!(
                 "checkpointing container {} failed with {:?}. Please check CRIU logfile {:}/{}",
                 self.id(),
@@ -132,11 +158,12 @@ impl Container {
                     .as_ref()
                     .unwrap_or(&opts.image_path)
                     .display(),
-                CRIU_CHECKPOINT_LOG_FILE
+                log_file
             );
         }
 
-        if !opts.leave_running {
+        // A pre-dump only copies the memory of the container, which keeps running
+        if !opts.pre_dump && !opts.leave_running {
             self.set_status(ContainerStatus::Stopped).save()?;
         }
 
@@ -144,3 +171,229 @@ impl Container {
         Ok(())
     }
 }
+
+/// Rejects option combinations that CRIU cannot handle, instead of silently
+/// dropping some of them.
+fn validate_options(opts: &CheckpointOptions, spec: &Spec) -> Result<()> {
+    if opts.pre_dump && opts.lazy_pages {
+        bail!("lazy pages cannot be used together with pre-dump");
+    }
+
+    if opts.pre_dump && opts.leave_running {
+        bail!("leave running has no effect on a pre-dump, the container always keeps running");
+    }
+
+    if opts.status_fd.is_some() && !opts.lazy_pages {
+        bail!("status fd can only be used together with lazy pages");
+    }
+
+    if let Some(page_server) = &opts.page_server {
+        parse_page_server(page_server)?;
+    }
+
+    // CRIU expects the parent image to be relative to the image directory
+    if let Some(parent_path) = &opts.parent_path {
+        if parent_path.is_absolute() {
+            bail!(
+                "parent path {:?} must be relative to the image path",
+                parent_path
+            );
+        }
+
+        let parent_images = opts.image_path.join(parent_path);
+        if !parent_images.is_dir() {
+            bail!("parent images {:?} do not exist", parent_images);
+        }
+    }
+
+    // Only the network namespace can be emptied. This requires the container
+    // to have its own network namespace, as the host's can not be dumped.
+    if opts.empty_ns {
+        let has_own_netns = spec
+            .linux()
+            .as_ref()
+            .and_then(|l| l.namespaces().as_ref())
+            .map(|namespaces| {
+                namespaces
+                    .iter()
+                    .any(|ns| ns.typ() == LinuxNamespaceType::Network && ns.path().is_none())
+            })
+            .unwrap_or(false);
+        if !has_own_netns {
+            bail!("empty ns requires the container to have its own network namespace");
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the page server address, which has to be given as ADDRESS:PORT
+fn parse_page_server(page_server: &str) -> Result<(String, i32)> {
+    let (address, port) = page_server
+        .rsplit_once(':')
+        .with_context(|| format!("page server {page_server} has to be ADDRESS:PORT"))?;
+    if address.is_empty() {
+        bail!("page server {} is missing an address", page_server);
+    }
+
+    let port = port
+        .parse()
+        .with_context(|| format!("invalid page server port {port}"))?;
+    Ok((address.to_owned(), port))
+}
+
+/// Returns the cgroup mounts that have to be marked as external mounts for CRIU.
+/// On cgroup v2 the unified hierarchy is handled by CRIU itself, so this is only
+/// non-empty on legacy and hybrid setups.
+pub(super) fn external_cgroup_mounts() -> Result<Vec<String>> {
+    #[allow(unused_mut)]
+    let mut cgroup_mounts = Vec::new();
+    match libcgroups::common::get_cgroup_setup().context("failed to determine cgroup setup")? {
+        // For v1 it is necessary to list all cgroup mounts as external mounts
+        Legacy | Hybrid => {
+            #[cfg(not(feature = "v1"))]
+            panic!(
+                "libcontainer can't run in a Legacy or Hybrid cgroup setup without the v1 feature"
+            );
+            #[cfg(feature = "v1")]
+            for mp in libcgroups::v1::util::list_subsystem_mount_points()
+                .context("failed to get subsystem mount points")?
+            {
+                let cgroup_mount = mp
+                    .into_os_string()
+                    .into_string()
+                    .expect("failed to convert mount point");
+                if cgroup_mount.starts_with(DEFAULT_CGROUP_ROOT) {
+                    cgroup_mounts.push(cgroup_mount);
+                }
+            }
+        }
+        _ => (),
+    }
+
+    Ok(cgroup_mounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_temp_dir;
+    use oci_spec::runtime::{LinuxBuilder, LinuxNamespaceBuilder, SpecBuilder};
+    use std::path::PathBuf;
+
+    fn default_options() -> CheckpointOptions {
+        CheckpointOptions {
+            auto_dedup: false,
+            empty_ns: false,
+            ext_unix_sk: false,
+            file_locks: false,
+            image_path: PathBuf::from("checkpoint"),
+            lazy_pages: false,
+            leave_running: false,
+            manage_cgroups_mode: None,
+            page_server: None,
+            parent_path: None,
+            pre_dump: false,
+            shell_job: false,
+            status_fd: None,
+            tcp_established: false,
+            work_path: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_default_options() {
+        assert!(validate_options(&default_options(), &Spec::default()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_pre_dump_with_lazy_pages() {
+        let opts = CheckpointOptions {
+            pre_dump: true,
+            lazy_pages: true,
+            ..default_options()
+        };
+        assert!(validate_options(&opts, &Spec::default()).is_err());
+    }
+
+    #[test]
+    fn test_validate_status_fd_without_lazy_pages() {
+        let opts = CheckpointOptions {
+            status_fd: Some(3),
+            ..default_options()
+        };
+        assert!(validate_options(&opts, &Spec::default()).is_err());
+
+        let opts = CheckpointOptions {
+            status_fd: Some(3),
+            lazy_pages: true,
+            ..default_options()
+        };
+        assert!(validate_options(&opts, &Spec::default()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_parent_path() {
+        let tmp = create_temp_dir("test_validate_parent_path").unwrap();
+        let opts = CheckpointOptions {
+            image_path: tmp.path().to_path_buf(),
+            parent_path: Some(PathBuf::from("../pre-dump")),
+            ..default_options()
+        };
+        assert!(validate_options(&opts, &Spec::default()).is_err());
+
+        fs::create_dir(tmp.path().join("pre-dump")).unwrap();
+        let image_path = tmp.path().join("dump");
+        fs::create_dir(&image_path).unwrap();
+        let opts = CheckpointOptions {
+            image_path,
+            parent_path: Some(PathBuf::from("../pre-dump")),
+            ..default_options()
+        };
+        assert!(validate_options(&opts, &Spec::default()).is_ok());
+
+        let opts = CheckpointOptions {
+            parent_path: Some(tmp.path().join("pre-dump")),
+            ..default_options()
+        };
+        assert!(validate_options(&opts, &Spec::default()).is_err());
+    }
+
+    #[test]
+    fn test_validate_empty_ns() {
+        let opts = CheckpointOptions {
+            empty_ns: true,
+            ..default_options()
+        };
+        let spec = SpecBuilder::default()
+            .linux(LinuxBuilder::default().namespaces(vec![]).build().unwrap())
+            .build()
+            .unwrap();
+        assert!(validate_options(&opts, &spec).is_err());
+
+        let spec = SpecBuilder::default()
+            .linux(
+                LinuxBuilder::default()
+                    .namespaces(vec![LinuxNamespaceBuilder::default()
+                        .typ(LinuxNamespaceType::Network)
+                        .build()
+                        .unwrap()])
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        assert!(validate_options(&opts, &spec).is_ok());
+    }
+
+    #[test]
+    fn test_parse_page_server() {
+        assert_eq!(
+            parse_page_server("192.168.0.1:27").unwrap(),
+            ("192.168.0.1".to_owned(), 27)
+        );
+        assert!(parse_page_server("192.168.0.1").is_err());
+        assert!(parse_page_server(":27").is_err());
+        assert!(parse_page_server("localhost:port").is_err());
+    }
+}
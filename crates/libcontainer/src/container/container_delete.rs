@@ -1,13 +1,24 @@
-use super::{Container, ContainerStatus};
+use super::{Container, ContainerStatus, State};
 use crate::config::YoukiConfig;
+use crate::error::LibcontainerError;
 use crate::hooks;
+use crate::rootless::{self, Rootless};
+use crate::utils;
 use anyhow::{bail, Context, Result};
-use libcgroups;
+use libcgroups::common::CgroupError;
 use nix::sys::signal;
+use nix::unistd::Pid;
+use oci_spec::runtime::Spec;
 use std::fs;
+use std::path::{Path, PathBuf};
 
 impl Container {
-    /// Deletes the container
+    /// Deletes the container. If `force` is set, a running container is
+    /// killed with SIGKILL first. Only if its annotations configure a stop
+    /// signal or timeout, it is stopped gracefully, see [`Container::stop`].
+    ///
+    /// The cgroup is removed and the poststop hooks are run before the state
+    /// directory is removed, so a delete that failed halfway can be retried.
     ///
     /// # Example
     ///
@@ -24,55 +35,243 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn delete(&mut self, force: bool) -> Result<()> {
+    pub fn delete(&mut self, force: bool) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
         if self.can_kill() && force {
-            self.do_kill(signal::Signal::SIGKILL, true)?;
-            self.set_status(ContainerStatus::Stopped).save()?;
+            if self.has_stop_annotations() {
+                self.stop(None, None)?;
+            } else {
+                self.do_kill(signal::Signal::SIGKILL, true)?;
+                self.set_status(ContainerStatus::Stopped).save()?;
+            }
         }
         log::debug!("container status: {:?}", self.status());
-        if self.can_delete() {
-            if self.root.exists() {
-                let config = YoukiConfig::load(&self.root).with_context(|| {
-                    format!("failed to load runtime spec for container {}", self.id())
-                })?;
-                log::debug!("config: {:?}", config);
-
-                // remove the directory storing container state
-                log::debug!("remove dir {:?}", self.root);
-                fs::remove_dir_all(&self.root).with_context(|| {
-                    format!("failed to remove container dir {}", self.root.display())
-                })?;
-
-                // remove the cgroup created for the container
-                // check https://man7.org/linux/man-pages/man7/cgroups.7.html
-                // creating and removing cgroups section for more information on cgroups
-                let use_systemd = self
-                    .systemd()
-                    .context("container state does not contain cgroup manager")?;
-                let cmanager = libcgroups::common::create_cgroup_manager(
-                    &config.cgroup_path,
-                    use_systemd,
+        if !self.can_delete() {
+            return Err(LibcontainerError::invalid_status(
+                self.id(),
+                self.status(),
+                "deleted",
+            ));
+        }
+
+        if !self.root.exists() {
+            return Ok(());
+        }
+
+        let config = self.cleanup_config();
+        log::debug!("config: {:?}", config);
+
+        // remove the cgroup created for the container
+        // check https://man7.org/linux/man-pages/man7/cgroups.7.html
+        // creating and removing cgroups section for more information on cgroups
+        let cmanager = libcgroups::common::create_cgroup_manager(
+            &config.cgroup_path,
+            self.cleanup_systemd(&config),
+            self.id(),
+        )?;
+        cmanager.remove()?;
+
+        // failing poststop hooks are only logged, they must not prevent the
+        // container from being deleted
+        if let Some(hooks) = config.hooks.as_ref() {
+            hooks::run_hooks_ignoring_failures(hooks.poststop().as_ref(), Some(self))
+                .with_context(|| "failed to run post stop hooks")?;
+        }
+
+        // remove the directory storing container state
+        log::debug!("remove dir {:?}", self.root);
+        fs::remove_dir_all(&self.root)
+            .with_context(|| format!("failed to remove container dir {}", self.root.display()))?;
+
+        Ok(())
+    }
+
+    /// Loads the container from its state directory for cleanup. Unlike
+    /// [`Container::load`], this also works if the state is missing or corrupt,
+    /// for example because creating or deleting the container crashed. The
+    /// container is then assumed to be stopped.
+    pub fn recover(container_root: PathBuf) -> Result<Self, LibcontainerError> {
+        let container_id = container_root
+            .file_name()
+            .and_then(|name| name.to_str())
+            .with_context(|| format!("invalid container directory {container_root:?}"))?
+            .to_owned();
+
+        match State::load(&container_root) {
+            Ok(state) => {
+                let mut container = Self {
+                    state,
+                    root: container_root,
+                };
+                container.refresh_status()?;
+                Ok(container)
+            }
+            Err(err) => {
+                log::warn!(
+                    "failed to load state of container {}: {:#}",
+                    container_id,
+                    err
+                );
+                let state = State::new(
+                    &container_id,
+                    ContainerStatus::Stopped,
+                    None,
+                    PathBuf::new(),
+                );
+                Ok(Self {
+                    state,
+                    root: container_root,
+                })
+            }
+        }
+    }
+
+    /// Returns the processes that are still alive in the cgroup of the
+    /// container. A cgroup that does not exist (anymore) contains no processes.
+    pub fn remaining_processes(&self) -> Result<Vec<Pid>, LibcontainerError> {
+        let config = self.cleanup_config();
+        let cmanager = libcgroups::common::create_cgroup_manager(
+            &config.cgroup_path,
+            self.cleanup_systemd(&config),
+            self.id(),
+        )?;
+
+        match cmanager.get_all_pids() {
+            Ok(pids) => Ok(pids),
+            Err(CgroupError::NotFound(_)) => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns whether the cgroup of the container is managed by systemd. If the
+    /// state does not tell, e.g. because it could not be loaded, this is derived
+    /// from the form of the cgroup path.
+    fn cleanup_systemd(&self, config: &YoukiConfig) -> bool {
+        self.systemd().unwrap_or_else(|| {
+            let use_systemd = is_systemd_cgroup_path(&config.cgroup_path);
+            log::warn!(
+                "container state of {} does not contain cgroup manager, assuming {}",
+                self.id(),
+                if use_systemd { "systemd" } else { "cgroupfs" }
+            );
+            use_systemd
+        })
+    }
+
+    /// Returns the config that has been saved when the container was created.
+    /// If it cannot be loaded, it is reconstructed from the spec in the bundle
+    /// and as a last resort, only the default cgroup path is used.
+    fn cleanup_config(&self) -> YoukiConfig {
+        let err = match YoukiConfig::load(&self.root) {
+            Ok(config) => return config,
+            Err(err) => err,
+        };
+        log::warn!(
+            "failed to load config of container {}, reconstructing it from the bundle: {:#}",
+            self.id(),
+            err
+        );
+
+        let reconstruct = || -> Result<YoukiConfig> {
+            if self.bundle().as_os_str().is_empty() {
+                bail!("bundle of the container is unknown");
+            }
+            let spec_path = self.bundle().join("config.json");
+            let spec = Spec::load(&spec_path)
+                .with_context(|| format!("failed to load spec from {spec_path:?}"))?;
+            let rootless = Rootless::new(&spec)
+                .map(|rootless| rootless.is_some())
+                .unwrap_or_else(|_| rootless::rootless_required());
+            YoukiConfig::from_spec(&spec, self.id(), rootless)
+        };
+
+        match reconstruct() {
+            Ok(config) => config,
+            Err(err) => {
+                log::warn!(
+                    "failed to reconstruct config of container {}, poststop hooks will not be run: {:#}",
                     self.id(),
-                )
-                .context("failed to create cgroup manager")?;
-                cmanager.remove().with_context(|| {
-                    format!("failed to remove cgroup {}", config.cgroup_path.display())
-                })?;
-
-                if let Some(hooks) = config.hooks.as_ref() {
-                    hooks::run_hooks(hooks.poststop().as_ref(), Some(self))
-                        .with_context(|| "failed to run post stop hooks")?;
+                    err
+                );
+                YoukiConfig {
+                    hooks: None,
+                    cgroup_path: utils::get_cgroup_path(
+                        &None,
+                        self.id(),
+                        rootless::rootless_required(),
+                    ),
+                    resources: None,
                 }
             }
-            Ok(())
-        } else {
-            bail!(
-                "{} could not be deleted because it was {:?}",
-                self.id(),
-                self.status()
-            )
         }
     }
 }
+
+/// systemd expects cgroup paths of the form [slice]:[prefix]:[name], while
+/// cgroupfs paths are plain paths
+fn is_systemd_cgroup_path(cgroup_path: &Path) -> bool {
+    let cgroup_path = cgroup_path.to_string_lossy();
+    !cgroup_path.starts_with('/') && cgroup_path.contains(':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_temp_dir;
+
+    #[test]
+    fn test_recover_without_state() -> Result<()> {
+        let tmp = create_temp_dir("test_recover_without_state")?;
+        let container_root = tmp.join("orphan");
+        fs::create_dir(&container_root)?;
+
+        let container = Container::recover(container_root.clone())?;
+        assert_eq!(container.id(), "orphan");
+        assert_eq!(container.status(), ContainerStatus::Stopped);
+        assert!(container.can_delete());
+        assert_eq!(container.root, container_root);
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_systemd_cgroup_path() {
+        assert!(is_systemd_cgroup_path(Path::new(
+            "system.slice:youki:sample"
+        )));
+        assert!(is_systemd_cgroup_path(Path::new(":youki:sample")));
+        assert!(!is_systemd_cgroup_path(Path::new("/youki/sample")));
+        assert!(!is_systemd_cgroup_path(Path::new("sample")));
+    }
+
+    #[test]
+    fn test_cleanup_config_fallback() -> Result<()> {
+        let tmp = create_temp_dir("test_cleanup_config_fallback")?;
+        let bundle = tmp.join("bundle");
+        let container_root = tmp.join("sample");
+        fs::create_dir(&bundle)?;
+        fs::create_dir(&container_root)?;
+
+        // neither the config nor the bundle exist
+        let mut container = Container::recover(container_root.clone())?;
+        let config = container.cleanup_config();
+        assert!(config.hooks.is_none());
+        assert!(config.cgroup_path.to_string_lossy().ends_with("sample"));
+
+        // the config is reconstructed from the bundle
+        let mut spec = Spec::default();
+        let mut linux = spec.linux().clone().unwrap();
+        linux.set_cgroups_path(Some(PathBuf::from("/youki/sample")));
+        spec.set_linux(Some(linux));
+        spec.save(bundle.join("config.json"))?;
+        container.state.bundle = bundle;
+        let config = container.cleanup_config();
+        assert_eq!(config.cgroup_path, PathBuf::from("/youki/sample"));
+
+        // the saved config takes precedence
+        let saved = YoukiConfig::from_spec(&Spec::default(), "sample", false)?;
+        saved.save(&container_root)?;
+        assert_eq!(container.cleanup_config(), saved);
+        Ok(())
+    }
+}
@@ -1,10 +1,137 @@
-use std::{thread, time::Duration};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
 
 use super::{Container, ContainerStatus};
-use anyhow::{bail, Context, Result};
+use crate::error::LibcontainerError;
+use anyhow::{anyhow, Context, Result};
+use libcgroups::{
+    common::CgroupManager,
+    events::{CgroupEvent, EventListener},
+    stats::Stats,
+};
+use nix::poll::{poll, PollFd, PollFlags};
+use serde::Serialize;
+
+// Upper bound for how long it takes to notice that the container has stopped
+const STATUS_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Type of a container event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventType {
+    /// Periodic resource usage statistics
+    Stats,
+    /// A process of the container has been killed by the oom killer
+    Oom,
+    /// The container tried to create more processes than allowed by its pids limit
+    PidsLimit,
+    /// The init process of the container has exited. This is always the last event.
+    Stopped,
+}
+
+impl From<CgroupEvent> for EventType {
+    fn from(event: CgroupEvent) -> Self {
+        match event {
+            CgroupEvent::Oom => EventType::Oom,
+            CgroupEvent::PidsLimit => EventType::PidsLimit,
+        }
+    }
+}
+
+/// Container event, serialized in the same envelope that runc uses,
+/// e.g. `{"type":"oom","id":"74f1a4cb3801"}`
+#[derive(Debug, Serialize)]
+pub struct Event {
+    #[serde(rename = "type")]
+    pub typ: EventType,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Stats>,
+}
+
+impl Event {
+    fn new(typ: EventType, id: &str, data: Option<Stats>) -> Self {
+        Self {
+            typ,
+            id: id.to_owned(),
+            data,
+        }
+    }
+}
+
+/// Iterator over the events of a running container. Statistics are emitted
+/// every interval, oom and pids limit events as soon as the kernel reports
+/// them. The iterator ends after the `Stopped` event.
+pub struct EventStream {
+    container: Container,
+    cgroup_manager: Box<dyn CgroupManager>,
+    listener: Box<dyn EventListener>,
+    interval: Duration,
+    next_stats: Instant,
+    pending: VecDeque<Event>,
+    done: bool,
+}
+
+impl EventStream {
+    fn next_event(&mut self) -> Result<Event> {
+        loop {
+            for event in self.listener.read_events()? {
+                self.pending
+                    .push_back(Event::new(event.into(), self.container.id(), None));
+            }
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+
+            self.container
+                .refresh_status()
+                .context("failed to refresh container status")?;
+            if self.container.status() == ContainerStatus::Stopped {
+                self.done = true;
+                return Ok(Event::new(EventType::Stopped, self.container.id(), None));
+            }
+
+            let now = Instant::now();
+            if now >= self.next_stats {
+                self.next_stats = now + self.interval;
+                let stats = self.cgroup_manager.stats()?;
+                return Ok(Event::new(
+                    EventType::Stats,
+                    self.container.id(),
+                    Some(stats),
+                ));
+            }
+
+            let timeout = (self.next_stats - now).min(STATUS_CHECK_INTERVAL);
+            let mut fds = [PollFd::new(self.listener.as_raw_fd(), PollFlags::POLLIN)];
+            match poll(&mut fds, timeout.as_millis() as i32) {
+                Ok(_) | Err(nix::errno::Errno::EINTR) => {}
+                Err(err) => return Err(err).context("failed to wait for cgroup events"),
+            }
+        }
+    }
+}
+
+impl Iterator for EventStream {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let event = self.next_event();
+        if event.is_err() {
+            self.done = true;
+        }
+        Some(event)
+    }
+}
 
 impl Container {
-    /// Displays container events
+    /// Displays container events, one JSON object per line
     ///
     /// # Example
     ///
@@ -21,32 +148,105 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn events(&mut self, interval: u32, stats: bool) -> Result<()> {
+    pub fn events(&mut self, interval: u32, stats: bool) -> Result<(), LibcontainerError> {
+        if stats {
+            self.refresh_status()
+                .context("failed to refresh container status")?;
+            if !self.state.status.eq(&ContainerStatus::Running) {
+                return Err(LibcontainerError::invalid_status(
+                    self.id(),
+                    self.status(),
+                    "monitored",
+                ));
+            }
+
+            let stats = self.stats()?;
+            let event = Event::new(EventType::Stats, self.id(), Some(stats));
+            println!(
+                "{}",
+                serde_json::to_string(&event).context("failed to serialize event")?
+            );
+            return Ok(());
+        }
+
+        for event in self.event_stream(Duration::from_secs(interval as u64))? {
+            println!(
+                "{}",
+                serde_json::to_string(&event?).context("failed to serialize event")?
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the current resource usage statistics of the container
+    pub fn stats(&self) -> Result<Stats, LibcontainerError> {
+        Ok(self.cgroup_manager()?.stats()?)
+    }
+
+    /// Returns a stream of events of the running container, with statistics
+    /// being collected every `interval`
+    pub fn event_stream(&mut self, interval: Duration) -> Result<EventStream, LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
         if !self.state.status.eq(&ContainerStatus::Running) {
-            bail!("{} is not in running state", self.id());
+            return Err(LibcontainerError::invalid_status(
+                self.id(),
+                self.status(),
+                "monitored",
+            ));
+        }
+        if interval.is_zero() {
+            return Err(anyhow!("event interval must be greater than zero").into());
         }
 
+        let cgroup_manager = self.cgroup_manager()?;
+        let listener = cgroup_manager.event_listener()?;
+
+        Ok(EventStream {
+            container: self.clone(),
+            cgroup_manager,
+            listener,
+            interval,
+            next_stats: Instant::now(),
+            pending: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    fn cgroup_manager(&self) -> Result<Box<dyn CgroupManager>, LibcontainerError> {
         let cgroups_path = self.spec()?.cgroup_path;
         let use_systemd = self
             .systemd()
             .context("could not determine cgroup manager")?;
 
-        let cgroup_manager =
-            libcgroups::common::create_cgroup_manager(cgroups_path, use_systemd, self.id())?;
-        match stats {
-            true => {
-                let stats = cgroup_manager.stats()?;
-                println!("{}", serde_json::to_string_pretty(&stats)?);
-            }
-            false => loop {
-                let stats = cgroup_manager.stats()?;
-                println!("{}", serde_json::to_string_pretty(&stats)?);
-                thread::sleep(Duration::from_secs(interval as u64));
-            },
-        }
+        Ok(libcgroups::common::create_cgroup_manager(
+            cgroups_path,
+            use_systemd,
+            self.id(),
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_envelope() -> Result<()> {
+        let event = Event::new(EventType::Oom, "74f1a4cb3801", None);
+        assert_eq!(
+            serde_json::to_string(&event)?,
+            r#"{"type":"oom","id":"74f1a4cb3801"}"#
+        );
+
+        let event = Event::new(EventType::Stats, "74f1a4cb3801", Some(Stats::default()));
+        let value = serde_json::to_value(event)?;
+        assert_eq!(value["type"], "stats");
+        assert!(value["data"]["memory"].is_object());
 
+        let event = Event::new(EventType::PidsLimit, "74f1a4cb3801", None);
+        assert_eq!(serde_json::to_value(event)?["type"], "pidsLimit");
         Ok(())
     }
 }
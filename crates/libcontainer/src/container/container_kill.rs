@@ -1,6 +1,6 @@
 use super::{Container, ContainerStatus};
-use crate::signal::Signal;
-use anyhow::{bail, Context, Result};
+use crate::{error::LibcontainerError, process::pidfd, signal::Signal};
+use anyhow::{Context, Result};
 use libcgroups::common::{create_cgroup_manager, get_cgroup_setup};
 use nix::sys::signal::{self};
 
@@ -23,7 +23,7 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn kill<S: Into<Signal>>(&mut self, signal: S, all: bool) -> Result<()> {
+    pub fn kill<S: Into<Signal>>(&mut self, signal: S, all: bool) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
         if self.can_kill() {
@@ -33,11 +33,11 @@ impl Container {
             if all && self.status() == ContainerStatus::Stopped {
                 self.do_kill(signal, all)?;
             } else {
-                bail!(
-                    "{} could not be killed because it was {:?}",
+                return Err(LibcontainerError::invalid_status(
                     self.id(),
-                    self.status()
-                )
+                    self.status(),
+                    "killed",
+                ));
             }
         }
         self.set_status(ContainerStatus::Stopped).save()?;
@@ -57,7 +57,7 @@ impl Container {
         let pid = self.pid().unwrap();
 
         log::debug!("kill signal {} to {}", signal, pid);
-        let res = signal::kill(pid, signal);
+        let res = pidfd::signal_process(pid, self.init_process_start(), signal);
 
         match res {
             Err(nix::errno::Errno::ESRCH) => {
@@ -103,7 +103,13 @@ impl Container {
         let pids = cmanger.get_all_pids()?;
         pids.iter().try_for_each(|&pid| {
             log::debug!("kill signal {} to {}", signal, pid);
-            let res = signal::kill(pid, signal);
+            // only the start time of the init process has been recorded
+            let start_time = if Some(pid) == self.pid() {
+                self.init_process_start()
+            } else {
+                None
+            };
+            let res = pidfd::signal_process(pid, start_time, signal);
             match res {
                 Err(nix::errno::Errno::ESRCH) => {
                     /* the process does not exist, which is what we want */
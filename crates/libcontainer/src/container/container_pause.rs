@@ -1,5 +1,6 @@
 use super::{Container, ContainerStatus};
-use anyhow::{bail, Context, Result};
+use crate::error::LibcontainerError;
+use anyhow::{Context, Result};
 use libcgroups::common::FreezerState;
 
 impl Container {
@@ -20,16 +21,16 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn pause(&mut self) -> Result<()> {
+    pub fn pause(&mut self) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
 
         if !self.can_pause() {
-            bail!(
-                "{} could not be paused because it was {:?}",
+            return Err(LibcontainerError::invalid_status(
                 self.id(),
-                self.status()
-            );
+                self.status(),
+                "paused",
+            ));
         }
 
         let cgroups_path = self.spec()?.cgroup_path;
@@ -0,0 +1,386 @@
+use super::container_checkpoint::{external_cgroup_mounts, DESCRIPTORS_JSON};
+use super::criu::{self, CriuOpts};
+use super::{Container, ContainerStatus};
+use crate::config::YoukiConfig;
+use crate::container::container::RestoreOptions;
+use crate::error::LibcontainerError;
+use crate::process::pidfd;
+use crate::utils;
+use anyhow::{bail, Context, Result};
+
+use libcgroups::common::{CgroupManager, ControllerOpt};
+use nix::unistd::Pid;
+use oci_spec::runtime::Spec;
+use procfs::process::Process;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::Path;
+
+const CRIU_RESTORE_LOG_FILE: &str = "restore.log";
+
+impl Container {
+    /// Restores a container from the CRIU images written by `checkpoint`. A new
+    /// container state directory is created below `root_path` and the restored
+    /// container is left in the running state.
+    pub fn restore(
+        root_path: &Path,
+        container_id: &str,
+        opts: &RestoreOptions,
+    ) -> Result<Self, LibcontainerError> {
+        let inherit_fds = validate_options(opts).context("invalid restore options")?;
+
+        let container_dir = root_path.join(container_id);
+        log::debug!("container directory will be {:?}", container_dir);
+        if container_dir.exists() {
+            return Err(LibcontainerError::AlreadyExists(container_id.to_owned()));
+        }
+        utils::create_dir_all(&container_dir).context("failed to create container dir")?;
+
+        let mut container = Container::new(
+            container_id,
+            ContainerStatus::Creating,
+            None,
+            &opts.bundle,
+            &container_dir,
+        )?;
+        container.set_systemd(opts.use_systemd);
+
+        if let Err(outer) = container.do_restore(opts, inherit_fds) {
+            if let Err(inner) = container.cleanup_restore() {
+                return Err(outer.context(inner).into());
+            }
+            return Err(outer.into());
+        }
+
+        log::debug!("container {} restored", container.id());
+        Ok(container)
+    }
+
+    fn do_restore(
+        &mut self,
+        opts: &RestoreOptions,
+        inherit_fds: Vec<(String, RawFd)>,
+    ) -> Result<()> {
+        let source_spec_path = self.bundle().join("config.json");
+        let mut spec = Spec::load(source_spec_path)?;
+        spec.canonicalize_rootfs(self.bundle())
+            .context("failed to canonicalize rootfs")?;
+        self.set_annotations(spec.annotations().clone());
+        self.save()?;
+
+        let config = YoukiConfig::from_spec(&spec, self.id(), false)?;
+        config.save(&self.root).context("failed to save config")?;
+
+        let mut criu_opts = CriuOpts {
+            inherit_fds,
+            ..Default::default()
+        };
+
+        // The bind mounts were marked as external during checkpoint. Their source
+        // may have moved since then, so map each destination to the source that
+        // is currently found in 'config.json'.
+        if let Some(mounts) = spec.mounts() {
+            for m in mounts {
+                match m.typ().as_deref() {
+                    Some("bind") => {
+                        let dest = m
+                            .destination()
+                            .clone()
+                            .into_os_string()
+                            .into_string()
+                            .expect("failed to convert mount destination");
+                        let source = m
+                            .source()
+                            .clone()
+                            .context("bind mount without source")?
+                            .into_os_string()
+                            .into_string()
+                            .expect("failed to convert mount source");
+                        criu_opts.external_mounts.push((dest, source));
+                    }
+                    Some("cgroup") => {
+                        for cgroup_mount in external_cgroup_mounts()? {
+                            criu_opts
+                                .external_mounts
+                                .push((cgroup_mount.clone(), cgroup_mount));
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        let directory = File::open(&opts.image_path)
+            .with_context(|| format!("failed to open {:?}", opts.image_path))?;
+        criu_opts.images_dir_fd = directory.as_raw_fd();
+
+        // It seems to be necessary to be defined outside of 'if' to
+        // keep the FD open until CRIU uses it.
+        let work_dir: File;
+        if let Some(wp) = &opts.work_path {
+            work_dir = File::open(wp)?;
+            criu_opts.work_dir_fd = Some(work_dir.as_raw_fd());
+        }
+
+        let rootfs = spec
+            .root()
+            .as_ref()
+            .context("no root in spec")?
+            .path()
+            .clone()
+            .into_os_string()
+            .into_string()
+            .expect("failed to convert rootfs path");
+
+        criu_opts.log_file = Some(CRIU_RESTORE_LOG_FILE.to_string());
+        criu_opts.log_level = Some(4);
+        criu_opts.ext_unix_sk = opts.ext_unix_sk;
+        criu_opts.shell_job = opts.shell_job;
+        criu_opts.tcp_established = opts.tcp_established;
+        criu_opts.file_locks = opts.file_locks;
+        criu_opts.orphan_pts_master = true;
+        criu_opts.manage_cgroups = true;
+        criu_opts.root = Some(rootfs);
+        if let Err(e) = criu::restore(&criu_opts) {
+            bail!(
+                "restoring container {} failed with {:?}. Please check CRIU logfile {:}/{}",
+                self.id(),
+                e,
+                opts.work_path
+                    .as_ref()
+                    .unwrap_or(&opts.image_path)
+                    .display(),
+                CRIU_RESTORE_LOG_FILE
+            );
+        }
+
+        let cmanager = libcgroups::common::create_cgroup_manager(
+            &config.cgroup_path,
+            opts.use_systemd,
+            self.id(),
+        )?;
+        let init_pid = Self::find_restored_init(cmanager.as_ref())
+            .context("failed to find restored container process")?;
+
+        // CRIU restores the processes into the cgroups they were checkpointed in,
+        // the resource limits however have to come from the current spec.
+        cmanager.add_task(init_pid)?;
+        if let Some(resources) = spec.linux().as_ref().and_then(|l| l.resources().as_ref()) {
+            let controller_opt = ControllerOpt {
+                resources,
+                freezer_state: None,
+                oom_score_adj: None,
+                disable_oom_killer: false,
+            };
+            cmanager
+                .apply(&controller_opt)
+                .context("failed to apply resource limits to cgroup")?;
+        }
+
+        if let Some(pid_file) = &opts.pid_file {
+            fs::write(pid_file, format!("{init_pid}")).context("failed to write pid file")?;
+        }
+
+        // The container went through created before it was checkpointed, set it
+        // first so that the creation timestamp gets recorded.
+        let init_process_start = pidfd::process_start_time(init_pid)
+            .context("failed to get start time of restored init process")?;
+        self.set_status(ContainerStatus::Created)
+            .set_status(ContainerStatus::Running)
+            .set_creator(nix::unistd::geteuid().as_raw())
+            .set_pid(init_pid.as_raw())
+            .set_init_process_start(init_process_start)
+            .save()
+            .context("failed to save container state")?;
+
+        Ok(())
+    }
+
+    fn find_restored_init(cmanager: &dyn CgroupManager) -> Result<Pid> {
+        let pids = cmanager.get_all_pids()?;
+        find_tree_root(&pids, |pid| {
+            Ok(Pid::from_raw(Process::new(pid.as_raw())?.stat()?.ppid))
+        })
+    }
+
+    fn cleanup_restore(&self) -> Result<()> {
+        let mut errors = Vec::new();
+        if let Ok(config) = YoukiConfig::load(&self.root) {
+            match libcgroups::common::create_cgroup_manager(
+                &config.cgroup_path,
+                self.systemd().unwrap_or_default(),
+                self.id(),
+            ) {
+                Ok(cmanager) => {
+                    if let Err(e) = cmanager.remove().context("failed to remove cgroup") {
+                        errors.push(e.to_string());
+                    }
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+
+        if self.root.exists() {
+            if let Err(e) = fs::remove_dir_all(&self.root)
+                .with_context(|| format!("could not delete {:?}", self.root))
+            {
+                errors.push(e.to_string());
+            }
+        }
+
+        if !errors.is_empty() {
+            bail!("failed to cleanup container: {}", errors.join(";"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks the options before anything is created for the container and returns
+/// the descriptors the restored container has to inherit
+fn validate_options(opts: &RestoreOptions) -> Result<Vec<(String, RawFd)>> {
+    if !opts.image_path.is_dir() {
+        bail!("image path {:?} is not a directory", opts.image_path);
+    }
+
+    if let Some(work_path) = &opts.work_path {
+        if !work_path.is_dir() {
+            bail!("work path {:?} is not a directory", work_path);
+        }
+    }
+
+    inherited_descriptors(&opts.image_path)
+}
+
+/// Pipes and sockets the container used as stdio at checkpoint time cannot be
+/// restored by CRIU, e.g. because the other end belonged to containerd. The
+/// stdio of youki takes their place, CRIU inherits it and hands the fd with the
+/// same number to the restored process.
+fn inherited_descriptors(image_path: &Path) -> Result<Vec<(String, RawFd)>> {
+    let descriptors_json_path = image_path.join(DESCRIPTORS_JSON);
+    let descriptors: Vec<String> = serde_json::from_reader(BufReader::new(
+        File::open(&descriptors_json_path)
+            .with_context(|| format!("failed to open {descriptors_json_path:?}"))?,
+    ))
+    .with_context(|| format!("failed to parse {descriptors_json_path:?}"))?;
+
+    let inherit_fds = descriptors
+        .into_iter()
+        .zip(0..)
+        .filter(|(descriptor, _)| {
+            descriptor.starts_with("pipe:[") || descriptor.starts_with("socket:[")
+        })
+        .collect();
+
+    Ok(inherit_fds)
+}
+
+/// The restored process tree lives in the container cgroup. Its root is the
+/// only process whose parent is not part of that cgroup.
+fn find_tree_root<F>(pids: &[Pid], parent_of: F) -> Result<Pid>
+where
+    F: Fn(Pid) -> Result<Pid>,
+{
+    for pid in pids {
+        if !pids.contains(&parent_of(*pid)?) {
+            return Ok(*pid);
+        }
+    }
+
+    bail!("no process found in container cgroup")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_temp_dir;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn restore_options(image_path: PathBuf) -> RestoreOptions {
+        RestoreOptions {
+            bundle: PathBuf::from("."),
+            ext_unix_sk: false,
+            file_locks: false,
+            image_path,
+            pid_file: None,
+            shell_job: false,
+            tcp_established: false,
+            use_systemd: false,
+            work_path: None,
+        }
+    }
+
+    fn write_descriptors(image_path: &Path, descriptors: &[&str]) {
+        fs::write(
+            image_path.join(DESCRIPTORS_JSON),
+            serde_json::to_string(descriptors).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_validate_options() -> Result<()> {
+        let tmp = create_temp_dir("test_restore_validate_options")?;
+        write_descriptors(tmp.path(), &["/dev/null", "/dev/pts/0", "/dev/pts/0"]);
+        let opts = restore_options(tmp.path().to_path_buf());
+        assert!(validate_options(&opts).is_ok());
+
+        let opts = RestoreOptions {
+            work_path: Some(tmp.path().join("work")),
+            ..restore_options(tmp.path().to_path_buf())
+        };
+        assert!(validate_options(&opts).is_err());
+
+        let opts = restore_options(tmp.path().join("checkpoint"));
+        assert!(validate_options(&opts).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_options_without_descriptors() -> Result<()> {
+        let tmp = create_temp_dir("test_restore_validate_options_without_descriptors")?;
+        let opts = restore_options(tmp.path().to_path_buf());
+        assert!(validate_options(&opts).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inherited_descriptors() -> Result<()> {
+        let tmp = create_temp_dir("test_restore_inherited_descriptors")?;
+        write_descriptors(tmp.path(), &["/dev/null", "/dev/pts/0", "/dev/pts/0"]);
+        assert!(inherited_descriptors(tmp.path())?.is_empty());
+
+        write_descriptors(tmp.path(), &["/dev/null", "pipe:[12345]", "socket:[12346]"]);
+        assert_eq!(
+            inherited_descriptors(tmp.path())?,
+            vec![
+                ("pipe:[12345]".to_owned(), 1),
+                ("socket:[12346]".to_owned(), 2)
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_tree_root() -> Result<()> {
+        // 10 was forked by the runtime, 11 and 13 by 10 and 12 by 11
+        let parents: HashMap<i32, i32> = HashMap::from([(12, 11), (11, 10), (13, 10), (10, 1)]);
+        let parent_of = |pid: Pid| {
+            parents
+                .get(&pid.as_raw())
+                .map(|ppid| Pid::from_raw(*ppid))
+                .context("unknown pid")
+        };
+
+        let pids: Vec<Pid> = [12, 13, 11, 10].into_iter().map(Pid::from_raw).collect();
+        assert_eq!(find_tree_root(&pids, parent_of)?, Pid::from_raw(10));
+        assert!(find_tree_root(&[], parent_of).is_err());
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use super::{Container, ContainerStatus};
+use crate::error::LibcontainerError;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use libcgroups::common::FreezerState;
 
 impl Container {
@@ -21,17 +22,17 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn resume(&mut self) -> Result<()> {
+    pub fn resume(&mut self) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
         // check if container can be resumed :
         // for example, a running process cannot be resumed
         if !self.can_resume() {
-            bail!(
-                "{} could not be resumed because it was {:?}",
+            return Err(LibcontainerError::invalid_status(
                 self.id(),
-                self.status()
-            );
+                self.status(),
+                "resumed",
+            ));
         }
 
         let cgroups_path = self.spec()?.cgroup_path;
@@ -1,11 +1,12 @@
 use crate::{
     config::YoukiConfig,
+    error::LibcontainerError,
     hooks,
     notify_socket::{NotifySocket, NOTIFY_FILE},
 };
 
 use super::{Container, ContainerStatus};
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use nix::unistd;
 
 impl Container {
@@ -26,31 +27,20 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn start(&mut self) -> Result<()> {
+    pub fn start(&mut self) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
 
         if !self.can_start() {
-            let err_msg = format!(
-                "{} could not be started because it was {:?}",
-                self.id(),
-                self.status()
-            );
-            log::error!("{}", err_msg);
-            bail!(err_msg);
+            let err = LibcontainerError::invalid_status(self.id(), self.status(), "started");
+            log::error!("{}", err);
+            return Err(err);
         }
 
         let config = YoukiConfig::load(&self.root)
             .with_context(|| format!("failed to load runtime spec for container {}", self.id()))?;
-        if let Some(hooks) = config.hooks.as_ref() {
-            // While prestart is marked as deprecated in the OCI spec, the docker and integration test still
-            // uses it.
-            #[allow(deprecated)]
-            hooks::run_hooks(hooks.prestart().as_ref(), Some(self))
-                .with_context(|| "failed to run pre start hooks")?;
-        }
-
-        unistd::chdir(self.root.as_os_str())?;
+        unistd::chdir(self.root.as_os_str())
+            .with_context(|| format!("failed to change directory to {:?}", self.root))?;
 
         let mut notify_socket = NotifySocket::new(self.root.join(NOTIFY_FILE));
         notify_socket.notify_container_start()?;
@@ -59,9 +49,10 @@ impl Container {
             .with_context(|| format!("could not save state for container {}", self.id()))?;
 
         // Run post start hooks. It runs after the container process is started.
-        // It is called in the runtime namespace.
+        // It is called in the runtime namespace. Failing poststart hooks must not
+        // affect the container, so they are only logged.
         if let Some(hooks) = config.hooks.as_ref() {
-            hooks::run_hooks(hooks.poststart().as_ref(), Some(self))
+            hooks::run_hooks_ignoring_failures(hooks.poststart().as_ref(), Some(self))
                 .with_context(|| "failed to run post start hooks")?;
         }
 
@@ -0,0 +1,199 @@
+use super::{Container, ContainerStatus};
+use crate::{error::LibcontainerError, signal::Signal};
+use anyhow::{Context, Result};
+use libcgroups::common::{create_cgroup_manager, CgroupManager};
+use nix::{sys::signal, unistd::Pid};
+use std::{
+    convert::TryFrom,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Annotation of the image spec which names the signal that stops the container
+pub const STOP_SIGNAL_ANNOTATION: &str = "org.opencontainers.image.stopSignal";
+/// Annotation with the number of seconds the container has to stop after it
+/// received the stop signal, before it is killed
+pub const STOP_TIMEOUT_ANNOTATION: &str = "io.github.containers.youki.stopTimeout";
+/// Time the container has to stop if no timeout has been configured
+pub const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+impl Container {
+    /// Stops all processes of the container. They are sent the stop signal
+    /// first and get until the timeout to exit on their own, after which the
+    /// remaining processes are killed with SIGKILL. If no signal or timeout is
+    /// given, the ones configured by the annotations of the container are used,
+    /// falling back to SIGTERM and [`DEFAULT_STOP_TIMEOUT`].
+    ///
+    /// Returns the processes that were still alive when the timeout expired.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use libcontainer::container::builder::ContainerBuilder;
+    /// use libcontainer::syscall::syscall::create_syscall;
+    /// use std::time::Duration;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut container = ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+    /// .as_init("/var/run/docker/bundle")
+    /// .build()?;
+    ///
+    /// let killed = container.stop(None, Some(Duration::from_secs(5)))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stop(
+        &mut self,
+        signal: Option<Signal>,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Pid>, LibcontainerError> {
+        self.refresh_status()
+            .context("failed to refresh container status")?;
+        if !self.can_kill() {
+            return Err(LibcontainerError::invalid_status(
+                self.id(),
+                self.status(),
+                "stopped",
+            ));
+        }
+
+        let signal = match signal {
+            Some(signal) => signal.into_raw(),
+            None => self.stop_signal()?,
+        };
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => self.stop_timeout()?,
+        };
+
+        let cgroups_path = self.spec()?.cgroup_path;
+        let use_systemd = self
+            .systemd()
+            .context("container state does not contain cgroup manager")?;
+        let cmanager = create_cgroup_manager(&cgroups_path, use_systemd, self.id())?;
+
+        let graceful = signal != signal::Signal::SIGKILL && !timeout.is_zero();
+        let mut remaining = Vec::new();
+        if graceful {
+            log::debug!(
+                "stopping container {} with {} and a timeout of {:?}",
+                self.id(),
+                signal,
+                timeout
+            );
+            self.do_kill(signal, true)?;
+            if !wait_until_empty(cmanager.as_ref(), timeout)? {
+                remaining = cmanager.get_all_pids()?;
+                log::warn!(
+                    "processes {:?} of container {} did not exit within {:?}, killing them",
+                    remaining,
+                    self.id(),
+                    timeout
+                );
+            }
+        }
+
+        if !graceful || !remaining.is_empty() {
+            self.do_kill(signal::Signal::SIGKILL, true)?;
+        }
+
+        self.set_status(ContainerStatus::Stopped).save()?;
+        Ok(remaining)
+    }
+
+    /// Returns whether the annotations of the container configure how it is
+    /// stopped
+    pub(crate) fn has_stop_annotations(&self) -> bool {
+        self.annotation(STOP_SIGNAL_ANNOTATION).is_some()
+            || self.annotation(STOP_TIMEOUT_ANNOTATION).is_some()
+    }
+
+    fn stop_signal(&self) -> Result<signal::Signal> {
+        match self.annotation(STOP_SIGNAL_ANNOTATION) {
+            Some(value) => Signal::try_from(value)
+                .map(Signal::into_raw)
+                .with_context(|| format!("invalid {STOP_SIGNAL_ANNOTATION} annotation")),
+            None => Ok(signal::Signal::SIGTERM),
+        }
+    }
+
+    fn stop_timeout(&self) -> Result<Duration> {
+        match self.annotation(STOP_TIMEOUT_ANNOTATION) {
+            Some(value) => value
+                .parse()
+                .map(Duration::from_secs)
+                .with_context(|| format!("invalid {STOP_TIMEOUT_ANNOTATION} annotation")),
+            None => Ok(DEFAULT_STOP_TIMEOUT),
+        }
+    }
+
+    fn annotation(&self, key: &str) -> Option<&str> {
+        self.state
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.get(key))
+            .map(String::as_str)
+    }
+}
+
+/// Waits until no process is left in the cgroup. Returns false if there still
+/// are processes after the timeout.
+fn wait_until_empty(cmanager: &dyn CgroupManager, timeout: Duration) -> Result<bool> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if !cmanager.is_populated()? {
+            return Ok(true);
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(false);
+        }
+        thread::sleep(STOP_POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn container_with_annotations(annotations: &[(&str, &str)]) -> Container {
+        let mut container = Container::default();
+        container.set_annotations(Some(
+            annotations
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+        ));
+        container
+    }
+
+    #[test]
+    fn test_stop_defaults() -> Result<()> {
+        let container = container_with_annotations(&[]);
+        assert_eq!(container.stop_signal()?, signal::Signal::SIGTERM);
+        assert_eq!(container.stop_timeout()?, DEFAULT_STOP_TIMEOUT);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stop_annotations() -> Result<()> {
+        let container = container_with_annotations(&[
+            (STOP_SIGNAL_ANNOTATION, "SIGQUIT"),
+            (STOP_TIMEOUT_ANNOTATION, "30"),
+        ]);
+        assert_eq!(container.stop_signal()?, signal::Signal::SIGQUIT);
+        assert_eq!(container.stop_timeout()?, Duration::from_secs(30));
+
+        let container = container_with_annotations(&[
+            (STOP_SIGNAL_ANNOTATION, "SIGNOPE"),
+            (STOP_TIMEOUT_ANNOTATION, "-1"),
+        ]);
+        assert!(container.stop_signal().is_err());
+        assert!(container.stop_timeout().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,259 @@
+use super::{Container, ContainerStatus};
+use crate::{config::YoukiConfig, error::LibcontainerError};
+use anyhow::{Context, Result};
+use libcgroups::common::ControllerOpt;
+use oci_spec::runtime::{LinuxMemoryBuilder, LinuxResources};
+
+impl Container {
+    /// Updates the resource limits of the container. The given resources are
+    /// merged into the limits that are currently in effect, so that everything
+    /// which is not set keeps its value. The merged limits are persisted and
+    /// used as the base for later updates.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use libcontainer::container::builder::ContainerBuilder;
+    /// use libcontainer::syscall::syscall::create_syscall;
+    /// use oci_spec::runtime::{LinuxPidsBuilder, LinuxResourcesBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut container = ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+    /// .as_init("/var/run/docker/bundle")
+    /// .build()?;
+    ///
+    /// let resources = LinuxResourcesBuilder::default()
+    ///     .pids(LinuxPidsBuilder::default().limit(100).build()?)
+    ///     .build()?;
+    /// container.update(&resources)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update(&mut self, resources: &LinuxResources) -> Result<(), LibcontainerError> {
+        self.refresh_status()
+            .context("failed to refresh container status")?;
+
+        if matches!(
+            self.status(),
+            ContainerStatus::Creating | ContainerStatus::Stopped
+        ) {
+            return Err(LibcontainerError::invalid_status(
+                self.id(),
+                self.status(),
+                "updated",
+            ));
+        }
+
+        let mut config = YoukiConfig::load(&self.root)
+            .with_context(|| format!("failed to load runtime spec for container {}", self.id()))?;
+        let mut merged = config.resources.clone().unwrap_or_default();
+        merge_resources(&mut merged, resources).context("failed to merge resources")?;
+
+        let use_systemd = self
+            .systemd()
+            .context("container state does not contain cgroup manager")?;
+        let cmanager =
+            libcgroups::common::create_cgroup_manager(&config.cgroup_path, use_systemd, self.id())?;
+        cmanager.apply(&ControllerOpt {
+            resources: &merged,
+            disable_oom_killer: false,
+            oom_score_adj: None,
+            freezer_state: None,
+        })?;
+
+        config.resources = Some(merged);
+        config
+            .save(&self.root)
+            .context("failed to save updated resources")?;
+
+        log::debug!("container {} updated", self.id());
+        Ok(())
+    }
+}
+
+/// Merges the limits that are set in `update` into `current`
+fn merge_resources(current: &mut LinuxResources, update: &LinuxResources) -> Result<()> {
+    if let Some(memory_update) = update.memory() {
+        // LinuxMemory has no setters, so it is rebuilt from the current limits
+        let current_memory = current.memory().unwrap_or_default();
+        let mut memory = LinuxMemoryBuilder::default();
+        if let Some(limit) = memory_update.limit().or(current_memory.limit()) {
+            memory = memory.limit(limit);
+        }
+        if let Some(reservation) = memory_update.reservation().or(current_memory.reservation()) {
+            memory = memory.reservation(reservation);
+        }
+        if let Some(swap) = memory_update.swap().or(current_memory.swap()) {
+            memory = memory.swap(swap);
+        }
+        if let Some(kernel) = memory_update.kernel().or(current_memory.kernel()) {
+            memory = memory.kernel(kernel);
+        }
+        if let Some(kernel_tcp) = memory_update.kernel_tcp().or(current_memory.kernel_tcp()) {
+            memory = memory.kernel_tcp(kernel_tcp);
+        }
+        if let Some(swappiness) = memory_update.swappiness().or(current_memory.swappiness()) {
+            memory = memory.swappiness(swappiness);
+        }
+        if let Some(disable_oom_killer) = memory_update
+            .disable_oom_killer()
+            .or(current_memory.disable_oom_killer())
+        {
+            memory = memory.disable_oom_killer(disable_oom_killer);
+        }
+        current.set_memory(Some(memory.build()?));
+    }
+
+    if let Some(cpu_update) = update.cpu() {
+        let mut cpu = current.cpu().clone().unwrap_or_default();
+        if let Some(shares) = cpu_update.shares() {
+            cpu.set_shares(Some(shares));
+        }
+        if let Some(quota) = cpu_update.quota() {
+            cpu.set_quota(Some(quota));
+        }
+        if let Some(period) = cpu_update.period() {
+            cpu.set_period(Some(period));
+        }
+        if let Some(realtime_runtime) = cpu_update.realtime_runtime() {
+            cpu.set_realtime_runtime(Some(realtime_runtime));
+        }
+        if let Some(realtime_period) = cpu_update.realtime_period() {
+            cpu.set_realtime_period(Some(realtime_period));
+        }
+        if let Some(cpus) = cpu_update.cpus() {
+            cpu.set_cpus(Some(cpus.clone()));
+        }
+        if let Some(mems) = cpu_update.mems() {
+            cpu.set_mems(Some(mems.clone()));
+        }
+        current.set_cpu(Some(cpu));
+    }
+
+    if let Some(block_io_update) = update.block_io() {
+        let mut block_io = current.block_io().clone().unwrap_or_default();
+        if let Some(weight) = block_io_update.weight() {
+            block_io.set_weight(Some(weight));
+        }
+        if let Some(leaf_weight) = block_io_update.leaf_weight() {
+            block_io.set_leaf_weight(Some(leaf_weight));
+        }
+        if let Some(weight_device) = block_io_update.weight_device() {
+            block_io.set_weight_device(Some(weight_device.clone()));
+        }
+        if let Some(devices) = block_io_update.throttle_read_bps_device() {
+            block_io.set_throttle_read_bps_device(Some(devices.clone()));
+        }
+        if let Some(devices) = block_io_update.throttle_write_bps_device() {
+            block_io.set_throttle_write_bps_device(Some(devices.clone()));
+        }
+        if let Some(devices) = block_io_update.throttle_read_iops_device() {
+            block_io.set_throttle_read_iops_device(Some(devices.clone()));
+        }
+        if let Some(devices) = block_io_update.throttle_write_iops_device() {
+            block_io.set_throttle_write_iops_device(Some(devices.clone()));
+        }
+        current.set_block_io(Some(block_io));
+    }
+
+    if let Some(pids) = update.pids() {
+        current.set_pids(Some(*pids));
+    }
+    if let Some(hugepage_limits) = update.hugepage_limits() {
+        current.set_hugepage_limits(Some(hugepage_limits.clone()));
+    }
+    if let Some(network) = update.network() {
+        current.set_network(Some(network.clone()));
+    }
+    if let Some(rdma) = update.rdma() {
+        current.set_rdma(Some(rdma.clone()));
+    }
+    if let Some(unified_update) = update.unified() {
+        let mut unified = current.unified().clone().unwrap_or_default();
+        unified.extend(unified_update.clone());
+        current.set_unified(Some(unified));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oci_spec::runtime::{
+        LinuxBlockIoBuilder, LinuxCpuBuilder, LinuxMemoryBuilder, LinuxPidsBuilder,
+        LinuxResourcesBuilder,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_merge_keeps_unset_limits() -> Result<()> {
+        let mut current = LinuxResourcesBuilder::default()
+            .memory(
+                LinuxMemoryBuilder::default()
+                    .limit(1024 * 1024 * 1024)
+                    .reservation(512 * 1024 * 1024)
+                    .build()?,
+            )
+            .cpu(
+                LinuxCpuBuilder::default()
+                    .shares(1024u64)
+                    .cpus("0-3")
+                    .build()?,
+            )
+            .pids(LinuxPidsBuilder::default().limit(100).build()?)
+            .build()?;
+
+        let update = LinuxResourcesBuilder::default()
+            .memory(
+                LinuxMemoryBuilder::default()
+                    .limit(2048i64 * 1024 * 1024)
+                    .build()?,
+            )
+            .cpu(LinuxCpuBuilder::default().quota(50000).build()?)
+            .block_io(LinuxBlockIoBuilder::default().weight(500u16).build()?)
+            .build()?;
+
+        merge_resources(&mut current, &update)?;
+
+        let memory = current.memory().as_ref().unwrap();
+        assert_eq!(memory.limit(), Some(2048i64 * 1024 * 1024));
+        assert_eq!(memory.reservation(), Some(512 * 1024 * 1024));
+
+        let cpu = current.cpu().as_ref().unwrap();
+        assert_eq!(cpu.shares(), Some(1024));
+        assert_eq!(cpu.quota(), Some(50000));
+        assert_eq!(cpu.cpus().as_deref(), Some("0-3"));
+
+        assert_eq!(current.block_io().as_ref().unwrap().weight(), Some(500));
+        assert_eq!(current.pids().as_ref().unwrap().limit(), 100);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_replaces_pids_and_extends_unified() -> Result<()> {
+        let mut current = LinuxResourcesBuilder::default()
+            .pids(LinuxPidsBuilder::default().limit(100).build()?)
+            .unified(HashMap::from([
+                ("memory.high".to_owned(), "1000000".to_owned()),
+                ("io.weight".to_owned(), "100".to_owned()),
+            ]))
+            .build()?;
+
+        let update = LinuxResourcesBuilder::default()
+            .pids(LinuxPidsBuilder::default().limit(200).build()?)
+            .unified(HashMap::from([(
+                "memory.high".to_owned(),
+                "2000000".to_owned(),
+            )]))
+            .build()?;
+
+        merge_resources(&mut current, &update)?;
+
+        assert_eq!(current.pids().as_ref().unwrap().limit(), 200);
+        let unified = current.unified().as_ref().unwrap();
+        assert_eq!(unified["memory.high"], "2000000");
+        assert_eq!(unified["io.weight"], "100");
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+use super::{Container, ContainerStatus, ExitStatus, State};
+use crate::{
+    error::LibcontainerError,
+    process::pidfd::{self, PidFd},
+};
+use anyhow::{Context, Result};
+use nix::{
+    errno::Errno,
+    poll::{poll, PollFd, PollFlags},
+    sys::wait::{waitpid, WaitStatus},
+    unistd::{self, Pid},
+};
+use procfs::process::{ProcState, Process};
+use std::{os::unix::io::AsRawFd, thread, time::Duration};
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+impl Container {
+    /// Blocks until the init process of the container has exited and returns
+    /// how it terminated. The container is marked as stopped and the exit
+    /// status is recorded in its state, so that it is still available when
+    /// the container is loaded later on.
+    ///
+    /// Only the parent of the init process can reliably collect its exit
+    /// status. Callers that want it should make themselves a child subreaper
+    /// with prctl(PR_SET_CHILD_SUBREAPER) before creating the container, so
+    /// that the init process is reparented to them. Otherwise the status can
+    /// only be read as long as the actual parent has not reaped the process
+    /// yet and `None` is returned if it has been missed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use libcontainer::container::builder::ContainerBuilder;
+    /// use libcontainer::syscall::syscall::create_syscall;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut container = ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+    /// .as_init("/var/run/docker/bundle")
+    /// .build()?;
+    ///
+    /// container.start()?;
+    /// let exit_status = container.wait()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wait(&mut self) -> Result<Option<ExitStatus>, LibcontainerError> {
+        let pid = match self.pid() {
+            Some(pid) => pid,
+            None => {
+                return Err(LibcontainerError::invalid_status(
+                    self.id(),
+                    self.status(),
+                    "waited for",
+                ))
+            }
+        };
+
+        if self.finished().is_some() {
+            // The exit has already been observed, e.g. while the container was
+            // loaded, but the process might still have to be reaped.
+            collect_exit_status(pid, self.init_process_start())?;
+            return Ok(self.exit_status());
+        }
+
+        log::debug!(
+            "waiting for init process {} of container {} to exit",
+            pid,
+            self.id()
+        );
+        let exit_status = wait_for_exit(pid, self.init_process_start())?;
+        log::debug!("container {} exited with {:?}", self.id(), exit_status);
+
+        // Other runtime invocations, e.g. start, may have changed the state in
+        // the meantime, so it is reloaded instead of being overwritten. If the
+        // container has already been deleted, there is nothing to record.
+        let exists = State::file_path(&self.root).exists();
+        if exists {
+            self.refresh_state()?;
+        }
+        self.set_status(ContainerStatus::Stopped)
+            .record_exit(exit_status);
+        if exists {
+            self.save()?;
+        }
+
+        Ok(exit_status)
+    }
+
+    /// Records that the init process has exited now with the exit status and
+    /// whether the oom killer has been involved
+    pub(crate) fn record_exit(&mut self, exit_status: Option<ExitStatus>) -> &mut Self {
+        let oom_killed = match self.stats() {
+            Ok(stats) => Some(stats.memory.oom_kill > 0),
+            Err(err) => {
+                log::debug!(
+                    "failed to check container {} for oom kills: {:?}",
+                    self.id(),
+                    err
+                );
+                None
+            }
+        };
+
+        self.set_exit_status(exit_status).set_oom_killed(oom_killed)
+    }
+}
+
+/// Waits until the process has exited and collects its exit status
+fn wait_for_exit(pid: Pid, start_time: Option<u64>) -> Result<Option<ExitStatus>> {
+    match PidFd::open(pid) {
+        Ok(pidfd) => {
+            // The pidfd pins the process, so once it has been checked to be
+            // the init process, the pid cannot be reused while waiting.
+            if !pidfd::is_same_process(pid, start_time) {
+                return Ok(None);
+            }
+            wait_for_pidfd(&pidfd)?;
+        }
+        Err(Errno::ENOSYS) => {
+            log::debug!("pidfd is not supported, falling back to polling");
+            while is_alive(pid, start_time) {
+                thread::sleep(WAIT_POLL_INTERVAL);
+            }
+        }
+        Err(Errno::ESRCH) => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("failed to open pidfd for {pid}")),
+    }
+
+    collect_exit_status(pid, start_time)
+}
+
+/// Blocks until the process has exited, which makes its pidfd readable
+fn wait_for_pidfd(pidfd: &PidFd) -> Result<()> {
+    let mut fds = [PollFd::new(pidfd.as_raw_fd(), PollFlags::POLLIN)];
+    loop {
+        match poll(&mut fds, -1) {
+            Ok(_) => return Ok(()),
+            Err(Errno::EINTR) => continue,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to poll pidfd of {}", pidfd.pid()))
+            }
+        }
+    }
+}
+
+fn is_alive(pid: Pid, start_time: Option<u64>) -> bool {
+    match Process::new(pid.as_raw()).and_then(|p| p.stat()) {
+        Ok(stat) => {
+            !matches!(stat.state(), Ok(ProcState::Zombie | ProcState::Dead))
+                && start_time.map_or(true, |start| start == stat.starttime)
+        }
+        Err(_) => false,
+    }
+}
+
+/// Collects the exit status of a process that has exited. The parent reaps
+/// the process to get it, any other process can only read it from procfs as
+/// long as the process has not been reaped.
+fn collect_exit_status(pid: Pid, start_time: Option<u64>) -> Result<Option<ExitStatus>> {
+    let stat = match Process::new(pid.as_raw()).and_then(|p| p.stat()) {
+        Ok(stat) if start_time.map_or(true, |start| start == stat.starttime) => stat,
+        // the process has already been reaped
+        _ => return Ok(None),
+    };
+
+    if stat.ppid == unistd::getpid().as_raw() {
+        let exit_status =
+            match waitpid(pid, None).with_context(|| format!("failed to wait for {pid}"))? {
+                WaitStatus::Exited(_, code) => Some(ExitStatus::Exited(code)),
+                WaitStatus::Signaled(_, signal, _) => Some(ExitStatus::Signaled(signal)),
+                _ => None,
+            };
+        return Ok(exit_status);
+    }
+
+    Ok(read_exit_status(pid, start_time))
+}
+
+/// Reads the exit status of a process that has exited, but has not been
+/// reaped by its parent yet
+pub(crate) fn read_exit_status(pid: Pid, start_time: Option<u64>) -> Option<ExitStatus> {
+    let stat = Process::new(pid.as_raw()).and_then(|p| p.stat()).ok()?;
+    if start_time.map_or(false, |start| start != stat.starttime)
+        || !matches!(stat.state(), Ok(ProcState::Zombie))
+    {
+        return None;
+    }
+    stat.exit_code.and_then(ExitStatus::from_raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_temp_dir;
+    use nix::sys::signal::{self, Signal};
+    use serial_test::serial;
+    use std::path::PathBuf;
+
+    fn spawn_child(exit_code: Option<i32>) -> Result<Pid> {
+        match unsafe { unistd::fork()? } {
+            unistd::ForkResult::Parent { child } => Ok(child),
+            unistd::ForkResult::Child => match exit_code {
+                Some(code) => unsafe { libc::_exit(code) },
+                None => loop {
+                    unistd::pause();
+                },
+            },
+        }
+    }
+
+    fn running_container(root: PathBuf, pid: Pid) -> Result<Container> {
+        let mut container = Container {
+            root,
+            ..Default::default()
+        };
+        container
+            .set_pid(pid.as_raw())
+            .set_init_process_start(Process::new(pid.as_raw())?.stat()?.starttime)
+            .set_status(ContainerStatus::Running);
+        Ok(container)
+    }
+
+    #[test]
+    #[serial]
+    fn test_wait_exited() -> Result<()> {
+        let tmp_dir = create_temp_dir("test_wait_exited")?;
+        let pid = spawn_child(Some(3))?;
+        let mut container = running_container(tmp_dir.path().to_path_buf(), pid)?;
+        container.save()?;
+
+        assert_eq!(container.wait()?, Some(ExitStatus::Exited(3)));
+        assert_eq!(container.status(), ContainerStatus::Stopped);
+
+        // the exit status is kept after the process has been reaped
+        let mut container = Container::load(tmp_dir.path().to_path_buf())?;
+        assert_eq!(container.status(), ContainerStatus::Stopped);
+        assert_eq!(container.exit_status(), Some(ExitStatus::Exited(3)));
+        assert!(container.finished().is_some());
+        assert_eq!(container.wait()?, Some(ExitStatus::Exited(3)));
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_wait_signaled() -> Result<()> {
+        let tmp_dir = create_temp_dir("test_wait_signaled")?;
+        let pid = spawn_child(None)?;
+        // the container has been deleted, so only the container is updated
+        let mut container = running_container(tmp_dir.path().join("deleted"), pid)?;
+
+        signal::kill(pid, Signal::SIGKILL)?;
+        assert_eq!(
+            container.wait()?,
+            Some(ExitStatus::Signaled(Signal::SIGKILL))
+        );
+        assert_eq!(container.status(), ContainerStatus::Stopped);
+        assert_eq!(
+            container.exit_status(),
+            Some(ExitStatus::Signaled(Signal::SIGKILL))
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_refresh_status_records_exit() -> Result<()> {
+        let tmp_dir = create_temp_dir("test_refresh_status_records_exit")?;
+        let pid = spawn_child(Some(5))?;
+        let mut container = running_container(tmp_dir.path().to_path_buf(), pid)?;
+        while is_alive(pid, container.init_process_start()) {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // the exit status can be read from the zombie without reaping it
+        container.refresh_status()?;
+        assert_eq!(container.status(), ContainerStatus::Stopped);
+        assert_eq!(container.exit_status(), Some(ExitStatus::Exited(5)));
+        assert!(container.finished().is_some());
+        // the container has no cgroup to check for oom kills
+        assert_eq!(container.oom_killed(), None);
+
+        assert_eq!(container.wait()?, Some(ExitStatus::Exited(5)));
+        assert!(Process::new(pid.as_raw()).is_err());
+        Ok(())
+    }
+}
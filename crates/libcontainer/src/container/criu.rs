@@ -0,0 +1,364 @@
+//! Client for the RPC interface of CRIU
+//!
+//! CRIU is started as a service worker (`criu swrk`) and receives a single
+//! protobuf encoded request over a socket pair. rust-criu only exposes a fixed
+//! subset of the options, so the messages are encoded here directly. Only the
+//! fields used by checkpoint and restore are implemented, see `images/rpc.proto`
+//! in the CRIU sources for the full definitions.
+
+use super::ManageCgroupsMode;
+use anyhow::{bail, Context, Result};
+use nix::fcntl::{self, FcntlArg, FdFlag};
+use nix::sys::socket::{self, AddressFamily, SockFlag, SockType};
+use nix::unistd;
+use protobuf::rt::WireType;
+use protobuf::{CodedInputStream, CodedOutputStream};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::{FromRawFd, RawFd};
+use std::process::Command;
+
+const CRIU_BINARY: &str = "criu";
+// taken from go-criu
+const RESPONSE_BUFFER_SIZE: usize = 2 * 4096;
+
+/// Values of criu_req_type, a response carries the type of its request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RequestType {
+    Dump = 1,
+    Restore = 2,
+    PreDump = 4,
+}
+
+/// The fields of criu_opts needed by checkpoint and restore. Flags are only
+/// sent if they are set, CRIU defaults all of them to false.
+#[derive(Debug, Default)]
+pub(super) struct CriuOpts {
+    pub images_dir_fd: RawFd,
+    pub work_dir_fd: Option<RawFd>,
+    pub pid: Option<i32>,
+    pub log_file: Option<String>,
+    pub log_level: Option<i32>,
+    pub root: Option<String>,
+    pub leave_running: bool,
+    pub ext_unix_sk: bool,
+    pub tcp_established: bool,
+    pub shell_job: bool,
+    pub file_locks: bool,
+    /// Address and port of the page server the memory pages are sent to
+    pub page_server: Option<(String, i32)>,
+    /// Images of a pre-dump, relative to the images directory
+    pub parent_img: Option<String>,
+    pub track_mem: bool,
+    pub auto_dedup: bool,
+    pub manage_cgroups: bool,
+    pub manage_cgroups_mode: Option<ManageCgroupsMode>,
+    /// Clone flags of the namespaces that are created empty on restore
+    pub empty_ns: Option<u32>,
+    pub lazy_pages: bool,
+    pub status_fd: Option<RawFd>,
+    pub orphan_pts_master: bool,
+    /// Mounts that are not part of the container image, as (key, value) pairs.
+    /// On dump the key is the mount point, on restore it is mapped to a source.
+    pub external_mounts: Vec<(String, String)>,
+    /// Descriptors that the restored processes inherit from CRIU, keyed by their
+    /// name at checkpoint time, e.g. `pipe:[1234]`. CRIU inherits the stdio of
+    /// youki, so the fds refer to youki's own descriptors.
+    pub inherit_fds: Vec<(String, RawFd)>,
+}
+
+impl CriuOpts {
+    fn write_to(&self, os: &mut CodedOutputStream) -> protobuf::Result<()> {
+        os.write_int32(1, self.images_dir_fd)?;
+        if let Some(pid) = self.pid {
+            os.write_int32(2, pid)?;
+        }
+        write_flag(os, 3, self.leave_running)?;
+        write_flag(os, 4, self.ext_unix_sk)?;
+        write_flag(os, 5, self.tcp_established)?;
+        write_flag(os, 7, self.shell_job)?;
+        write_flag(os, 8, self.file_locks)?;
+        if let Some(log_level) = self.log_level {
+            os.write_int32(9, log_level)?;
+        }
+        if let Some(log_file) = &self.log_file {
+            os.write_string(10, log_file)?;
+        }
+        if let Some((address, port)) = &self.page_server {
+            // criu_page_server_info
+            write_message(os, 11, |os| {
+                os.write_string(1, address)?;
+                os.write_int32(2, *port)
+            })?;
+        }
+        if let Some(root) = &self.root {
+            os.write_string(13, root)?;
+        }
+        if let Some(parent_img) = &self.parent_img {
+            os.write_string(14, parent_img)?;
+        }
+        write_flag(os, 15, self.track_mem)?;
+        write_flag(os, 16, self.auto_dedup)?;
+        if let Some(work_dir_fd) = self.work_dir_fd {
+            os.write_int32(17, work_dir_fd)?;
+        }
+        for (key, value) in &self.external_mounts {
+            // ext_mount_map
+            write_message(os, 23, |os| {
+                os.write_string(1, key)?;
+                os.write_string(2, value)
+            })?;
+        }
+        write_flag(os, 24, self.manage_cgroups)?;
+        for (key, fd) in &self.inherit_fds {
+            // inherit_fd
+            write_message(os, 27, |os| {
+                os.write_string(1, key)?;
+                os.write_int32(2, *fd)
+            })?;
+        }
+        if let Some(mode) = self.manage_cgroups_mode {
+            // criu_cg_mode
+            let mode = match mode {
+                ManageCgroupsMode::Ignore => 0,
+                ManageCgroupsMode::Soft => 3,
+                ManageCgroupsMode::Full => 4,
+                ManageCgroupsMode::Strict => 5,
+            };
+            os.write_enum(34, mode)?;
+        }
+        if let Some(empty_ns) = self.empty_ns {
+            os.write_uint32(38, empty_ns)?;
+        }
+        write_flag(os, 48, self.lazy_pages)?;
+        if let Some(status_fd) = self.status_fd {
+            os.write_int32(49, status_fd)?;
+        }
+        write_flag(os, 50, self.orphan_pts_master)?;
+
+        Ok(())
+    }
+}
+
+fn write_flag(os: &mut CodedOutputStream, field_number: u32, value: bool) -> protobuf::Result<()> {
+    if value {
+        os.write_bool(field_number, true)?;
+    }
+
+    Ok(())
+}
+
+fn write_message<F>(os: &mut CodedOutputStream, field_number: u32, write: F) -> protobuf::Result<()>
+where
+    F: FnOnce(&mut CodedOutputStream) -> protobuf::Result<()>,
+{
+    let mut bytes = Vec::new();
+    {
+        let mut nested = CodedOutputStream::vec(&mut bytes);
+        write(&mut nested)?;
+        nested.flush()?;
+    }
+    os.write_bytes(field_number, &bytes)
+}
+
+fn encode_request(request_type: RequestType, opts: &CriuOpts) -> protobuf::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    {
+        let mut os = CodedOutputStream::vec(&mut bytes);
+        os.write_enum(1, request_type as i32)?;
+        write_message(&mut os, 2, |os| opts.write_to(os))?;
+        os.flush()?;
+    }
+
+    Ok(bytes)
+}
+
+/// The fields of criu_resp that are needed to tell whether a request succeeded
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Response {
+    request_type: i32,
+    success: bool,
+    errno: Option<i32>,
+    errmsg: Option<String>,
+}
+
+fn decode_response(bytes: &[u8]) -> Result<Response> {
+    let mut is = CodedInputStream::from_bytes(bytes);
+    let mut response = Response::default();
+    while let Some(tag) = is.read_raw_tag_or_eof()? {
+        match tag >> 3 {
+            1 => response.request_type = is.read_int32()?,
+            2 => response.success = is.read_bool()?,
+            7 => response.errno = Some(is.read_int32()?),
+            9 => response.errmsg = Some(is.read_string()?),
+            _ => {
+                let wire_type = WireType::new(tag & 7)
+                    .with_context(|| format!("invalid wire type in tag {tag}"))?;
+                is.skip_field(wire_type)?;
+            }
+        }
+    }
+
+    Ok(response)
+}
+
+/// Dumps the process tree of `opts.pid` into the images directory
+pub(super) fn dump(opts: &CriuOpts) -> Result<()> {
+    call(RequestType::Dump, opts)
+}
+
+/// Copies the memory of the process tree of `opts.pid` into the images
+/// directory while it keeps running
+pub(super) fn pre_dump(opts: &CriuOpts) -> Result<()> {
+    call(RequestType::PreDump, opts)
+}
+
+/// Restores the process tree from the images directory
+pub(super) fn restore(opts: &CriuOpts) -> Result<()> {
+    call(RequestType::Restore, opts)
+}
+
+fn call(request_type: RequestType, opts: &CriuOpts) -> Result<()> {
+    let (fd, criu_fd) = socket::socketpair(
+        AddressFamily::Unix,
+        SockType::SeqPacket,
+        None,
+        SockFlag::empty(),
+    )
+    .context("failed to create socket pair for CRIU")?;
+    let mut socket = unsafe { File::from_raw_fd(fd) };
+    fcntl::fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+
+    let spawned = Command::new(CRIU_BINARY)
+        .arg("swrk")
+        .arg(criu_fd.to_string())
+        .spawn();
+    unistd::close(criu_fd)?;
+    let mut criu = spawned.with_context(|| format!("failed to execute {CRIU_BINARY}"))?;
+
+    let result = send_request(&mut socket, request_type, opts);
+    // CRIU exits once the request has been served and the socket is closed
+    drop(socket);
+    criu.wait().context("failed to wait for CRIU")?;
+
+    result
+}
+
+fn send_request(socket: &mut File, request_type: RequestType, opts: &CriuOpts) -> Result<()> {
+    let request = encode_request(request_type, opts).context("failed to encode CRIU request")?;
+    socket
+        .write_all(&request)
+        .context("failed to send CRIU request")?;
+
+    let mut buffer = [0; RESPONSE_BUFFER_SIZE];
+    let read = socket
+        .read(&mut buffer)
+        .context("failed to read CRIU response")?;
+    if read == 0 {
+        bail!(
+            "CRIU exited without responding to the {:?} request",
+            request_type
+        );
+    }
+
+    let response = decode_response(&buffer[..read]).context("failed to decode CRIU response")?;
+    if !response.success {
+        bail!(
+            "CRIU {:?} request failed with message: {} error: {}",
+            request_type,
+            response.errmsg.unwrap_or_default(),
+            response.errno.unwrap_or_default()
+        );
+    }
+    if response.request_type != request_type as i32 {
+        bail!(
+            "unexpected CRIU response type {} to the {:?} request",
+            response.request_type,
+            request_type
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_request() -> Result<()> {
+        let opts = CriuOpts {
+            images_dir_fd: 3,
+            inherit_fds: vec![("pipe:[1]".to_owned(), 0)],
+            ..Default::default()
+        };
+
+        let mut expected = vec![
+            0x08, 0x02, // type: RESTORE
+            0x12, 0x11, // opts
+            0x08, 0x03, // images_dir_fd: 3
+            0xda, 0x01, 0x0c, // inherit_fd
+            0x0a, 0x08, // key
+        ];
+        expected.extend_from_slice(b"pipe:[1]");
+        expected.extend_from_slice(&[0x10, 0x00]); // fd: 0
+        assert_eq!(encode_request(RequestType::Restore, &opts)?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_pre_dump_request() -> Result<()> {
+        let opts = CriuOpts {
+            images_dir_fd: 3,
+            page_server: Some(("10.0.0.1".to_owned(), 27)),
+            track_mem: true,
+            manage_cgroups_mode: Some(ManageCgroupsMode::Soft),
+            ..Default::default()
+        };
+
+        let mut expected = vec![
+            0x08, 0x04, // type: PRE_DUMP
+            0x12, 0x15, // opts
+            0x08, 0x03, // images_dir_fd: 3
+            0x5a, 0x0c, // ps
+            0x0a, 0x08, // address
+        ];
+        expected.extend_from_slice(b"10.0.0.1");
+        expected.extend_from_slice(&[
+            0x10, 0x1b, // port: 27
+            0x78, 0x01, // track_mem: true
+            0x90, 0x02, 0x03, // manage_cgroups_mode: SOFT
+        ]);
+        assert_eq!(encode_request(RequestType::PreDump, &opts)?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_response() -> Result<()> {
+        let mut bytes = Vec::new();
+        {
+            let mut os = CodedOutputStream::vec(&mut bytes);
+            os.write_enum(1, RequestType::Dump as i32)?;
+            os.write_bool(2, false)?;
+            // dump, which is skipped
+            os.write_bytes(3, &[0x08, 0x01])?;
+            os.write_int32(7, 1)?;
+            os.write_string(9, "failed to dump")?;
+            os.flush()?;
+        }
+
+        assert_eq!(
+            decode_response(&bytes)?,
+            Response {
+                request_type: RequestType::Dump as i32,
+                success: false,
+                errno: Some(1),
+                errmsg: Some("failed to dump".to_owned()),
+            }
+        );
+
+        Ok(())
+    }
+}
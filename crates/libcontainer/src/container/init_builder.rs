@@ -1,15 +1,16 @@
 use anyhow::{bail, Context, Result};
 use nix::unistd;
-use oci_spec::runtime::Spec;
+use oci_spec::runtime::{LinuxNamespaceType, Spec};
 use rootless::Rootless;
 use std::{
-    fs,
+    fs::{self, File},
+    io::BufReader,
     path::{Path, PathBuf},
 };
 
 use crate::{
-    apparmor, config::YoukiConfig, notify_socket::NOTIFY_FILE, process::args::ContainerType,
-    rootless, tty, utils,
+    apparmor, config::YoukiConfig, error::LibcontainerError, notify_socket::NOTIFY_FILE,
+    process::args::ContainerType, rootfs::idmap, rootless, tty, utils,
 };
 
 use super::{
@@ -41,11 +42,9 @@ impl<'a> InitContainerBuilder<'a> {
     }
 
     /// Creates a new container
-    pub fn build(self) -> Result<Container> {
-        let spec = self.load_spec().context("failed to load spec")?;
-        let container_dir = self
-            .create_container_dir()
-            .context("failed to create container dir")?;
+    pub fn build(self) -> Result<Container, LibcontainerError> {
+        let spec = self.load_spec().map_err(LibcontainerError::InvalidSpec)?;
+        let container_dir = self.create_container_dir()?;
 
         let mut container = self
             .create_container_state(&container_dir)
@@ -54,10 +53,12 @@ impl<'a> InitContainerBuilder<'a> {
             .set_systemd(self.use_systemd)
             .set_annotations(spec.annotations().clone());
 
-        unistd::chdir(&container_dir)?;
+        unistd::chdir(&container_dir)
+            .with_context(|| format!("failed to change directory to {container_dir:?}"))?;
         let notify_path = container_dir.join(NOTIFY_FILE);
         // convert path of root file system of the container to absolute path
-        let rootfs = fs::canonicalize(spec.root().as_ref().context("no root in spec")?.path())?;
+        let rootfs = fs::canonicalize(spec.root().as_ref().context("no root in spec")?.path())
+            .context("failed to canonicalize rootfs")?;
 
         // if socket file path is given in commandline options,
         // get file descriptors of console socket
@@ -91,20 +92,25 @@ impl<'a> InitContainerBuilder<'a> {
             container: Some(container.clone()),
             preserve_fds: self.base.preserve_fds,
             detached: false, // TODO this should be set properly based on how the command is given
+            executors: self.base.executors,
         };
 
-        builder_impl.create()?;
+        builder_impl
+            .create()
+            .map_err(LibcontainerError::from_hook_chain)?;
         container.refresh_state()?;
 
         Ok(container)
     }
 
-    fn create_container_dir(&self) -> Result<PathBuf> {
+    fn create_container_dir(&self) -> Result<PathBuf, LibcontainerError> {
         let container_dir = self.base.root_path.join(&self.base.container_id);
         log::debug!("container directory will be {:?}", container_dir);
 
         if container_dir.exists() {
-            bail!("container {} already exists", self.base.container_id);
+            return Err(LibcontainerError::AlreadyExists(
+                self.base.container_id.clone(),
+            ));
         }
 
         utils::create_dir_all(&container_dir).context("failed to create container dir")?;
@@ -114,6 +120,13 @@ impl<'a> InitContainerBuilder<'a> {
 
     fn load_spec(&self) -> Result<Spec> {
         let source_spec_path = self.bundle.join("config.json");
+        let config: serde_json::Value = serde_json::from_reader(BufReader::new(
+            File::open(&source_spec_path)
+                .with_context(|| format!("failed to open {source_spec_path:?}"))?,
+        ))
+        .with_context(|| format!("failed to parse {source_spec_path:?}"))?;
+        idmap::validate_mount_mappings(&config).context("invalid idmapped mounts")?;
+
         let mut spec = Spec::load(source_spec_path)?;
         Self::validate_spec(&spec).context("failed to validate runtime spec")?;
 
@@ -142,6 +155,24 @@ impl<'a> InitContainerBuilder<'a> {
             }
         }
 
+        if let Some(linux) = spec.linux() {
+            if linux.time_offsets().is_some() {
+                let creates_time_ns = linux.namespaces().as_ref().map_or(false, |namespaces| {
+                    namespaces
+                        .iter()
+                        .any(|ns| ns.typ() == LinuxNamespaceType::Time && ns.path().is_none())
+                });
+                if !creates_time_ns {
+                    bail!(
+                        "time offsets are specified in runtime spec, \
+                    but no new time namespace is created"
+                    );
+                }
+            }
+        }
+
+        idmap::validate(spec).context("invalid idmapped mounts")?;
+
         Ok(())
     }
 
@@ -1,4 +1,6 @@
 //! Container management
+#[cfg(feature = "async")]
+pub mod async_container;
 /// This crate is responsible for the creation of containers. It provides a builder that can
 /// be used to configure and create containers. We distinguish between an init container for which
 /// namespaces and cgroups will be created (usually) and a tenant container process that will move
@@ -13,11 +15,20 @@ mod container_delete;
 mod container_events;
 mod container_kill;
 mod container_pause;
+mod container_restore;
 mod container_resume;
 mod container_start;
+mod container_stop;
+mod container_update;
+mod container_wait;
+mod criu;
 pub mod init_builder;
 pub mod state;
 pub mod tenant_builder;
 pub use container::CheckpointOptions;
 pub use container::Container;
-pub use state::{ContainerProcessState, ContainerStatus, State};
+pub use container::ManageCgroupsMode;
+pub use container::RestoreOptions;
+pub use container_events::{Event, EventStream, EventType};
+pub use container_stop::{DEFAULT_STOP_TIMEOUT, STOP_SIGNAL_ANNOTATION, STOP_TIMEOUT_ANNOTATION};
+pub use state::{ContainerProcessState, ContainerStatus, ExitStatus, State};
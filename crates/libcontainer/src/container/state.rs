@@ -8,6 +8,7 @@ use std::{fs::File, path::Path};
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use nix::sys::signal::Signal;
 use serde::{Deserialize, Serialize};
 
 /// Indicates status of the container
@@ -72,6 +73,39 @@ impl Display for ContainerStatus {
     }
 }
 
+/// How the init process of a container has terminated
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// The process exited with the exit code
+    Exited(i32),
+    /// The process was terminated by the signal
+    Signaled(Signal),
+}
+
+impl ExitStatus {
+    /// Decodes a status in the format reported by waitpid(2)
+    pub fn from_raw(status: i32) -> Option<Self> {
+        if libc::WIFEXITED(status) {
+            Some(Self::Exited(libc::WEXITSTATUS(status)))
+        } else if libc::WIFSIGNALED(status) {
+            Signal::try_from(libc::WTERMSIG(status))
+                .ok()
+                .map(Self::Signaled)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the exit code, following the convention of shells to report
+    /// 128 plus the signal number for processes terminated by a signal
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::Exited(code) => *code,
+            Self::Signaled(signal) => 128 + *signal as i32,
+        }
+    }
+}
+
 /// Stores the state information of the container
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
@@ -85,6 +119,10 @@ pub struct State {
     // Pid is the process ID for the container process.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pid: Option<i32>,
+    // Start time of the container process in clock ticks after boot. Used to
+    // detect that the pid has been reused by another process.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init_process_start: Option<u64>,
     // Bundle is the path to the container's bundle directory.
     pub bundle: PathBuf,
     // Annotations are key values associated with the container.
@@ -98,6 +136,23 @@ pub struct State {
     pub creator: Option<u32>,
     // Specifies if systemd should be used to manage cgroups
     pub use_systemd: Option<bool>,
+    // Name of the executor that runs the container workload
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub executor: Option<String>,
+    // Exit code of the container process, 128 plus the signal number if it
+    // was terminated by a signal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_status: Option<i32>,
+    // Name of the signal that terminated the container process
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_signal: Option<String>,
+    // Time at which the exit of the container process was observed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished: Option<DateTime<Utc>>,
+    // Whether the oom killer killed a process of the container. Unknown if
+    // the cgroup could not be inspected when the exit was observed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oom_killed: Option<bool>,
 }
 
 impl State {
@@ -114,11 +169,17 @@ impl State {
             id: container_id.to_string(),
             status,
             pid,
+            init_process_start: None,
             bundle,
             annotations: Some(HashMap::default()),
             created: None,
             creator: None,
             use_systemd: None,
+            executor: None,
+            exit_status: None,
+            exit_signal: None,
+            finished: None,
+            oom_killed: None,
         }
     }
 
@@ -183,6 +244,21 @@ pub struct ContainerProcessState {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_exit_status_from_raw() {
+        assert_eq!(ExitStatus::from_raw(0), Some(ExitStatus::Exited(0)));
+        assert_eq!(ExitStatus::from_raw(3 << 8), Some(ExitStatus::Exited(3)));
+        assert_eq!(
+            ExitStatus::from_raw(Signal::SIGKILL as i32),
+            Some(ExitStatus::Signaled(Signal::SIGKILL))
+        );
+        // stopped by SIGSTOP
+        assert_eq!(ExitStatus::from_raw(0x137f), None);
+
+        assert_eq!(ExitStatus::Exited(3).code(), 3);
+        assert_eq!(ExitStatus::Signaled(Signal::SIGKILL).code(), 137);
+    }
+
     #[test]
     fn test_creating_status() {
         let cstatus = ContainerStatus::default();
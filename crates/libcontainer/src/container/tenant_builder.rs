@@ -1,4 +1,4 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use caps::Capability;
 use nix::fcntl::OFlag;
 use nix::unistd::{self, close, pipe2, read, Pid};
@@ -20,13 +20,14 @@ use std::{
     str::FromStr,
 };
 
+use crate::error::LibcontainerError;
 use crate::process::args::ContainerType;
 use crate::{capabilities::CapabilityExt, container::builder_impl::ContainerBuilderImpl};
 use crate::{notify_socket::NotifySocket, rootless::Rootless, tty, utils};
 
 use super::{builder::ContainerBuilder, Container};
 
-const NAMESPACE_TYPES: &[&str] = &["ipc", "uts", "net", "pid", "mnt", "cgroup"];
+const NAMESPACE_TYPES: &[&str] = &["ipc", "uts", "net", "pid", "mnt", "cgroup", "time"];
 const TENANT_NOTIFY: &str = "tenant-notify-";
 const TENANT_TTY: &str = "tenant-tty-";
 
@@ -41,6 +42,7 @@ pub struct TenantContainerBuilder<'a> {
     capabilities: Vec<String>,
     process: Option<PathBuf>,
     detached: bool,
+    tty: bool,
 }
 
 impl<'a> TenantContainerBuilder<'a> {
@@ -57,6 +59,7 @@ impl<'a> TenantContainerBuilder<'a> {
             capabilities: Vec::new(),
             process: None,
             detached: false,
+            tty: false,
         }
     }
 
@@ -98,26 +101,32 @@ impl<'a> TenantContainerBuilder<'a> {
         self
     }
 
+    /// Allocates a pseudo terminal for the process. Ignored if a process.json
+    /// is given, which decides this on its own.
+    pub fn with_tty(mut self, tty: bool) -> Self {
+        self.tty = tty;
+        self
+    }
+
     /// Joins an existing container
-    pub fn build(self) -> Result<Pid> {
-        let container_dir = self
-            .lookup_container_dir()
-            .context("failed to look up container dir")?;
-        let container = self
-            .load_container_state(container_dir.clone())
-            .context("failed to load container state")?;
+    pub fn build(self) -> Result<Pid, LibcontainerError> {
+        let container_dir = self.lookup_container_dir()?;
+        let container = self.load_container_state(container_dir.clone())?;
         let mut spec = self
             .load_init_spec(&container)
-            .context("failed to load init spec")?;
+            .context("failed to load init spec")
+            .map_err(LibcontainerError::InvalidSpec)?;
         self.adapt_spec_for_tenant(&mut spec, &container)
             .context("failed to adapt spec for tenant")?;
 
         log::debug!("{:#?}", spec);
 
-        unistd::chdir(&container_dir)?;
+        unistd::chdir(&container_dir)
+            .with_context(|| format!("failed to change directory to {container_dir:?}"))?;
         let notify_path = Self::setup_notify_listener(&container_dir)?;
         // convert path of root file system of the container to absolute path
-        let rootfs = fs::canonicalize(spec.root().as_ref().context("no root in spec")?.path())?;
+        let rootfs = fs::canonicalize(spec.root().as_ref().context("no root in spec")?.path())
+            .context("failed to canonicalize rootfs")?;
 
         // if socket file path is given in commandline options,
         // get file descriptors of console socket
@@ -126,7 +135,8 @@ impl<'a> TenantContainerBuilder<'a> {
         let use_systemd = self.should_use_systemd(&container);
         let rootless = Rootless::new(&spec)?;
 
-        let (read_end, write_end) = pipe2(OFlag::O_CLOEXEC)?;
+        let (read_end, write_end) =
+            pipe2(OFlag::O_CLOEXEC).context("failed to create exec notify pipe")?;
 
         let mut builder_impl = ContainerBuilderImpl {
             container_type: ContainerType::TenantContainer {
@@ -144,6 +154,7 @@ impl<'a> TenantContainerBuilder<'a> {
             container: None,
             preserve_fds: self.base.preserve_fds,
             detached: self.detached,
+            executors: self.base.executors,
         };
 
         let pid = builder_impl.create()?;
@@ -151,18 +162,20 @@ impl<'a> TenantContainerBuilder<'a> {
         let mut notify_socket = NotifySocket::new(notify_path);
         notify_socket.notify_container_start()?;
 
-        close(write_end)?;
+        close(write_end).context("failed to close exec notify pipe")?;
 
         let mut err_str_buf = Vec::new();
 
         loop {
             let mut buf = [0; 3];
-            match read(read_end, &mut buf)? {
+            match read(read_end, &mut buf).context("failed to read from exec notify pipe")? {
                 0 => {
                     if err_str_buf.is_empty() {
                         return Ok(pid);
                     } else {
-                        bail!(String::from_utf8_lossy(&err_str_buf).to_string());
+                        return Err(
+                            anyhow!(String::from_utf8_lossy(&err_str_buf).to_string()).into()
+                        );
                     }
                 }
                 _ => {
@@ -172,10 +185,10 @@ impl<'a> TenantContainerBuilder<'a> {
         }
     }
 
-    fn lookup_container_dir(&self) -> Result<PathBuf> {
+    fn lookup_container_dir(&self) -> Result<PathBuf, LibcontainerError> {
         let container_dir = self.base.root_path.join(&self.base.container_id);
         if !container_dir.exists() {
-            bail!("container {} does not exist", self.base.container_id);
+            return Err(LibcontainerError::NotFound(self.base.container_id.clone()));
         }
 
         Ok(container_dir)
@@ -192,13 +205,14 @@ impl<'a> TenantContainerBuilder<'a> {
         Ok(spec)
     }
 
-    fn load_container_state(&self, container_dir: PathBuf) -> Result<Container> {
+    fn load_container_state(&self, container_dir: PathBuf) -> Result<Container, LibcontainerError> {
         let container = Container::load(container_dir)?;
         if !container.can_exec() {
-            bail!(
-                "Cannot exec as container is in state {}",
-                container.status()
-            );
+            return Err(LibcontainerError::invalid_status(
+                container.id(),
+                container.status(),
+                "joined",
+            ));
         }
 
         Ok(container)
@@ -210,7 +224,8 @@ impl<'a> TenantContainerBuilder<'a> {
         } else {
             let mut process_builder = ProcessBuilder::default()
                 .args(self.get_args()?)
-                .env(self.get_environment()?);
+                .env(self.get_environment()?)
+                .terminal(self.tty);
             if let Some(cwd) = self.get_working_dir()? {
                 process_builder = process_builder.cwd(cwd);
             }
@@ -223,6 +238,17 @@ impl<'a> TenantContainerBuilder<'a> {
                 process_builder = process_builder.capabilities(caps);
             }
 
+            // Like runc, exec'd processes inherit the scheduling settings of the
+            // container process unless a process.json is given.
+            if let Some(process) = spec.process() {
+                if let Some(scheduler) = process.scheduler() {
+                    process_builder = process_builder.scheduler(scheduler.clone());
+                }
+                if let Some(io_priority) = process.io_priority() {
+                    process_builder = process_builder.io_priority(*io_priority);
+                }
+            }
+
             process_builder.build()?
         };
 
@@ -232,7 +258,14 @@ impl<'a> TenantContainerBuilder<'a> {
 
         let init_process = procfs::process::Process::new(container.pid().unwrap().as_raw())?;
         let ns = self.get_namespaces(init_process.namespaces()?)?;
-        let linux = LinuxBuilder::default().namespaces(ns).build()?;
+        let mut linux = LinuxBuilder::default().namespaces(ns).build()?;
+        // The personality is a property of the container, so it applies to the
+        // tenant process as well.
+        linux.set_personality(
+            spec.linux()
+                .as_ref()
+                .and_then(|linux| linux.personality().clone()),
+        );
 
         spec.set_process(Some(process)).set_linux(Some(linux));
         Ok(())
@@ -0,0 +1,128 @@
+//! Errors returned by the public API of libcontainer
+//!
+//! Failures that callers are expected to handle, e.g. by mapping them to a
+//! status code, have their own variant. All other failures are reported as
+//! [`LibcontainerError::Other`] and keep their context chain.
+use libcgroups::common::CgroupError;
+
+use crate::container::ContainerStatus;
+use crate::hooks::HookError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LibcontainerError {
+    /// No container with the given id exists
+    #[error("container {0} does not exist")]
+    NotFound(String),
+    /// A container with the given id already exists
+    #[error("container {0} already exists")]
+    AlreadyExists(String),
+    /// The container id contains characters that are not allowed
+    #[error("invalid container ID format: {0:?}")]
+    InvalidId(String),
+    /// The operation is not allowed in the current status of the container
+    #[error("{id} could not be {operation} because it was {status:?}")]
+    InvalidStatus {
+        id: String,
+        status: ContainerStatus,
+        operation: &'static str,
+    },
+    /// The runtime spec could not be loaded or failed validation
+    #[error("invalid runtime spec")]
+    InvalidSpec(#[source] anyhow::Error),
+    /// The cgroup of the container could not be managed
+    #[error("failed to manage cgroup")]
+    Cgroup(#[from] CgroupError),
+    /// The root filesystem of the container could not be prepared
+    #[error("failed to prepare rootfs")]
+    Rootfs(#[source] anyhow::Error),
+    /// A hook failed, which aborts the operation
+    #[error("failed to run hook")]
+    Hook(#[from] HookError),
+    /// The seccomp filter of the container could not be installed
+    #[error("failed to initialize seccomp")]
+    Seccomp(#[source] anyhow::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl LibcontainerError {
+    pub(crate) fn invalid_status(
+        id: &str,
+        status: ContainerStatus,
+        operation: &'static str,
+    ) -> Self {
+        Self::InvalidStatus {
+            id: id.to_owned(),
+            status,
+            operation,
+        }
+    }
+
+    /// Returns the failed hook as [`LibcontainerError::Hook`] if one caused the
+    /// error and [`LibcontainerError::Other`] otherwise. The context of the
+    /// hook error is only logged.
+    pub(crate) fn from_hook_chain(err: anyhow::Error) -> Self {
+        if err.downcast_ref::<HookError>().is_none() {
+            return Self::Other(err);
+        }
+
+        log::error!("{:?}", err);
+        match err.downcast::<HookError>() {
+            Ok(hook_err) => Self::Hook(hook_err),
+            Err(err) => Self::Other(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_error_source_chain() {
+        let err = LibcontainerError::InvalidSpec(
+            anyhow::anyhow!("unsupported version").context("failed to validate runtime spec"),
+        );
+        assert_eq!(err.to_string(), "invalid runtime spec");
+        let source = err.source().expect("error should have a source");
+        assert_eq!(source.to_string(), "failed to validate runtime spec");
+
+        let err = LibcontainerError::from(CgroupError::SystemdUnavailable);
+        assert!(matches!(
+            err,
+            LibcontainerError::Cgroup(CgroupError::SystemdUnavailable)
+        ));
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn test_from_hook_chain() {
+        let hook_err = HookError::NonZeroExit {
+            path: "/bin/false".into(),
+            code: 1,
+        };
+        let err = anyhow::Error::from(hook_err)
+            .context("failed to run prestart hooks")
+            .context("failed to create container");
+        assert!(matches!(
+            LibcontainerError::from_hook_chain(err),
+            LibcontainerError::Hook(HookError::NonZeroExit { code: 1, .. })
+        ));
+
+        let err = anyhow::anyhow!("failed to create container");
+        assert!(matches!(
+            LibcontainerError::from_hook_chain(err),
+            LibcontainerError::Other(_)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_status() {
+        let err = LibcontainerError::invalid_status("sample", ContainerStatus::Stopped, "paused");
+        assert_eq!(
+            err.to_string(),
+            "sample could not be paused because it was Stopped"
+        );
+    }
+}
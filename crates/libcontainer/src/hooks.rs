@@ -1,142 +1,269 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{bail, Result};
 use nix::{sys::signal, unistd::Pid};
 use oci_spec::runtime::Hook;
 use std::{
-    collections::HashMap, fmt, io::ErrorKind, io::Write, os::unix::prelude::CommandExt, process,
-    thread, time,
+    collections::HashMap,
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    os::unix::prelude::{CommandExt, ExitStatusExt},
+    path::{Path, PathBuf},
+    process, thread, time,
 };
 
-use crate::{container::Container, utils};
-// A special error used to signal a timeout. We want to differentiate between a
-// timeout vs. other error.
-#[derive(Debug)]
-pub struct HookTimeoutError;
-impl std::error::Error for HookTimeoutError {}
-impl fmt::Display for HookTimeoutError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        "hook command timeout".fmt(f)
+use crate::{container::Container, container::State, utils};
+
+/// Time to wait for the output of a hook after it exited. The output is only
+/// complete once every process that inherited stdout and stderr of the hook
+/// closed them, which a hook that left a daemon behind never does.
+const OUTPUT_TIMEOUT: time::Duration = time::Duration::from_secs(1);
+
+/// Errors of a single hook. The path of the hook is attached to each of them.
+#[derive(Debug, thiserror::Error)]
+pub enum HookError {
+    /// The hook could not be started
+    #[error("failed to execute hook {path:?}")]
+    Spawn { path: PathBuf, source: io::Error },
+    /// The container state could not be written to stdin of the hook
+    #[error("failed to write container state to stdin of hook {path:?}")]
+    WriteState { path: PathBuf, source: io::Error },
+    /// Waiting for the hook to exit failed
+    #[error("failed to wait for hook {path:?}")]
+    Wait { path: PathBuf, source: io::Error },
+    /// The hook exited with a non-zero exit code
+    #[error("hook {path:?} exited with non-zero code {code}")]
+    NonZeroExit { path: PathBuf, code: i32 },
+    /// The hook has been killed by a signal
+    #[error("hook {path:?} has been killed by signal {signal}")]
+    Killed { path: PathBuf, signal: i32 },
+    /// The hook did not exit within its timeout and has been killed
+    #[error("hook {path:?} timed out after {timeout} seconds")]
+    Timeout { path: PathBuf, timeout: i64 },
+}
+
+impl HookError {
+    /// Path of the hook that failed
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            Self::Spawn { path, .. }
+            | Self::WriteState { path, .. }
+            | Self::Wait { path, .. }
+            | Self::NonZeroExit { path, .. }
+            | Self::Killed { path, .. }
+            | Self::Timeout { path, .. } => path,
+        }
     }
 }
 
+/// Runs the hooks one after another and fails on the first hook that fails.
+/// This is the behavior the OCI spec requires for the prestart,
+/// createRuntime, createContainer and startContainer hooks. The returned error
+/// can be downcast to [`HookError`].
 pub fn run_hooks(hooks: Option<&Vec<Hook>>, container: Option<&Container>) -> Result<()> {
-    if container.is_none() {
-        bail!("container state is required to run hook");
+    let state = container_state(container)?;
+    for hook in hooks.into_iter().flatten() {
+        run_hook(hook, state)?;
     }
 
-    let state = &container.unwrap().state;
-
-    if let Some(hooks) = hooks {
-        for hook in hooks {
-            let mut hook_command = process::Command::new(hook.path());
-            // Based on OCI spec, the first argument of the args vector is the
-            // arg0, which can be different from the path.  For example, path
-            // may be "/usr/bin/true" and arg0 is set to "true". However, rust
-            // command differenciates arg0 from args, where rust command arg
-            // doesn't include arg0. So we have to make the split arg0 from the
-            // rest of args.
-            if let Some((arg0, args)) = hook.args().as_ref().and_then(|a| a.split_first()) {
-                log::debug!("run_hooks arg0: {:?}, args: {:?}", arg0, args);
-                hook_command.arg0(arg0).args(args)
-            } else {
-                hook_command.arg0(&hook.path().display().to_string())
-            };
-
-            let envs: HashMap<String, String> = if let Some(env) = hook.env() {
-                utils::parse_env(env)
-            } else {
-                HashMap::new()
-            };
-            log::debug!("run_hooks envs: {:?}", envs);
-
-            let mut hook_process = hook_command
-                .env_clear()
-                .envs(envs)
-                .stdin(process::Stdio::piped())
-                .spawn()
-                .with_context(|| "Failed to execute hook")?;
-            let hook_process_pid = Pid::from_raw(hook_process.id() as i32);
-            // Based on the OCI spec, we need to pipe the container state into
-            // the hook command through stdin.
-            if let Some(stdin) = &mut hook_process.stdin {
-                // We want to ignore BrokenPipe here. A BrokenPipe indicates
-                // either the hook is crashed/errored or it ran successfully.
-                // Either way, this is an indication that the hook command
-                // finished execution.  If the hook command was successful,
-                // which we will check later in this function, we should not
-                // fail this step here. We still want to check for all the other
-                // error, in the case that the hook command is waiting for us to
-                // write to stdin.
-                let encoded_state =
-                    serde_json::to_string(state).context("failed to encode container state")?;
-                if let Err(e) = stdin.write_all(encoded_state.as_bytes()) {
-                    if e.kind() != ErrorKind::BrokenPipe {
-                        // Not a broken pipe. The hook command may be waiting
-                        // for us.
-                        let _ = signal::kill(hook_process_pid, signal::Signal::SIGKILL);
-                        bail!("failed to write container state to stdin: {:?}", e);
-                    }
-                }
+    Ok(())
+}
+
+/// Runs all hooks and only logs a warning for the ones that fail. This is the
+/// behavior the OCI spec requires for the poststart and poststop hooks.
+pub fn run_hooks_ignoring_failures(
+    hooks: Option<&Vec<Hook>>,
+    container: Option<&Container>,
+) -> Result<()> {
+    let state = container_state(container)?;
+    for hook in hooks.into_iter().flatten() {
+        if let Err(err) = run_hook(hook, state) {
+            log::warn!("{:#}", anyhow::Error::from(err));
+        }
+    }
+
+    Ok(())
+}
+
+fn container_state(container: Option<&Container>) -> Result<&State> {
+    match container {
+        Some(container) => Ok(&container.state),
+        None => bail!("container state is required to run hook"),
+    }
+}
+
+fn run_hook(hook: &Hook, state: &State) -> Result<(), HookError> {
+    let path = hook.path().clone();
+    let mut hook_command = process::Command::new(hook.path());
+    // Based on OCI spec, the first argument of the args vector is the
+    // arg0, which can be different from the path.  For example, path
+    // may be "/usr/bin/true" and arg0 is set to "true". However, rust
+    // command differenciates arg0 from args, where rust command arg
+    // doesn't include arg0. So we have to make the split arg0 from the
+    // rest of args.
+    if let Some((arg0, args)) = hook.args().as_ref().and_then(|a| a.split_first()) {
+        log::debug!("run_hooks arg0: {:?}, args: {:?}", arg0, args);
+        hook_command.arg0(arg0).args(args)
+    } else {
+        hook_command.arg0(&hook.path().display().to_string())
+    };
+
+    // Every hook gets exactly the environment that is configured for it
+    let envs: HashMap<String, String> = if let Some(env) = hook.env() {
+        utils::parse_env(env)
+    } else {
+        HashMap::new()
+    };
+    log::debug!("run_hooks envs: {:?}", envs);
+
+    let mut hook_process = hook_command
+        .env_clear()
+        .envs(envs)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()
+        .map_err(|source| HookError::Spawn {
+            path: path.clone(),
+            source,
+        })?;
+    let hook_process_pid = Pid::from_raw(hook_process.id() as i32);
+
+    let (output_sender, output_receiver) = crossbeam_channel::unbounded();
+    if let Some(stdout) = hook_process.stdout.take() {
+        log_output(
+            &path,
+            "stdout",
+            log::Level::Info,
+            stdout,
+            output_sender.clone(),
+        );
+    }
+    if let Some(stderr) = hook_process.stderr.take() {
+        log_output(&path, "stderr", log::Level::Warn, stderr, output_sender);
+    }
+
+    let result = wait_for_hook(hook, &path, state, hook_process, hook_process_pid);
+
+    // Wait for the output to be logged, so that it appears before anything
+    // that happens after the hook
+    let _ = output_receiver.recv_timeout(OUTPUT_TIMEOUT);
+
+    result
+}
+
+fn wait_for_hook(
+    hook: &Hook,
+    path: &Path,
+    state: &State,
+    mut hook_process: process::Child,
+    hook_process_pid: Pid,
+) -> Result<(), HookError> {
+    // Based on the OCI spec, we need to pipe the container state into
+    // the hook command through stdin.
+    if let Some(mut stdin) = hook_process.stdin.take() {
+        // We want to ignore BrokenPipe here. A BrokenPipe indicates
+        // either the hook is crashed/errored or it ran successfully.
+        // Either way, this is an indication that the hook command
+        // finished execution.  If the hook command was successful,
+        // which we will check later in this function, we should not
+        // fail this step here. We still want to check for all the other
+        // error, in the case that the hook command is waiting for us to
+        // write to stdin.
+        let encoded_state = serde_json::to_string(state).map_err(|err| HookError::WriteState {
+            path: path.to_path_buf(),
+            source: err.into(),
+        })?;
+        if let Err(e) = stdin.write_all(encoded_state.as_bytes()) {
+            if e.kind() != ErrorKind::BrokenPipe {
+                // Not a broken pipe. The hook command may be waiting
+                // for us.
+                let _ = signal::kill(hook_process_pid, signal::Signal::SIGKILL);
+                let _ = hook_process.wait();
+                return Err(HookError::WriteState {
+                    path: path.to_path_buf(),
+                    source: e,
+                });
             }
+        }
+        // stdin is closed here, so the hook sees the end of the state
+    }
 
-            let res = if let Some(timeout_sec) = hook.timeout() {
-                // Rust does not make it easy to handle executing a command and
-                // timeout. Here we decided to wait for the command in a
-                // different thread, so the main thread is not blocked. We use a
-                // channel shared between main thread and the wait thread, since
-                // the channel has timeout functions out of the box. Rust won't
-                // let us copy the Command structure, so we can't share it
-                // between the wait thread and main thread. Therefore, we will
-                // use pid to identify the process and send a kill signal. This
-                // is what the Command.kill() does under the hood anyway. When
-                // timeout, we have to kill the process and clean up properly.
-                let (s, r) = crossbeam_channel::unbounded();
-                thread::spawn(move || {
-                    let res = hook_process.wait();
-                    let _ = s.send(res);
+    let res = if let Some(timeout_sec) = hook.timeout() {
+        // Rust does not make it easy to handle executing a command and
+        // timeout. Here we decided to wait for the command in a
+        // different thread, so the main thread is not blocked. We use a
+        // channel shared between main thread and the wait thread, since
+        // the channel has timeout functions out of the box. Rust won't
+        // let us copy the Command structure, so we can't share it
+        // between the wait thread and main thread. Therefore, we will
+        // use pid to identify the process and send a kill signal. This
+        // is what the Command.kill() does under the hood anyway. When
+        // timeout, we have to kill the process and clean up properly.
+        let (s, r) = crossbeam_channel::unbounded();
+        thread::spawn(move || {
+            let res = hook_process.wait();
+            let _ = s.send(res);
+        });
+        match r.recv_timeout(time::Duration::from_secs(timeout_sec as u64)) {
+            Ok(res) => res,
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
+                // Kill the process. The wait thread reaps it.
+                let _ = signal::kill(hook_process_pid, signal::Signal::SIGKILL);
+                return Err(HookError::Timeout {
+                    path: path.to_path_buf(),
+                    timeout: timeout_sec,
                 });
-                match r.recv_timeout(time::Duration::from_secs(timeout_sec as u64)) {
-                    Ok(res) => res,
-                    Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
-                        // Kill the process. There is no need to further clean
-                        // up because we will be error out.
-                        let _ = signal::kill(hook_process_pid, signal::Signal::SIGKILL);
-                        return Err(HookTimeoutError.into());
-                    }
-                    Err(_) => {
-                        unreachable!();
-                    }
-                }
-            } else {
-                hook_process.wait()
-            };
-
-            match res {
-                Ok(exit_status) => match exit_status.code() {
-                    Some(0) => {}
-                    Some(exit_code) => {
-                        bail!(
-                            "Failed to execute hook command. Non-zero return code. {:?}",
-                            exit_code
-                        );
-                    }
-                    None => {
-                        bail!("Process is killed by signal");
-                    }
-                },
-                Err(e) => {
-                    bail!("Failed to execute hook command: {:?}", e);
-                }
+            }
+            Err(_) => {
+                unreachable!();
             }
         }
+    } else {
+        hook_process.wait()
+    };
+
+    let exit_status = res.map_err(|source| HookError::Wait {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    match (exit_status.code(), exit_status.signal()) {
+        (Some(0), _) => Ok(()),
+        (Some(code), _) => Err(HookError::NonZeroExit {
+            path: path.to_path_buf(),
+            code,
+        }),
+        (None, Some(signal)) => Err(HookError::Killed {
+            path: path.to_path_buf(),
+            signal,
+        }),
+        (None, None) => unreachable!("a process either exits or is killed by a signal"),
     }
+}
 
-    Ok(())
+/// Logs the output of a hook line by line in a separate thread. The sender
+/// is dropped once the output has been closed, which disconnects the channel
+/// after all outputs of the hook are done.
+fn log_output<R: Read + Send + 'static>(
+    path: &Path,
+    stream: &'static str,
+    level: log::Level,
+    output: R,
+    done: crossbeam_channel::Sender<()>,
+) {
+    let path = path.to_path_buf();
+    thread::spawn(move || {
+        for line in BufReader::new(output).lines() {
+            match line {
+                Ok(line) => log::log!(level, "hook {:?} {}: {}", path, stream, line),
+                Err(_) => break,
+            }
+        }
+        drop(done);
+    });
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use anyhow::{bail, Result};
+    use anyhow::{bail, Context, Result};
     use oci_spec::runtime::HookBuilder;
     use serial_test::serial;
     use std::{env, fs};
@@ -200,6 +327,60 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    #[serial]
+    fn test_run_hook_errors() -> Result<()> {
+        let default_container: Container = Default::default();
+
+        let hook = HookBuilder::default().path("false").build()?;
+        let err = run_hooks(Some(&vec![hook.clone()]), Some(&default_container)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<HookError>(),
+            Some(HookError::NonZeroExit { code: 1, .. })
+        ));
+
+        let killed = HookBuilder::default()
+            .path("bash")
+            .args(vec![
+                String::from("bash"),
+                String::from("-c"),
+                String::from("kill -9 $$"),
+            ])
+            .build()?;
+        let err = run_hooks(Some(&vec![killed]), Some(&default_container)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<HookError>(),
+            Some(HookError::Killed { signal: 9, .. })
+        ));
+
+        let missing = HookBuilder::default()
+            .path("/youki/does/not/exist")
+            .build()?;
+        let err = run_hooks(Some(&vec![missing]), Some(&default_container)).unwrap_err();
+        match err.downcast_ref::<HookError>() {
+            Some(err @ HookError::Spawn { .. }) => {
+                assert_eq!(err.path(), &PathBuf::from("/youki/does/not/exist"))
+            }
+            _ => bail!("unexpected error: {:?}", err),
+        }
+
+        // a failing hook does not stop the hooks after it
+        let marker = env::temp_dir().join("youki_test_run_hooks_ignoring_failures");
+        let _ = fs::remove_file(&marker);
+        let touch = HookBuilder::default()
+            .path("touch")
+            .args(vec![
+                String::from("touch"),
+                marker.to_string_lossy().to_string(),
+            ])
+            .build()?;
+        run_hooks_ignoring_failures(Some(&vec![hook, touch]), Some(&default_container))?;
+        assert!(marker.exists());
+        fs::remove_file(&marker)?;
+
+        Ok(())
+    }
+
     #[test]
     #[serial]
     // This will test executing hook with a timeout. Since the timeout is set in
@@ -224,7 +405,10 @@ mod test {
             Err(err) => {
                 // We want to make sure the error returned is indeed timeout
                 // error. All other errors are considered failure.
-                if !err.is::<HookTimeoutError>() {
+                if !matches!(
+                    err.downcast_ref::<HookError>(),
+                    Some(HookError::Timeout { timeout: 1, .. })
+                ) {
                     bail!("Failed to execute hook: {:?}", err);
                 }
             }
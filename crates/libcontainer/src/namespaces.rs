@@ -5,13 +5,22 @@
 //! Interprocess Communication (Control or communication between processes),
 //! Network (which network devices can be seen by the processes in the namespace), User (User configs),
 //! UTS (hostname and domain information, processes will think they're running on servers with different names),
-//! Cgroup (Resource limits, execution priority etc.)
+//! Cgroup (Resource limits, execution priority etc.),
+//! Time (offsets of the monotonic and boot time clocks)
 
 use crate::syscall::{syscall::create_syscall, Syscall};
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use nix::{fcntl, sched::CloneFlags, sys::stat, unistd};
 use oci_spec::runtime::{LinuxNamespace, LinuxNamespaceType};
-use std::collections;
+use std::{collections, fs};
+
+// neither nix nor the locked libc provide a clone flag for the time namespace yet
+// SAFETY: 0x80 is CLONE_NEWTIME in <linux/sched.h>. The flag is only passed to
+// unshare and setns, which accept it since linux 5.6, and never to clone, for
+// which the same bit is part of the exit signal.
+pub const CLONE_NEWTIME: CloneFlags = unsafe { CloneFlags::from_bits_unchecked(0x80) };
+
+const TIMENS_OFFSETS_PATH: &str = "/proc/self/timens_offsets";
 
 static ORDERED_NAMESPACES: &[CloneFlags] = &[
     CloneFlags::CLONE_NEWUSER,
@@ -38,6 +47,7 @@ fn get_clone_flag(namespace_type: LinuxNamespaceType) -> CloneFlags {
         LinuxNamespaceType::Network => CloneFlags::CLONE_NEWNET,
         LinuxNamespaceType::Cgroup => CloneFlags::CLONE_NEWCGROUP,
         LinuxNamespaceType::Mount => CloneFlags::CLONE_NEWNS,
+        LinuxNamespaceType::Time => CLONE_NEWTIME,
     }
 }
 
@@ -94,6 +104,54 @@ impl Namespaces {
     }
 }
 
+/// Sets the offsets of the clocks in the time namespace that was created by
+/// the calling process. This has to happen before the first process is
+/// created inside of the namespace, afterwards the offsets can not be changed.
+pub fn set_time_offsets(offsets: &collections::HashMap<String, String>) -> Result<()> {
+    let content = format_time_offsets(offsets)?;
+    log::debug!("set time offsets: {:?}", content);
+    fs::write(TIMENS_OFFSETS_PATH, content)
+        .with_context(|| format!("failed to write time offsets to {TIMENS_OFFSETS_PATH}"))
+}
+
+fn format_time_offsets(offsets: &collections::HashMap<String, String>) -> Result<String> {
+    let mut content = String::new();
+    // only these two clocks can be adjusted, see time_namespaces(7)
+    for clock in ["monotonic", "boottime"] {
+        if let Some(offset) = offsets.get(clock) {
+            let (secs, nanosecs) = parse_time_offset(offset)
+                .with_context(|| format!("invalid time offset {offset:?} for clock {clock}"))?;
+            content.push_str(&format!("{clock} {secs} {nanosecs}\n"));
+        }
+    }
+
+    if let Some(clock) = offsets
+        .keys()
+        .find(|clock| !matches!(clock.as_str(), "monotonic" | "boottime"))
+    {
+        bail!("time offsets can not be set for clock {}", clock);
+    }
+
+    Ok(content)
+}
+
+/// Parses an offset given as "secs nanosecs", where the seconds may be negative
+fn parse_time_offset(offset: &str) -> Result<(i64, u32)> {
+    let mut fields = offset.split_whitespace();
+    let (secs, nanosecs) = match (fields.next(), fields.next(), fields.next()) {
+        (Some(secs), Some(nanosecs), None) => (secs, nanosecs),
+        _ => bail!("time offset has to be given as \"secs nanosecs\""),
+    };
+
+    let secs = secs.parse().context("invalid seconds")?;
+    let nanosecs = nanosecs.parse().context("invalid nanoseconds")?;
+    if nanosecs >= 1_000_000_000 {
+        bail!("nanoseconds have to be less than one second");
+    }
+
+    Ok((secs, nanosecs))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,4 +212,46 @@ mod tests {
         expect.sort();
         assert_eq!(unshare_args, expect)
     }
+
+    #[test]
+    #[serial]
+    fn test_apply_namespaces_skips_time_namespace() {
+        let sample_linux_namespaces = vec![LinuxNamespaceBuilder::default()
+            .typ(LinuxNamespaceType::Time)
+            .build()
+            .unwrap()];
+        let namespaces = Namespaces::from(Some(&sample_linux_namespaces));
+        let test_command: &TestHelperSyscall = namespaces.command.as_any().downcast_ref().unwrap();
+        // the time namespace only applies to children of the process that
+        // creates it, therefore it is handled separately
+        assert!(namespaces.apply_namespaces(|_| true).is_ok());
+        assert!(test_command.get_unshare_args().is_empty());
+        assert!(namespaces.get(LinuxNamespaceType::Time).is_some());
+    }
+
+    #[test]
+    fn test_format_time_offsets() -> Result<()> {
+        let offsets = collections::HashMap::from([
+            ("boottime".to_owned(), "-86400 0".to_owned()),
+            ("monotonic".to_owned(), "3600 500".to_owned()),
+        ]);
+        assert_eq!(
+            format_time_offsets(&offsets)?,
+            "monotonic 3600 500\nboottime -86400 0\n"
+        );
+
+        let offsets = collections::HashMap::from([("realtime".to_owned(), "1 0".to_owned())]);
+        assert!(format_time_offsets(&offsets).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_time_offsets() {
+        for offset in ["", "3600", "3600 0 0", "1h 0", "3600 -1", "3600 1000000000"] {
+            assert!(
+                parse_time_offset(offset).is_err(),
+                "{offset:?} should be rejected"
+            );
+        }
+    }
 }
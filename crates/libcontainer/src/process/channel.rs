@@ -62,6 +62,15 @@ impl MainSender {
         Ok(())
     }
 
+    // requests the Main to run the hooks which have to be executed in the
+    // runtime namespace once the container namespaces have been created
+    pub fn hook_request(&mut self) -> Result<()> {
+        log::debug!("send hook request");
+        self.sender.send(Message::HookRequest)?;
+
+        Ok(())
+    }
+
     pub fn exec_failed(&mut self, err: String) -> Result<()> {
         self.sender.send(Message::ExecFailed(err))?;
         Ok(())
@@ -130,6 +139,20 @@ impl MainReceiver {
         }
     }
 
+    pub fn wait_for_hook_request(&mut self) -> Result<()> {
+        let msg = self
+            .receiver
+            .recv()
+            .context("failed to wait for hook request")?;
+        match msg {
+            Message::HookRequest => Ok(()),
+            msg => bail!(
+                "receive unexpected message {:?} waiting for hook request",
+                msg
+            ),
+        }
+    }
+
     /// Waits for associated init process to send ready message
     /// and return the pid of init process which is forked by init process
     pub fn wait_for_init_ready(&mut self) -> Result<()> {
@@ -212,12 +235,29 @@ pub struct InitSender {
 }
 
 impl InitSender {
+    /// Hands the detached idmapped mount for the mount at `index` of the spec
+    /// over to the init process
+    pub fn idmapped_mount(&mut self, index: usize, fd: RawFd) -> Result<()> {
+        log::debug!("sending idmapped mount {}", index);
+        self.sender.send_fds(Message::IdmappedMount(index), &[fd])?;
+
+        Ok(())
+    }
+
     pub fn seccomp_notify_done(&mut self) -> Result<()> {
         self.sender.send(Message::SeccompNotifyDone)?;
 
         Ok(())
     }
 
+    /// Notifies the init process that the hooks are done and passes on its pid,
+    /// as the init process can not see its pid in the runtime namespace
+    pub fn hook_done(&mut self, init_pid: Pid) -> Result<()> {
+        self.sender.send(Message::HookDone(init_pid.as_raw()))?;
+
+        Ok(())
+    }
+
     pub fn close(&self) -> Result<()> {
         self.sender.close()
     }
@@ -228,6 +268,40 @@ pub struct InitReceiver {
 }
 
 impl InitReceiver {
+    /// Waits for an idmapped mount created by the main process and returns the
+    /// index of the mount in the spec together with the mount fd
+    pub fn wait_for_idmapped_mount(&mut self) -> Result<(usize, RawFd)> {
+        let (msg, fds) = self
+            .receiver
+            .recv_with_fds::<[RawFd; 1]>()
+            .context("failed to wait for idmapped mount")?;
+
+        match msg {
+            Message::IdmappedMount(index) => match fds {
+                Some(fds) => Ok((index, fds[0])),
+                None => bail!("expecting fd of idmapped mount {}", index),
+            },
+            msg => bail!(
+                "receive unexpected message {:?} waiting for idmapped mount",
+                msg
+            ),
+        }
+    }
+
+    /// Waits until the main process has run the hooks requested by
+    /// [`MainSender::hook_request`] and returns the pid of the init process
+    pub fn wait_for_hook_done(&mut self) -> Result<Pid> {
+        let msg = self
+            .receiver
+            .recv()
+            .context("failed to wait for hook done")?;
+
+        match msg {
+            Message::HookDone(pid) => Ok(Pid::from_raw(pid)),
+            msg => bail!("receive unexpected message {:?} waiting for hook done", msg),
+        }
+    }
+
     pub fn wait_for_seccomp_request_done(&mut self) -> Result<()> {
         let msg = self
             .receiver
@@ -538,6 +612,63 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[serial]
+    fn test_channel_idmapped_mount() -> Result<()> {
+        let (sender, receiver) = &mut init_channel()?;
+        match unsafe { unistd::fork()? } {
+            unistd::ForkResult::Parent { child } => {
+                wait::waitpid(child, None)?;
+                let (index, fd) = receiver.wait_for_idmapped_mount()?;
+                assert_eq!(index, 3);
+                assert!(fd >= 0);
+                unistd::close(fd)?;
+                receiver.close()?;
+            }
+            unistd::ForkResult::Child => {
+                let (read_end, _) = unistd::pipe()?;
+                sender
+                    .idmapped_mount(3, read_end)
+                    .with_context(|| "Failed to send idmapped mount")?;
+                sender.close()?;
+                std::process::exit(0);
+            }
+        };
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_channel_hook_sync() -> Result<()> {
+        let (main_sender, main_receiver) = &mut main_channel()?;
+        let (init_sender, init_receiver) = &mut init_channel()?;
+        match unsafe { unistd::fork()? } {
+            unistd::ForkResult::Parent { child } => {
+                main_receiver.wait_for_hook_request()?;
+                init_sender.hook_done(child)?;
+                // the child exits with 1 if it did not receive its own pid
+                assert_eq!(
+                    wait::waitpid(child, None)?,
+                    wait::WaitStatus::Exited(child, 0)
+                );
+                main_receiver.close()?;
+                init_sender.close()?;
+            }
+            unistd::ForkResult::Child => {
+                main_sender
+                    .hook_request()
+                    .with_context(|| "Failed to send hook request")?;
+                let pid = init_receiver.wait_for_hook_done()?;
+                main_sender.close()?;
+                init_receiver.close()?;
+                std::process::exit(i32::from(pid != unistd::getpid()));
+            }
+        };
+
+        Ok(())
+    }
+
     #[test]
     #[serial]
     fn test_channel_main_graceful_exit() -> Result<()> {
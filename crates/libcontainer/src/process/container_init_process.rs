@@ -1,10 +1,13 @@
 use super::args::{ContainerArgs, ContainerType};
 use crate::apparmor;
-use crate::syscall::Syscall;
-use crate::workload::ExecutorManager;
+use crate::syscall::{linux, Syscall};
 use crate::{
-    capabilities, hooks, namespaces::Namespaces, process::channel, rootfs::RootFS,
-    rootless::Rootless, seccomp, tty, utils,
+    capabilities, hooks,
+    namespaces::Namespaces,
+    process::channel,
+    rootfs::{idmap, RootFS},
+    rootless::Rootless,
+    seccomp, tty, utils,
 };
 use anyhow::{bail, Context, Ok, Result};
 use nix::mount::MsFlags;
@@ -13,9 +16,12 @@ use nix::sys::stat::Mode;
 use nix::unistd::setsid;
 
 use nix::unistd::{self, Gid, Uid};
-use oci_spec::runtime::{LinuxNamespaceType, Spec, User};
+use oci_spec::runtime::{
+    IOPriorityClass, LinuxIOPriority, LinuxNamespaceType, LinuxPersonality, LinuxPersonalityDomain,
+    LinuxSchedulerFlag, LinuxSchedulerPolicy, Scheduler, Spec, User,
+};
 use std::collections::HashMap;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::{
     env, fs,
     path::{Path, PathBuf},
@@ -171,7 +177,7 @@ pub fn container_init_process(
     let mut envs: Vec<String> = proc.env().as_ref().unwrap_or(&vec![]).clone();
     let rootfs_path = args.rootfs;
     let hooks = spec.hooks().as_ref();
-    let container = args.container.as_ref();
+    let mut container = args.container.clone();
     let namespaces = Namespaces::from(linux.namespaces().as_ref());
 
     setsid().context("failed to create session")?;
@@ -187,10 +193,28 @@ pub fn container_init_process(
     }
 
     if matches!(args.container_type, ContainerType::InitContainer) {
+        // The idmapped mounts are sent before anything else by the main process
+        let idmapped_mounts = receive_idmapped_mounts(spec, init_receiver)
+            .context("failed to receive idmapped mounts")?;
+
+        // The prestart and createRuntime hooks are run by the main process in
+        // the runtime namespace, once the container namespaces exist.
+        main_sender
+            .hook_request()
+            .context("failed to request hooks")?;
+        let init_pid = init_receiver
+            .wait_for_hook_done()
+            .context("failed to wait for hooks")?;
+        // The state passed to the hooks run by the init process has to contain
+        // its pid, which is only known to the main process.
+        if let Some(container) = container.as_mut() {
+            container.set_pid(init_pid.as_raw());
+        }
+
         // create_container hook needs to be called after the namespace setup, but
         // before pivot_root is called. This runs in the container namespaces.
         if let Some(hooks) = hooks {
-            hooks::run_hooks(hooks.create_container().as_ref(), container)
+            hooks::run_hooks(hooks.create_container().as_ref(), container.as_ref())
                 .context("Failed to run create container hooks")?;
         }
 
@@ -202,6 +226,7 @@ pub fn container_init_process(
                 rootfs_path,
                 bind_service,
                 namespaces.get(LinuxNamespaceType::Cgroup).is_some(),
+                &idmapped_mounts,
             )
             .with_context(|| "Failed to prepare rootfs")?;
 
@@ -255,6 +280,20 @@ pub fn container_init_process(
         }
     }
 
+    // Setting a real-time policy or a higher I/O priority requires CAP_SYS_NICE
+    // and CAP_SYS_ADMIN, so these have to be applied before dropping capabilities.
+    if let Some(scheduler) = proc.scheduler() {
+        set_scheduler(scheduler, syscall).context("failed to set scheduler")?;
+    }
+
+    if let Some(io_priority) = proc.io_priority() {
+        set_io_priority(io_priority, syscall).context("failed to set io priority")?;
+    }
+
+    if let Some(personality) = linux.personality() {
+        set_personality(personality, syscall).context("failed to set personality")?;
+    }
+
     if let Some(paths) = linux.readonly_paths() {
         // mount readonly path
         for path in paths {
@@ -437,12 +476,14 @@ pub fn container_init_process(
     // before pivot_root is called. This runs in the container namespaces.
     if matches!(args.container_type, ContainerType::InitContainer) {
         if let Some(hooks) = hooks {
-            hooks::run_hooks(hooks.start_container().as_ref(), container)?
+            hooks::run_hooks(hooks.start_container().as_ref(), container.as_ref())?
         }
     }
 
     if proc.args().is_some() {
-        ExecutorManager::exec(spec)
+        args.executor
+            .exec(spec)
+            .with_context(|| format!("{} execution failed", args.executor.name()))
     } else {
         bail!("on non-Windows, at least one process arg entry is required")
     }
@@ -514,6 +555,100 @@ fn set_supplementary_gids(
     Ok(())
 }
 
+fn set_scheduler(scheduler: &Scheduler, syscall: &dyn Syscall) -> Result<()> {
+    let policy = match scheduler.policy() {
+        LinuxSchedulerPolicy::SchedOther => linux::SCHED_OTHER,
+        LinuxSchedulerPolicy::SchedFifo => linux::SCHED_FIFO,
+        LinuxSchedulerPolicy::SchedRr => linux::SCHED_RR,
+        LinuxSchedulerPolicy::SchedBatch => linux::SCHED_BATCH,
+        LinuxSchedulerPolicy::SchedIso => linux::SCHED_ISO,
+        LinuxSchedulerPolicy::SchedIdle => linux::SCHED_IDLE,
+        LinuxSchedulerPolicy::SchedDeadline => linux::SCHED_DEADLINE,
+    };
+
+    let mut flags = 0;
+    for flag in scheduler.flags().iter().flatten() {
+        flags |= match flag {
+            LinuxSchedulerFlag::SchedResetOnFork => linux::SCHED_FLAG_RESET_ON_FORK,
+            LinuxSchedulerFlag::SchedFlagReclaim => linux::SCHED_FLAG_RECLAIM,
+            LinuxSchedulerFlag::SchedFlagDLOverrun => linux::SCHED_FLAG_DL_OVERRUN,
+            LinuxSchedulerFlag::SchedFlagKeepPolicy => linux::SCHED_FLAG_KEEP_POLICY,
+            LinuxSchedulerFlag::SchedFlagKeepParams => linux::SCHED_FLAG_KEEP_PARAMS,
+            LinuxSchedulerFlag::SchedFlagUtilClampMin => linux::SCHED_FLAG_UTIL_CLAMP_MIN,
+            LinuxSchedulerFlag::SchedFlagUtilClampMax => linux::SCHED_FLAG_UTIL_CLAMP_MAX,
+        };
+    }
+
+    let priority = scheduler.priority().unwrap_or(0);
+    if priority < 0 {
+        bail!("invalid scheduler priority {}", priority);
+    }
+
+    let attr = linux::SchedAttr {
+        sched_policy: policy,
+        sched_flags: flags,
+        sched_nice: scheduler.nice().unwrap_or(0),
+        sched_priority: priority as u32,
+        sched_runtime: scheduler.runtime().unwrap_or(0),
+        sched_deadline: scheduler.deadline().unwrap_or(0),
+        sched_period: scheduler.period().unwrap_or(0),
+        ..Default::default()
+    };
+    log::debug!("set scheduler {:?}", attr);
+    syscall.set_scheduler(&attr)
+}
+
+fn set_io_priority(io_priority: &LinuxIOPriority, syscall: &dyn Syscall) -> Result<()> {
+    let class = match io_priority.class() {
+        IOPriorityClass::IoprioClassRt => linux::IOPRIO_CLASS_RT,
+        IOPriorityClass::IoprioClassBe => linux::IOPRIO_CLASS_BE,
+        IOPriorityClass::IoprioClassIdle => linux::IOPRIO_CLASS_IDLE,
+    };
+
+    // The kernel supports 8 priority levels per class, 0 being the highest.
+    let priority = io_priority.priority();
+    if !(0..8).contains(&priority) {
+        bail!("invalid io priority {}, must be between 0 and 7", priority);
+    }
+
+    log::debug!("set io priority class {} priority {}", class, priority);
+    syscall.set_io_priority(class, priority)
+}
+
+fn set_personality(personality: &LinuxPersonality, syscall: &dyn Syscall) -> Result<()> {
+    let persona = match personality.domain() {
+        LinuxPersonalityDomain::PerLinux => linux::PER_LINUX,
+        LinuxPersonalityDomain::PerLinux32 => linux::PER_LINUX32,
+    };
+
+    if let Some(flags) = personality.flags() {
+        if !flags.is_empty() {
+            log::warn!(
+                "personality flags {:?} are not supported and ignored",
+                flags
+            );
+        }
+    }
+
+    log::debug!("set personality {:#x}", persona);
+    syscall.set_personality(persona)
+}
+
+// The main process creates the idmapped mounts of the spec in the same order
+// as they appear in the spec and sends them over one by one.
+fn receive_idmapped_mounts(
+    spec: &Spec,
+    init_receiver: &mut channel::InitReceiver,
+) -> Result<HashMap<usize, RawFd>> {
+    let mut mounts = HashMap::new();
+    for _ in idmap::idmapped_mounts(spec) {
+        let (index, fd) = init_receiver.wait_for_idmapped_mount()?;
+        mounts.insert(index, fd);
+    }
+
+    Ok(mounts)
+}
+
 fn sync_seccomp(
     fd: Option<i32>,
     main_sender: &mut channel::MainSender,
@@ -537,10 +672,13 @@ mod tests {
     use super::*;
     use crate::syscall::{
         syscall::create_syscall,
-        test::{ArgName, MountArgs, TestHelperSyscall},
+        test::{ArgName, IoPriorityArgs, MountArgs, TestHelperSyscall},
     };
     use nix::unistd;
-    use oci_spec::runtime::{LinuxNamespaceBuilder, SpecBuilder, UserBuilder};
+    use oci_spec::runtime::{
+        LinuxIOPriorityBuilder, LinuxNamespaceBuilder, LinuxPersonalityBuilder, SchedulerBuilder,
+        SpecBuilder, UserBuilder,
+    };
     use serial_test::serial;
     use std::fs;
 
@@ -782,4 +920,114 @@ mod tests {
         let got = mocks.get_mount_args();
         assert_eq!(0, got.len());
     }
+
+    #[test]
+    fn test_set_scheduler() -> Result<()> {
+        let syscall = create_syscall();
+        let scheduler = SchedulerBuilder::default()
+            .policy(LinuxSchedulerPolicy::SchedDeadline)
+            .runtime(1_000_000u64)
+            .deadline(2_000_000u64)
+            .period(5_000_000u64)
+            .flags(vec![
+                LinuxSchedulerFlag::SchedResetOnFork,
+                LinuxSchedulerFlag::SchedFlagReclaim,
+            ])
+            .build()?;
+        set_scheduler(&scheduler, syscall.as_ref())?;
+
+        let got = syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap()
+            .get_scheduler_args();
+        let want = linux::SchedAttr {
+            sched_policy: linux::SCHED_DEADLINE,
+            sched_flags: linux::SCHED_FLAG_RESET_ON_FORK | linux::SCHED_FLAG_RECLAIM,
+            sched_runtime: 1_000_000,
+            sched_deadline: 2_000_000,
+            sched_period: 5_000_000,
+            ..Default::default()
+        };
+        assert_eq!(got, vec![want]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_scheduler_nice() -> Result<()> {
+        let syscall = create_syscall();
+        let scheduler = SchedulerBuilder::default()
+            .policy(LinuxSchedulerPolicy::SchedBatch)
+            .nice(10)
+            .build()?;
+        set_scheduler(&scheduler, syscall.as_ref())?;
+
+        let got = syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap()
+            .get_scheduler_args();
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].sched_policy, linux::SCHED_BATCH);
+        assert_eq!(got[0].sched_nice, 10);
+        assert_eq!(got[0].sched_flags, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_io_priority() -> Result<()> {
+        let syscall = create_syscall();
+        let io_priority = LinuxIOPriorityBuilder::default()
+            .class(IOPriorityClass::IoprioClassBe)
+            .priority(4)
+            .build()?;
+        set_io_priority(&io_priority, syscall.as_ref())?;
+
+        let got = syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap()
+            .get_io_priority_args();
+        let want = IoPriorityArgs {
+            class: linux::IOPRIO_CLASS_BE,
+            priority: 4,
+        };
+        assert_eq!(got, vec![want]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_io_priority_out_of_range() -> Result<()> {
+        let syscall = create_syscall();
+        let io_priority = LinuxIOPriorityBuilder::default()
+            .class(IOPriorityClass::IoprioClassRt)
+            .priority(8)
+            .build()?;
+        assert!(set_io_priority(&io_priority, syscall.as_ref()).is_err());
+
+        let got = syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap()
+            .get_io_priority_args();
+        assert!(got.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_personality() -> Result<()> {
+        let syscall = create_syscall();
+        let personality = LinuxPersonalityBuilder::default()
+            .domain(LinuxPersonalityDomain::PerLinux32)
+            .build()?;
+        set_personality(&personality, syscall.as_ref())?;
+
+        let got = syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap()
+            .get_personality_args();
+        assert_eq!(got, vec![linux::PER_LINUX32]);
+        Ok(())
+    }
 }
@@ -1,4 +1,8 @@
-use crate::{namespaces::Namespaces, process::channel, process::fork};
+use crate::{
+    namespaces::{self, Namespaces},
+    process::channel,
+    process::fork,
+};
 use anyhow::{Context, Error, Result};
 use libcgroups::common::CgroupManager;
 use nix::unistd::{close, write};
@@ -84,6 +88,20 @@ pub fn container_intermediate_process(
             .with_context(|| format!("failed to enter pid namespace: {pid_namespace:?}"))?;
     }
 
+    // Like the pid namespace, a new time namespace only applies to the children
+    // of the process that created it. The clock offsets have to be set before
+    // the init process is forked into the namespace.
+    if let Some(time_namespace) = namespaces.get(LinuxNamespaceType::Time) {
+        namespaces
+            .unshare_or_setns(time_namespace)
+            .with_context(|| format!("failed to enter time namespace: {time_namespace:?}"))?;
+        if time_namespace.path().is_none() {
+            if let Some(offsets) = linux.time_offsets() {
+                namespaces::set_time_offsets(offsets)?;
+            }
+        }
+    }
+
     // We have to record the pid of the child (container init process), since
     // the child will be inside the pid namespace. We can't rely on child_ready
     // to send us the correct pid.
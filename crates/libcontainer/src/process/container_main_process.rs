@@ -1,9 +1,11 @@
 use crate::{
     container::ContainerProcessState,
+    hooks,
     process::{
         args::{ContainerArgs, ContainerType},
         channel, container_intermediate_process, fork,
     },
+    rootfs::idmap,
     rootless::Rootless,
     seccomp, utils,
 };
@@ -78,6 +80,26 @@ pub fn container_main_process(container_args: &ContainerArgs) -> Result<(Pid, Pi
     // process.  The intermediate process should exit after this point.
     let init_pid = main_receiver.wait_for_intermediate_ready()?;
 
+    // Idmapped mounts can only be created with privileges in the user namespace
+    // that owns the source filesystem, which the init process no longer has.
+    // The main process creates them and passes them on to be attached in the
+    // container rootfs.
+    if matches!(container_args.container_type, ContainerType::InitContainer) {
+        send_idmapped_mounts(container_args, init_pid, init_sender)
+            .context("failed to send idmapped mounts to init")?;
+
+        // The prestart and createRuntime hooks run in the runtime namespace
+        // after the container namespaces have been created, but before
+        // pivot_root. The init process stops at that point until they are done.
+        main_receiver
+            .wait_for_hook_request()
+            .context("failed to wait for hook request")?;
+        run_runtime_hooks(container_args, init_pid)?;
+        init_sender
+            .hook_done(init_pid)
+            .context("failed to notify init that hooks are done")?;
+    }
+
     if let Some(linux) = container_args.spec.linux() {
         if let Some(seccomp) = linux.seccomp() {
             let state = ContainerProcessState {
@@ -119,6 +141,46 @@ pub fn container_main_process(container_args: &ContainerArgs) -> Result<(Pid, Pi
     Ok((intermediate_pid, init_pid))
 }
 
+fn send_idmapped_mounts(
+    container_args: &ContainerArgs,
+    init_pid: Pid,
+    init_sender: &mut channel::InitSender,
+) -> Result<()> {
+    for (index, mount, idmap) in idmap::idmapped_mounts(container_args.spec) {
+        let fd = idmap::create_idmapped_mount(container_args.syscall, mount, idmap, init_pid)?;
+        let result = init_sender.idmapped_mount(index, fd);
+        // The fd has been duplicated into the init process, so it is safe to
+        // close it here regardless of the outcome.
+        let _ = unistd::close(fd);
+        result?;
+    }
+
+    Ok(())
+}
+
+fn run_runtime_hooks(container_args: &ContainerArgs, init_pid: Pid) -> Result<()> {
+    let hooks = match container_args.spec.hooks() {
+        Some(hooks) => hooks,
+        None => return Ok(()),
+    };
+
+    // The state passed to the hooks has to contain the pid of the init process
+    let mut container = container_args.container.clone();
+    if let Some(container) = &mut container {
+        container.set_pid(init_pid.as_raw());
+    }
+
+    // While prestart is marked as deprecated in the OCI spec, the docker and integration test still
+    // uses it.
+    #[allow(deprecated)]
+    hooks::run_hooks(hooks.prestart().as_ref(), container.as_ref())
+        .context("failed to run prestart hooks")?;
+    hooks::run_hooks(hooks.create_runtime().as_ref(), container.as_ref())
+        .context("failed to run create runtime hooks")?;
+
+    Ok(())
+}
+
 fn sync_seccomp(
     seccomp: &runtime::LinuxSeccomp,
     state: &ContainerProcessState,
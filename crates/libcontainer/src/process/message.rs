@@ -10,4 +10,7 @@ pub enum Message {
     SeccompNotify,
     SeccompNotifyDone,
     ExecFailed(String),
+    IdmappedMount(usize),
+    HookRequest,
+    HookDone(i32),
 }
@@ -8,3 +8,4 @@ pub mod container_intermediate_process;
 pub mod container_main_process;
 pub mod fork;
 pub mod message;
+pub mod pidfd;
@@ -0,0 +1,181 @@
+//! Process file descriptors refer to one specific process rather than to a pid,
+//! which the kernel is free to reuse once the process has exited and been reaped.
+//! Signals sent through a pidfd can therefore never reach an unrelated process.
+use anyhow::{Context, Result};
+use nix::{
+    errno::Errno,
+    sys::signal::{self, Signal},
+    unistd::Pid,
+};
+use procfs::process::Process;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use syscalls::{syscall, Sysno};
+
+/// File descriptor referring to a process, see pidfd_open(2)
+#[derive(Debug)]
+pub struct PidFd {
+    fd: OwnedFd,
+    pid: Pid,
+}
+
+impl PidFd {
+    /// Opens a pidfd for the process. Fails with ENOSYS on kernels older
+    /// than 5.3 and with ESRCH if the process does not exist.
+    pub fn open(pid: Pid) -> nix::Result<Self> {
+        let fd = unsafe { syscall!(Sysno::pidfd_open, pid.as_raw(), 0) }.map_err(to_errno)?;
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd as RawFd) },
+            pid,
+        })
+    }
+
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    /// Sends a signal to the process, see pidfd_send_signal(2). A signal of
+    /// `None` only checks if the process is still alive.
+    pub fn send_signal(&self, signal: Option<Signal>) -> nix::Result<()> {
+        let signal = signal.map_or(0, |s| s as i32);
+        unsafe {
+            syscall!(
+                Sysno::pidfd_send_signal,
+                self.fd.as_raw_fd(),
+                signal,
+                std::ptr::null::<libc::siginfo_t>(),
+                0
+            )
+        }
+        .map(drop)
+        .map_err(to_errno)
+    }
+
+    /// Returns the start time of the process the pidfd refers to
+    pub fn start_time(&self) -> Result<u64> {
+        let start_time = read_start_time(self.pid)?;
+        // If the process is still alive after its stat has been read, the pid
+        // cannot have been reused in between.
+        self.send_signal(None)
+            .with_context(|| format!("process {} has exited", self.pid))?;
+        Ok(start_time)
+    }
+}
+
+impl AsRawFd for PidFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+fn to_errno(err: syscalls::Errno) -> Errno {
+    Errno::from_i32(err.into_raw())
+}
+
+/// Reads the start time of the process in clock ticks after boot from
+/// /proc/<pid>/stat. Together with the pid it identifies a process.
+fn read_start_time(pid: Pid) -> Result<u64> {
+    let stat = Process::new(pid.as_raw())
+        .and_then(|p| p.stat())
+        .with_context(|| format!("failed to read stat of process {pid}"))?;
+    Ok(stat.starttime)
+}
+
+/// Returns the start time of the process. On kernels that support pidfds, the
+/// process is pinned while the start time is read, so the result is known to
+/// belong to the process that had the pid when this function was called.
+pub fn process_start_time(pid: Pid) -> Result<u64> {
+    match PidFd::open(pid) {
+        Ok(pidfd) => pidfd.start_time(),
+        Err(Errno::ENOSYS) => read_start_time(pid),
+        Err(err) => Err(err).with_context(|| format!("failed to open pidfd for {pid}")),
+    }
+}
+
+/// Returns true if the process with the pid is the one that has been started
+/// at `start_time`. If the start time is unknown, any process is accepted.
+pub fn is_same_process(pid: Pid, start_time: Option<u64>) -> bool {
+    match start_time {
+        Some(start_time) => read_start_time(pid).map_or(false, |current| current == start_time),
+        None => true,
+    }
+}
+
+/// Sends a signal to the process with the pid, as long as it is still the
+/// process that has been started at `start_time`. Fails with ESRCH if the
+/// process has exited, even if the pid has been reused in the meantime.
+///
+/// On kernels without pidfd support the signal is sent with kill(2) after the
+/// identity of the process has been checked, which leaves a small window for
+/// the pid to be reused.
+pub fn signal_process(pid: Pid, start_time: Option<u64>, signal: Signal) -> nix::Result<()> {
+    match PidFd::open(pid) {
+        Ok(pidfd) => {
+            // The pidfd refers to whatever process had the pid when it was
+            // opened. If that process still matches the start time now, it is
+            // the expected one and the signal cannot go astray.
+            if !is_same_process(pid, start_time) {
+                return Err(Errno::ESRCH);
+            }
+            pidfd.send_signal(Some(signal))
+        }
+        Err(Errno::ENOSYS) => {
+            log::debug!("pidfd is not supported, falling back to kill");
+            if !is_same_process(pid, start_time) {
+                return Err(Errno::ESRCH);
+            }
+            signal::kill(pid, signal)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nix::{
+        sys::wait::{waitpid, WaitStatus},
+        unistd::{self, ForkResult},
+    };
+
+    fn spawn_sleeping_child() -> Result<Pid> {
+        match unsafe { unistd::fork()? } {
+            ForkResult::Parent { child } => Ok(child),
+            ForkResult::Child => loop {
+                unistd::pause();
+            },
+        }
+    }
+
+    #[test]
+    fn test_process_start_time() -> Result<()> {
+        let pid = unistd::getpid();
+        let expected = Process::myself()?.stat()?.starttime;
+        assert_eq!(process_start_time(pid)?, expected);
+        assert!(is_same_process(pid, Some(expected)));
+        assert!(!is_same_process(pid, Some(expected + 1)));
+        assert!(is_same_process(pid, None));
+        Ok(())
+    }
+
+    #[test]
+    fn test_signal_process() -> Result<()> {
+        let child = spawn_sleeping_child()?;
+        let start_time = process_start_time(child)?;
+
+        // a different start time means that the pid belongs to another process
+        assert_eq!(
+            signal_process(child, Some(start_time + 1), Signal::SIGKILL),
+            Err(Errno::ESRCH)
+        );
+
+        signal_process(child, Some(start_time), Signal::SIGKILL)?;
+        assert_eq!(
+            waitpid(child, None)?,
+            WaitStatus::Signaled(child, Signal::SIGKILL, false)
+        );
+
+        // the child has been reaped, so its pid must not be signalled anymore
+        assert!(signal_process(child, Some(start_time), Signal::SIGKILL).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+//! Idmapped mounts make the files of a host directory appear with the ids of a
+//! user namespace, so that containers in a user namespace can share volumes
+//! with the host without chowning them. Creating an idmapped mount requires
+//! privileges in the user namespace that owns the filesystem, so the mount is
+//! created by the main process and then handed over to the init process, which
+//! moves it into the container rootfs. The ids are always mapped to the user
+//! namespace of the container, mounts with their own uidMappings and
+//! gidMappings are not supported yet.
+use super::utils::{parse_mount, IdmapType};
+use crate::syscall::{linux, Syscall};
+use anyhow::{bail, Context, Result};
+use nix::unistd::{self, Pid};
+use oci_spec::runtime::{LinuxNamespaceType, Mount, Spec};
+use std::{
+    fs::{self, File},
+    mem,
+    os::unix::io::{AsRawFd, RawFd},
+    path::Path,
+};
+
+/// Returns the mounts of the spec that have to be idmapped together with their
+/// position in the list of mounts
+pub fn idmapped_mounts(spec: &Spec) -> Vec<(usize, &Mount, IdmapType)> {
+    spec.mounts()
+        .iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(index, m)| parse_mount(m).idmap.map(|idmap| (index, m, idmap)))
+        .collect()
+}
+
+/// Checks that the idmapped mounts of the spec can be created
+pub fn validate(spec: &Spec) -> Result<()> {
+    let has_user_ns = spec
+        .linux()
+        .as_ref()
+        .and_then(|l| l.namespaces().as_ref())
+        .map_or(false, |namespaces| {
+            namespaces
+                .iter()
+                .any(|ns| ns.typ() == LinuxNamespaceType::User)
+        });
+
+    for (_, m, _) in idmapped_mounts(spec) {
+        if m.typ().as_deref() != Some("bind") {
+            bail!(
+                "idmapped mounts are only supported for bind mounts, but {:?} is of type {:?}",
+                m.destination(),
+                m.typ()
+            );
+        }
+
+        if !has_user_ns {
+            bail!(
+                "idmapped mount {:?} requires a user namespace",
+                m.destination()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// oci-spec does not know the uidMappings and gidMappings of mounts yet and
+/// drops them while parsing the spec. Reject mounts that carry them on the raw
+/// config, instead of mapping their ids to the container user namespace.
+pub fn validate_mount_mappings(config: &serde_json::Value) -> Result<()> {
+    let mounts = config.get("mounts").and_then(|mounts| mounts.as_array());
+    for m in mounts.into_iter().flatten() {
+        if m.get("uidMappings").is_some() || m.get("gidMappings").is_some() {
+            bail!(
+                "mount {} has its own id mappings, which are not supported",
+                m.get("destination").unwrap_or(&serde_json::Value::Null)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates a detached, idmapped copy of the source of the mount and returns a
+/// file descriptor for it. The ids are mapped to the user namespace of the
+/// container process.
+pub fn create_idmapped_mount(
+    syscall: &dyn Syscall,
+    m: &Mount,
+    idmap: IdmapType,
+    container_pid: Pid,
+) -> Result<RawFd> {
+    let source = m
+        .source()
+        .as_ref()
+        .with_context(|| format!("no source for idmapped mount {:?}", m.destination()))?;
+    let source =
+        fs::canonicalize(source).with_context(|| format!("failed to canonicalize: {source:?}"))?;
+
+    let userns_path = format!("/proc/{container_pid}/ns/user");
+    let userns =
+        File::open(&userns_path).with_context(|| format!("failed to open {userns_path}"))?;
+
+    let mut open_flags = linux::OPEN_TREE_CLONE | linux::OPEN_TREE_CLOEXEC;
+    let mut setattr_flags = libc::AT_EMPTY_PATH as u32;
+    if idmap == IdmapType::RecursiveIdmap {
+        open_flags |= linux::AT_RECURSIVE;
+        setattr_flags |= linux::AT_RECURSIVE;
+    }
+
+    let mount_fd = syscall
+        .open_tree(libc::AT_FDCWD, &source, open_flags)
+        .with_context(|| format!("failed to clone mount {source:?}"))?;
+    let mount_attr = linux::MountAttr {
+        attr_set: linux::MOUNT_ATTR_IDMAP,
+        attr_clr: 0,
+        propagation: 0,
+        userns_fd: userns.as_raw_fd() as u64,
+    };
+    if let Err(err) = syscall.mount_setattr(
+        mount_fd,
+        Path::new(""),
+        setattr_flags,
+        &mount_attr,
+        mem::size_of::<linux::MountAttr>(),
+    ) {
+        let _ = unistd::close(mount_fd);
+        return Err(err.context(format!("failed to idmap mount {source:?}")));
+    }
+
+    Ok(mount_fd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oci_spec::runtime::{LinuxBuilder, LinuxNamespaceBuilder, MountBuilder, SpecBuilder};
+    use std::path::PathBuf;
+
+    fn bind_mount(destination: &str, options: &[&str]) -> Mount {
+        MountBuilder::default()
+            .destination(PathBuf::from(destination))
+            .typ("bind")
+            .source(PathBuf::from("/srv").join(destination.trim_start_matches('/')))
+            .options(options.iter().map(|o| o.to_string()).collect::<Vec<_>>())
+            .build()
+            .unwrap()
+    }
+
+    fn spec_with_mounts(mounts: Vec<Mount>, user_ns: bool) -> Spec {
+        let namespaces = if user_ns {
+            vec![LinuxNamespaceBuilder::default()
+                .typ(LinuxNamespaceType::User)
+                .build()
+                .unwrap()]
+        } else {
+            vec![]
+        };
+        SpecBuilder::default()
+            .mounts(mounts)
+            .linux(
+                LinuxBuilder::default()
+                    .namespaces(namespaces)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_idmapped_mounts() {
+        let spec = spec_with_mounts(
+            vec![
+                bind_mount("/a", &["rbind"]),
+                bind_mount("/b", &["rbind", "idmap"]),
+                bind_mount("/c", &["rbind", "ridmap"]),
+            ],
+            true,
+        );
+
+        let mounts: Vec<_> = idmapped_mounts(&spec)
+            .into_iter()
+            .map(|(index, m, idmap)| (index, m.destination().clone(), idmap))
+            .collect();
+        assert_eq!(
+            mounts,
+            vec![
+                (1, PathBuf::from("/b"), IdmapType::Idmap),
+                (2, PathBuf::from("/c"), IdmapType::RecursiveIdmap)
+            ]
+        );
+        assert!(validate(&spec).is_ok());
+    }
+
+    #[test]
+    fn test_validate_requires_user_namespace() {
+        let spec = spec_with_mounts(vec![bind_mount("/b", &["rbind", "idmap"])], false);
+        assert!(validate(&spec).is_err());
+
+        let spec = spec_with_mounts(vec![bind_mount("/b", &["rbind", "idmap"])], true);
+        assert!(validate(&spec).is_ok());
+    }
+
+    #[test]
+    fn test_validate_requires_bind_mount() {
+        let mount = MountBuilder::default()
+            .destination(PathBuf::from("/tmp"))
+            .typ("tmpfs")
+            .source(PathBuf::from("tmpfs"))
+            .options(vec!["idmap".to_string()])
+            .build()
+            .unwrap();
+        assert!(validate(&spec_with_mounts(vec![mount], true)).is_err());
+    }
+
+    #[test]
+    fn test_validate_mount_mappings() {
+        let mut config = serde_json::json!({
+            "mounts": [{
+                "destination": "/data",
+                "type": "bind",
+                "source": "/srv/data",
+                "options": ["rbind", "idmap"]
+            }]
+        });
+        assert!(validate_mount_mappings(&config).is_ok());
+
+        let mappings = serde_json::json!([{"containerID": 0, "hostID": 100000, "size": 65536}]);
+        config["mounts"][0]["uidMappings"] = mappings.clone();
+        assert!(validate_mount_mappings(&config).is_err());
+
+        config["mounts"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("uidMappings");
+        config["mounts"][0]["gidMappings"] = mappings;
+        assert!(validate_mount_mappings(&config).is_err());
+    }
+}
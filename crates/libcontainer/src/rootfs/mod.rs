@@ -8,6 +8,8 @@ pub use rootfs::RootFS;
 pub mod device;
 pub use device::Device;
 
+pub mod idmap;
+
 pub(super) mod mount;
 pub(super) mod symlink;
 
@@ -12,12 +12,12 @@ use anyhow::{bail, Context, Result};
 use libcgroups::common::CgroupSetup::{Hybrid, Legacy, Unified};
 #[cfg(feature = "v1")]
 use libcgroups::common::DEFAULT_CGROUP_ROOT;
-use nix::{dir::Dir, errno::Errno, fcntl::OFlag, mount::MsFlags, sys::stat::Mode};
+use nix::{dir::Dir, errno::Errno, fcntl::OFlag, mount::MsFlags, sys::stat::Mode, unistd::close};
 use oci_spec::runtime::{Mount as SpecMount, MountBuilder as SpecMountBuilder};
 use procfs::process::{MountInfo, MountOptFields, Process};
 use std::fs::{canonicalize, create_dir_all, OpenOptions};
 use std::mem;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::path::{Path, PathBuf};
 
 #[cfg(feature = "v1")]
@@ -209,6 +209,7 @@ impl Mount {
             flags: MsFlags::MS_NOEXEC | MsFlags::MS_NOSUID | MsFlags::MS_NODEV,
             data: data.to_string(),
             rec_attr: None,
+            idmap: None,
         };
 
         self.mount_into_container(
@@ -391,23 +392,7 @@ impl Mount {
         let src = if typ == Some("bind") {
             let src = canonicalize(source)
                 .with_context(|| format!("failed to canonicalize: {source:?}"))?;
-            let dir = if src.is_file() {
-                Path::new(&dest).parent().unwrap()
-            } else {
-                Path::new(&dest)
-            };
-
-            create_dir_all(dir)
-                .with_context(|| format!("failed to create dir for bind mount: {dir:?}"))?;
-
-            if src.is_file() {
-                OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .open(dest)
-                    .with_context(|| format!("failed to create file for bind mount: {src:?}"))?;
-            }
-
+            Self::create_bind_destination(&src, dest)?;
             src
         } else {
             create_dir_all(dest).with_context(|| format!("Failed to create device: {dest:?}"))?;
@@ -436,16 +421,91 @@ impl Mount {
                 .with_context(|| format!("failed to mount {src:?} to {dest:?}"))?;
         }
 
-        if typ == Some("bind")
-            && mount_option_config.flags.intersects(
-                !(MsFlags::MS_REC
-                    | MsFlags::MS_REMOUNT
-                    | MsFlags::MS_BIND
-                    | MsFlags::MS_PRIVATE
-                    | MsFlags::MS_SHARED
-                    | MsFlags::MS_SLAVE),
+        if typ == Some("bind") {
+            self.remount_bind(dest, mount_option_config)?;
+        } else {
+            self.set_recursive_attributes(dest, mount_option_config)?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves an idmapped mount, which has been created outside of the container
+    /// by the main process, to its destination in the container rootfs. The
+    /// mount fd is consumed.
+    pub fn setup_idmapped_mount(
+        &self,
+        m: &SpecMount,
+        options: &MountOptions,
+        mount_fd: RawFd,
+    ) -> Result<()> {
+        log::debug!("Moving idmapped mount {:?}", m);
+        let result = self.move_idmapped_mount(m, options.root, mount_fd);
+        let _ = close(mount_fd);
+        result.with_context(|| format!("failed to mount idmapped mount: {m:?}"))
+    }
+
+    fn move_idmapped_mount(&self, m: &SpecMount, rootfs: &Path, mount_fd: RawFd) -> Result<()> {
+        let dest_for_host = utils::secure_join(rootfs, m.destination())
+            .with_context(|| format!("failed to join {:?} with {:?}", rootfs, m.destination()))?;
+        let dest = Path::new(&dest_for_host);
+        let source = m
+            .source()
+            .as_ref()
+            .with_context(|| "no source in mount spec".to_string())?;
+        let src =
+            canonicalize(source).with_context(|| format!("failed to canonicalize: {source:?}"))?;
+        Self::create_bind_destination(&src, dest)?;
+
+        self.syscall
+            .move_mount(
+                mount_fd,
+                Path::new(""),
+                libc::AT_FDCWD,
+                dest,
+                linux::MOVE_MOUNT_F_EMPTY_PATH,
             )
-        {
+            .with_context(|| format!("failed to move mount to {dest:?}"))?;
+
+        // the flags are applied through a bind remount, which must not touch the
+        // superblock even if the mount options do not contain bind
+        let mut mount_option_config = parse_mount(m);
+        mount_option_config.flags |= MsFlags::MS_BIND;
+        self.remount_bind(dest, &mount_option_config)
+    }
+
+    fn create_bind_destination(src: &Path, dest: &Path) -> Result<()> {
+        let dir = if src.is_file() {
+            dest.parent().unwrap()
+        } else {
+            dest
+        };
+
+        create_dir_all(dir)
+            .with_context(|| format!("failed to create dir for bind mount: {dir:?}"))?;
+
+        if src.is_file() {
+            OpenOptions::new()
+                .create(true)
+                .write(true)
+                .open(dest)
+                .with_context(|| format!("failed to create file for bind mount: {src:?}"))?;
+        }
+
+        Ok(())
+    }
+
+    // Bind mounts ignore most of the mount flags, they have to be applied by
+    // remounting the bind mount.
+    fn remount_bind(&self, dest: &Path, mount_option_config: &MountOptionConfig) -> Result<()> {
+        if mount_option_config.flags.intersects(
+            !(MsFlags::MS_REC
+                | MsFlags::MS_REMOUNT
+                | MsFlags::MS_BIND
+                | MsFlags::MS_PRIVATE
+                | MsFlags::MS_SHARED
+                | MsFlags::MS_SLAVE),
+        ) {
             self.syscall
                 .mount(
                     Some(dest),
@@ -457,6 +517,14 @@ impl Mount {
                 .with_context(|| format!("Failed to remount: {dest:?}"))?;
         }
 
+        self.set_recursive_attributes(dest, mount_option_config)
+    }
+
+    fn set_recursive_attributes(
+        &self,
+        dest: &Path,
+        mount_option_config: &MountOptionConfig,
+    ) -> Result<()> {
         if let Some(mount_attr) = &mount_option_config.rec_attr {
             let open_dir = Dir::open(dest, OFlag::O_DIRECTORY, Mode::empty())?;
             let dir_fd_pathbuf = PathBuf::from(format!("/proc/self/fd/{}", open_dir.as_raw_fd()));
@@ -475,11 +543,10 @@ impl Mount {
 
 #[cfg(test)]
 mod tests {
-    #[cfg(feature = "v1")]
     use std::fs;
 
     use super::*;
-    use crate::syscall::test::{MountArgs, TestHelperSyscall};
+    use crate::syscall::test::{MountArgs, MoveMountArgs, TestHelperSyscall};
     use crate::utils::create_temp_dir;
     use anyhow::Result;
 
@@ -579,6 +646,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_setup_idmapped_mount() -> Result<()> {
+        let tmp_dir = create_temp_dir("test_setup_idmapped_mount")?;
+        let source = tmp_dir.path().join("data");
+        fs::create_dir_all(&source)?;
+        let rootfs = tmp_dir.path().join("rootfs");
+
+        let m = Mount::new();
+        let mount = SpecMountBuilder::default()
+            .destination(PathBuf::from("/data"))
+            .typ("bind")
+            .source(&source)
+            .options(vec!["idmap".to_string(), "ro".to_string()])
+            .build()?;
+        let options = MountOptions {
+            root: &rootfs,
+            label: None,
+            cgroup_ns: false,
+        };
+        m.setup_idmapped_mount(&mount, &options, -1)?;
+
+        let syscall = m
+            .syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap();
+        assert_eq!(
+            syscall.get_move_mount_args(),
+            vec![MoveMountArgs {
+                from_dirfd: -1,
+                from_path: PathBuf::new(),
+                to_dirfd: libc::AT_FDCWD,
+                to_path: rootfs.join("data"),
+                flags: linux::MOVE_MOUNT_F_EMPTY_PATH,
+            }]
+        );
+        assert_eq!(
+            syscall.get_mount_args(),
+            vec![MountArgs {
+                source: Some(rootfs.join("data")),
+                target: rootfs.join("data"),
+                fstype: None,
+                flags: MsFlags::MS_BIND | MsFlags::MS_RDONLY | MsFlags::MS_REMOUNT,
+                data: None,
+            }]
+        );
+        assert!(rootfs.join("data").is_dir());
+        Ok(())
+    }
+
     #[test]
     fn test_make_parent_mount_private() {
         let tmp_dir = create_temp_dir("test_make_parent_mount_private").unwrap();
@@ -817,6 +934,7 @@ mod tests {
             flags,
             data: String::new(),
             rec_attr: None,
+            idmap: None,
         };
         mounter
             .mount_cgroup_v2(&spec_cgroup_mount, &mount_opts, &mount_option_config)
@@ -4,10 +4,13 @@ use super::{
     symlink::Symlink,
     utils::default_devices,
 };
+use crate::error::LibcontainerError;
 use crate::syscall::{syscall::create_syscall, Syscall};
 use anyhow::{bail, Context, Result};
 use nix::mount::MsFlags;
 use oci_spec::runtime::{Linux, Spec};
+use std::collections::HashMap;
+use std::os::unix::io::RawFd;
 use std::path::Path;
 
 /// Holds information about rootfs
@@ -28,12 +31,27 @@ impl RootFS {
         }
     }
 
+    /// Sets up the mounts, devices and symlinks of the root filesystem of the
+    /// container
     pub fn prepare_rootfs(
         &self,
         spec: &Spec,
         rootfs: &Path,
         bind_devices: bool,
         cgroup_ns: bool,
+        idmapped_mounts: &HashMap<usize, RawFd>,
+    ) -> Result<(), LibcontainerError> {
+        self.setup_rootfs(spec, rootfs, bind_devices, cgroup_ns, idmapped_mounts)
+            .map_err(LibcontainerError::Rootfs)
+    }
+
+    fn setup_rootfs(
+        &self,
+        spec: &Spec,
+        rootfs: &Path,
+        bind_devices: bool,
+        cgroup_ns: bool,
+        idmapped_mounts: &HashMap<usize, RawFd>,
     ) -> Result<()> {
         log::debug!("Prepare rootfs: {:?}", rootfs);
         let mut flags = MsFlags::MS_REC;
@@ -72,7 +90,14 @@ impl RootFS {
         };
 
         if let Some(mounts) = spec.mounts() {
-            for mount in mounts {
+            for (index, mount) in mounts.iter().enumerate() {
+                if let Some(mount_fd) = idmapped_mounts.get(&index) {
+                    mounter
+                        .setup_idmapped_mount(mount, &global_options, *mount_fd)
+                        .with_context(|| format!("failed to setup mount {mount:#?}"))?;
+                    continue;
+                }
+
                 mounter
                     .setup_mount(mount, &global_options)
                     .with_context(|| format!("failed to setup mount {mount:#?}"))?;
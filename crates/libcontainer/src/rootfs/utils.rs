@@ -19,8 +19,78 @@ pub struct MountOptionConfig {
 
     /// RecAttr represents mount properties to be applied recrusively.
     pub rec_attr: Option<linux::MountAttr>,
+
+    /// Idmapping requested for the mount.
+    pub idmap: Option<IdmapType>,
+}
+
+/// Kind of idmapping of a bind mount, see mount_setattr(2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdmapType {
+    /// Only the mount itself is idmapped (`idmap` option).
+    Idmap,
+    /// The mount and all of its submounts are idmapped (`ridmap` option).
+    RecursiveIdmap,
 }
 
+/// Mount options that are understood by `parse_mount`. Any other option is
+/// passed on to the filesystem as mount data.
+pub const KNOWN_MOUNT_OPTIONS: &[&str] = &[
+    "async",
+    "atime",
+    "bind",
+    "defaults",
+    "dev",
+    "diratime",
+    "dirsync",
+    "exec",
+    "idmap",
+    "mand",
+    "noatime",
+    "nodev",
+    "nodiratime",
+    "noexec",
+    "nomand",
+    "norelatime",
+    "nostrictatime",
+    "nosuid",
+    "private",
+    "ratime",
+    "rbind",
+    "rdev",
+    "rdiratime",
+    "relatime",
+    "remount",
+    "rexec",
+    "ridmap",
+    "rnoatime",
+    "rnodev",
+    "rnodiratime",
+    "rnoexec",
+    "rnorelatime",
+    "rnostrictatime",
+    "rnosuid",
+    "rnosymfollow",
+    "ro",
+    "rprivate",
+    "rrelatime",
+    "rro",
+    "rrw",
+    "rshared",
+    "rslave",
+    "rstrictatime",
+    "rsuid",
+    "rsymfollow",
+    "runbindable",
+    "rw",
+    "shared",
+    "slave",
+    "strictatime",
+    "suid",
+    "sync",
+    "unbindable",
+];
+
 pub fn default_devices() -> Vec<LinuxDevice> {
     vec![
         LinuxDeviceBuilder::default()
@@ -87,6 +157,7 @@ pub fn parse_mount(m: &Mount) -> MountOptionConfig {
     let mut flags = MsFlags::empty();
     let mut data = Vec::new();
     let mut mount_attr: Option<linux::MountAttr> = None;
+    let mut idmap: Option<IdmapType> = None;
 
     if let Some(options) = &m.options() {
         for s in options {
@@ -134,6 +205,18 @@ pub fn parse_mount(m: &Mount) -> MountOptionConfig {
                 continue;
             }
 
+            match s.as_str() {
+                "idmap" => {
+                    idmap = Some(IdmapType::Idmap);
+                    continue;
+                }
+                "ridmap" => {
+                    idmap = Some(IdmapType::RecursiveIdmap);
+                    continue;
+                }
+                _ => (),
+            }
+
             if let Ok(mount_attr_option) = linux::MountAttrOption::from_str(s.as_str()) {
                 let (is_clear, flag) = match mount_attr_option {
                     MountAttrOption::MountArrtRdonly(is_clear, flag) => (is_clear, flag),
@@ -180,6 +263,7 @@ pub fn parse_mount(m: &Mount) -> MountOptionConfig {
         flags,
         data: data.join(","),
         rec_attr: mount_attr,
+        idmap,
     }
 }
 
@@ -202,6 +286,18 @@ mod tests {
     use anyhow::Context;
     use oci_spec::runtime::MountBuilder;
 
+    #[test]
+    fn test_known_mount_options() -> anyhow::Result<()> {
+        for option in KNOWN_MOUNT_OPTIONS {
+            let mount = MountBuilder::default()
+                .destination(PathBuf::from("/mnt"))
+                .options(vec![option.to_string()])
+                .build()?;
+            assert_eq!(parse_mount(&mount).data, "", "option {option} is not known");
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_find_parent_mount() -> anyhow::Result<()> {
         let mount_infos = vec![
@@ -271,6 +367,7 @@ mod tests {
                 flags: MsFlags::empty(),
                 data: "".to_string(),
                 rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -294,6 +391,7 @@ mod tests {
                 flags: MsFlags::MS_NOSUID,
                 data: "mode=755,size=65536k".to_string(),
                 rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -318,7 +416,8 @@ mod tests {
             MountOptionConfig {
                 flags: MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC,
                 data: "newinstance,ptmxmode=0666,mode=0620,gid=5".to_string(),
-                rec_attr: None
+                rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -342,7 +441,8 @@ mod tests {
             MountOptionConfig {
                 flags: MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC | MsFlags::MS_NODEV,
                 data: "mode=1777,size=65536k".to_string(),
-                rec_attr: None
+                rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -364,7 +464,8 @@ mod tests {
             MountOptionConfig {
                 flags: MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC | MsFlags::MS_NODEV,
                 data: "".to_string(),
-                rec_attr: None
+                rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -391,6 +492,7 @@ mod tests {
                     | MsFlags::MS_RDONLY,
                 data: "".to_string(),
                 rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -417,7 +519,8 @@ mod tests {
                     | MsFlags::MS_NODEV
                     | MsFlags::MS_RDONLY,
                 data: "".to_string(),
-                rec_attr: None
+                rec_attr: None,
+                idmap: None,
             },
             mount_option_config,
         );
@@ -476,6 +579,7 @@ mod tests {
                     | MsFlags::MS_UNBINDABLE,
                 data: "".to_string(),
                 rec_attr: None,
+                idmap: None,
             },
             mount_option_config
         );
@@ -510,7 +614,34 @@ mod tests {
             MountOptionConfig {
                 flags: MsFlags::empty(),
                 data: "".to_string(),
-                rec_attr: Some(MountAttr::all())
+                rec_attr: Some(MountAttr::all()),
+                idmap: None,
+            },
+            mount_option_config
+        );
+    }
+
+    #[test]
+    fn test_parse_idmap_mount() {
+        let mount_option_config = parse_mount(
+            &MountBuilder::default()
+                .destination(PathBuf::from("/data"))
+                .typ("bind")
+                .source(PathBuf::from("/srv/data"))
+                .options(vec![
+                    "rbind".to_string(),
+                    "ridmap".to_string(),
+                    "ro".to_string(),
+                ])
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(
+            MountOptionConfig {
+                flags: MsFlags::MS_BIND | MsFlags::MS_REC | MsFlags::MS_RDONLY,
+                data: "".to_string(),
+                rec_attr: None,
+                idmap: Some(IdmapType::RecursiveIdmap),
             },
             mount_option_config
         );
@@ -42,6 +42,43 @@ const MOUNT_ATTR_NOATIME: u64 = 0x00000010;
 const MOUNT_ATTR_STRICTATIME: u64 = 0x00000020;
 const MOUNT_ATTR_NODIRATIME: u64 = 0x00000080;
 const MOUNT_ATTR_NOSYMFOLLOW: u64 = 0x00200000;
+pub const MOUNT_ATTR_IDMAP: u64 = 0x00100000; // Idmap the mount with the user namespace in userns_fd.
+
+// Flags used in open_tree(2).
+pub const OPEN_TREE_CLONE: u32 = 0x00000001; // Create a detached copy of the mount.
+pub const OPEN_TREE_CLOEXEC: u32 = libc::O_CLOEXEC as u32;
+
+// Flags used in move_mount(2).
+pub const MOVE_MOUNT_F_EMPTY_PATH: u32 = 0x00000004; // Empty from path permitted.
+
+// Scheduling policies and flags used in sched_setattr(2).
+// see https://man7.org/linux/man-pages/man2/sched_setattr.2.html.
+pub const SCHED_OTHER: u32 = 0;
+pub const SCHED_FIFO: u32 = 1;
+pub const SCHED_RR: u32 = 2;
+pub const SCHED_BATCH: u32 = 3;
+pub const SCHED_ISO: u32 = 4;
+pub const SCHED_IDLE: u32 = 5;
+pub const SCHED_DEADLINE: u32 = 6;
+pub const SCHED_FLAG_RESET_ON_FORK: u64 = 0x01;
+pub const SCHED_FLAG_RECLAIM: u64 = 0x02;
+pub const SCHED_FLAG_DL_OVERRUN: u64 = 0x04;
+pub const SCHED_FLAG_KEEP_POLICY: u64 = 0x08;
+pub const SCHED_FLAG_KEEP_PARAMS: u64 = 0x10;
+pub const SCHED_FLAG_UTIL_CLAMP_MIN: u64 = 0x20;
+pub const SCHED_FLAG_UTIL_CLAMP_MAX: u64 = 0x40;
+
+// Classes used in ioprio_set(2).
+// see https://man7.org/linux/man-pages/man2/ioprio_set.2.html.
+pub const IOPRIO_CLASS_RT: i64 = 1;
+pub const IOPRIO_CLASS_BE: i64 = 2;
+pub const IOPRIO_CLASS_IDLE: i64 = 3;
+const IOPRIO_CLASS_SHIFT: i64 = 13;
+const IOPRIO_WHO_PROCESS: i32 = 1;
+
+// Execution domains used in personality(2).
+pub const PER_LINUX: u64 = 0x0000;
+pub const PER_LINUX32: u64 = 0x0008;
 
 /// Constants used by mount_setattr(2).
 pub enum MountAttrOption {
@@ -123,7 +160,7 @@ impl FromStr for MountAttrOption {
                 true,
                 MOUNT_ATTR_NOSYMFOLLOW,
             )),
-            // No support for MOUNT_ATTR_IDMAP yet (needs UserNS FD)
+            // MOUNT_ATTR_IDMAP needs a user namespace fd and is set up separately
             _ => Err(anyhow!("Unexpected option.")),
         }
     }
@@ -146,6 +183,41 @@ pub struct MountAttr {
     pub userns_fd: u64,
 }
 
+#[repr(C)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// A structure used as the second argument of sched_setattr(2).
+pub struct SchedAttr {
+    /// Size of the structure, filled in by the syscall wrapper.
+    pub size: u32,
+
+    /// Scheduling policy.
+    pub sched_policy: u32,
+
+    /// Scheduling flags.
+    pub sched_flags: u64,
+
+    /// Nice value for SCHED_OTHER and SCHED_BATCH.
+    pub sched_nice: i32,
+
+    /// Static priority for SCHED_FIFO and SCHED_RR.
+    pub sched_priority: u32,
+
+    /// Runtime in nanoseconds for SCHED_DEADLINE.
+    pub sched_runtime: u64,
+
+    /// Deadline in nanoseconds for SCHED_DEADLINE.
+    pub sched_deadline: u64,
+
+    /// Period in nanoseconds for SCHED_DEADLINE.
+    pub sched_period: u64,
+
+    /// Minimum utilization hint.
+    pub sched_util_min: u32,
+
+    /// Maximum utilization hint.
+    pub sched_util_max: u32,
+}
+
 impl MountAttr {
     /// Return MountAttr with the flag raised.
     /// This function is used in test code.
@@ -515,6 +587,84 @@ impl Syscall for LinuxSyscall {
             Err(e) => bail!(e),
         }
     }
+
+    fn open_tree(&self, dirfd: RawFd, pathname: &Path, flags: u32) -> Result<RawFd> {
+        let path_c_string = CString::new(pathname.as_os_str().as_bytes())?;
+        let result = unsafe {
+            // TODO: nix/libc crate hasn't supported open_tree system call yet.
+            syscall!(Sysno::open_tree, dirfd, path_c_string.as_ptr(), flags)
+        };
+
+        match result {
+            Ok(fd) => Ok(fd as RawFd),
+            Err(e) => bail!(e),
+        }
+    }
+
+    fn move_mount(
+        &self,
+        from_dirfd: RawFd,
+        from_pathname: &Path,
+        to_dirfd: RawFd,
+        to_pathname: &Path,
+        flags: u32,
+    ) -> Result<()> {
+        let from_c_string = CString::new(from_pathname.as_os_str().as_bytes())?;
+        let to_c_string = CString::new(to_pathname.as_os_str().as_bytes())?;
+        let result = unsafe {
+            // TODO: nix/libc crate hasn't supported move_mount system call yet.
+            syscall!(
+                Sysno::move_mount,
+                from_dirfd,
+                from_c_string.as_ptr(),
+                to_dirfd,
+                to_c_string.as_ptr(),
+                flags
+            )
+        };
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => bail!(e),
+        }
+    }
+
+    fn set_scheduler(&self, attr: &SchedAttr) -> Result<()> {
+        let attr = SchedAttr {
+            size: mem::size_of::<SchedAttr>() as u32,
+            ..attr.clone()
+        };
+        let result = unsafe {
+            // TODO: nix/libc crate hasn't supported sched_setattr system call yet.
+            syscall!(Sysno::sched_setattr, 0, &attr as *const SchedAttr, 0)
+        };
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => bail!(e),
+        }
+    }
+
+    fn set_io_priority(&self, class: i64, priority: i64) -> Result<()> {
+        let ioprio = (class << IOPRIO_CLASS_SHIFT) | priority;
+        let result = unsafe {
+            // TODO: nix/libc crate hasn't supported ioprio_set system call yet.
+            syscall!(Sysno::ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio)
+        };
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => bail!(e),
+        }
+    }
+
+    fn set_personality(&self, persona: u64) -> Result<()> {
+        let res = unsafe { libc::personality(persona as libc::c_ulong) };
+        if let Err(e) = Errno::result(res).map(drop) {
+            bail!("Failed to set personality {:#x}. {:?}", persona, e)
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
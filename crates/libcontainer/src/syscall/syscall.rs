@@ -17,7 +17,7 @@ use nix::{
 use oci_spec::runtime::LinuxRlimit;
 
 use crate::syscall::{
-    linux::{LinuxSyscall, MountAttr},
+    linux::{LinuxSyscall, MountAttr, SchedAttr},
     test::TestHelperSyscall,
 };
 
@@ -56,6 +56,18 @@ pub trait Syscall {
         mount_attr: &MountAttr,
         size: libc::size_t,
     ) -> Result<()>;
+    fn open_tree(&self, dirfd: i32, pathname: &Path, flags: u32) -> Result<i32>;
+    fn move_mount(
+        &self,
+        from_dirfd: i32,
+        from_pathname: &Path,
+        to_dirfd: i32,
+        to_pathname: &Path,
+        flags: u32,
+    ) -> Result<()>;
+    fn set_scheduler(&self, attr: &SchedAttr) -> Result<()>;
+    fn set_io_priority(&self, class: i64, priority: i64) -> Result<()>;
+    fn set_personality(&self, persona: u64) -> Result<()>;
 }
 
 pub fn create_syscall() -> Box<dyn Syscall> {
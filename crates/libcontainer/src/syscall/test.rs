@@ -43,6 +43,21 @@ pub struct ChownArgs {
     pub group: Option<Gid>,
 }
 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MoveMountArgs {
+    pub from_dirfd: i32,
+    pub from_path: PathBuf,
+    pub to_dirfd: i32,
+    pub to_path: PathBuf,
+    pub flags: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct IoPriorityArgs {
+    pub class: i64,
+    pub priority: i64,
+}
+
 #[derive(Default)]
 struct Mock {
     values: Vec<Box<dyn Any>>,
@@ -62,6 +77,10 @@ pub enum ArgName {
     Domainname,
     Groups,
     Capability,
+    MoveMount,
+    Scheduler,
+    IoPriority,
+    Personality,
 }
 
 impl ArgName {
@@ -77,6 +96,10 @@ impl ArgName {
             ArgName::Domainname,
             ArgName::Groups,
             ArgName::Capability,
+            ArgName::MoveMount,
+            ArgName::Scheduler,
+            ArgName::IoPriority,
+            ArgName::Personality,
         ]
         .iter()
         .copied()
@@ -249,6 +272,44 @@ impl Syscall for TestHelperSyscall {
     ) -> anyhow::Result<()> {
         todo!()
     }
+
+    fn open_tree(&self, _: i32, _: &Path, _: u32) -> anyhow::Result<i32> {
+        todo!()
+    }
+
+    fn move_mount(
+        &self,
+        from_dirfd: i32,
+        from_path: &Path,
+        to_dirfd: i32,
+        to_path: &Path,
+        flags: u32,
+    ) -> anyhow::Result<()> {
+        self.mocks.act(
+            ArgName::MoveMount,
+            Box::new(MoveMountArgs {
+                from_dirfd,
+                from_path: from_path.to_owned(),
+                to_dirfd,
+                to_path: to_path.to_owned(),
+                flags,
+            }),
+        )
+    }
+    fn set_scheduler(&self, attr: &linux::SchedAttr) -> anyhow::Result<()> {
+        self.mocks.act(ArgName::Scheduler, Box::new(attr.clone()))
+    }
+
+    fn set_io_priority(&self, class: i64, priority: i64) -> anyhow::Result<()> {
+        self.mocks.act(
+            ArgName::IoPriority,
+            Box::new(IoPriorityArgs { class, priority }),
+        )
+    }
+
+    fn set_personality(&self, persona: u64) -> anyhow::Result<()> {
+        self.mocks.act(ArgName::Personality, Box::new(persona))
+    }
 }
 
 impl TestHelperSyscall {
@@ -350,4 +411,40 @@ impl TestHelperSyscall {
             .map(|x| x.downcast_ref::<Vec<Gid>>().unwrap().clone())
             .collect::<Vec<Vec<Gid>>>()
     }
+
+    pub fn get_move_mount_args(&self) -> Vec<MoveMountArgs> {
+        self.mocks
+            .fetch(ArgName::MoveMount)
+            .values
+            .iter()
+            .map(|x| x.downcast_ref::<MoveMountArgs>().unwrap().clone())
+            .collect::<Vec<MoveMountArgs>>()
+    }
+
+    pub fn get_scheduler_args(&self) -> Vec<linux::SchedAttr> {
+        self.mocks
+            .fetch(ArgName::Scheduler)
+            .values
+            .iter()
+            .map(|x| x.downcast_ref::<linux::SchedAttr>().unwrap().clone())
+            .collect::<Vec<linux::SchedAttr>>()
+    }
+
+    pub fn get_io_priority_args(&self) -> Vec<IoPriorityArgs> {
+        self.mocks
+            .fetch(ArgName::IoPriority)
+            .values
+            .iter()
+            .map(|x| x.downcast_ref::<IoPriorityArgs>().unwrap().clone())
+            .collect::<Vec<IoPriorityArgs>>()
+    }
+
+    pub fn get_personality_args(&self) -> Vec<u64> {
+        self.mocks
+            .fetch(ArgName::Personality)
+            .values
+            .iter()
+            .map(|x| *x.downcast_ref::<u64>().unwrap())
+            .collect::<Vec<u64>>()
+    }
 }
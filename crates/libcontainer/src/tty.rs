@@ -1,23 +1,29 @@
 //! tty (teletype) for user-system interaction
 
-use std::io::IoSlice;
+use std::fs;
+use std::io::{IoSlice, IoSliceMut};
 use std::os::unix::fs::symlink;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::net::UnixListener;
 use std::os::unix::prelude::RawFd;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use anyhow::{bail, Result};
 use nix::errno::Errno;
-use nix::sys::socket::{self, UnixAddr};
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::sys::signal::{SigSet, Signal};
+use nix::sys::signalfd::{SfdFlags, SignalFd};
+use nix::sys::socket::{self, ControlMessageOwned, UnixAddr};
+use nix::sys::termios::{self, SetArg, Termios};
 use nix::unistd::close;
 use nix::unistd::dup2;
+use nix::unistd::{isatty, read, write};
 
 const STDIN: i32 = 0;
 const STDOUT: i32 = 1;
 const STDERR: i32 = 2;
 
-// TODO: Handling when there isn't console-socket.
 pub fn setup_console_socket(
     container_dir: &Path,
     console_socket_path: &Path,
@@ -61,6 +67,9 @@ pub fn setup_console(console_fd: &RawFd) -> Result<()> {
         None,
     )
     .context("failed to send pty master")?;
+    // the master belongs to the receiver of the console socket, so it must not
+    // leak into the container process
+    close(openpty_result.master).context("could not close pty master")?;
 
     if unsafe { libc::ioctl(openpty_result.slave, libc::TIOCSCTTY) } < 0 {
         log::warn!("could not TIOCSCTTY");
@@ -71,6 +80,189 @@ pub fn setup_console(console_fd: &RawFd) -> Result<()> {
     Ok(())
 }
 
+/// Socket on which youki itself receives the pty master of a container. It is
+/// used instead of a console socket provided by the caller when youki attaches
+/// the container terminal to its own terminal in the foreground.
+pub struct ConsoleListener {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl ConsoleListener {
+    pub fn bind<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path = path.into();
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("failed to bind console socket {path:?}"))?;
+        Ok(Self { listener, path })
+    }
+
+    /// Path of the socket, which has to be passed to the container builder as
+    /// console socket
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Waits for the container to connect and returns the pty master it sent
+    pub fn receive(&self) -> Result<OwnedFd> {
+        let (stream, _) = self
+            .listener
+            .accept()
+            .context("failed to accept connection on console socket")?;
+
+        let mut buf = [0u8; 16];
+        let mut iov = [IoSliceMut::new(&mut buf)];
+        let mut cmsg = nix::cmsg_space!([RawFd; 1]);
+        let msg = socket::recvmsg::<UnixAddr>(
+            stream.as_raw_fd(),
+            &mut iov,
+            Some(&mut cmsg),
+            socket::MsgFlags::MSG_CMSG_CLOEXEC,
+        )
+        .context("failed to receive pty master")?;
+
+        for cmsg in msg.cmsgs() {
+            if let ControlMessageOwned::ScmRights(fds) = cmsg {
+                if let Some(fd) = fds.first() {
+                    return Ok(unsafe { OwnedFd::from_raw_fd(*fd) });
+                }
+            }
+        }
+
+        bail!("container did not send a pty master")
+    }
+}
+
+impl Drop for ConsoleListener {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            log::warn!("failed to remove console socket {:?}: {}", self.path, err);
+        }
+    }
+}
+
+/// Puts a terminal into raw mode, so that input is passed on to the container
+/// unmodified. The previous mode is restored when this is dropped.
+pub struct RawTerminal {
+    fd: RawFd,
+    original: Termios,
+}
+
+impl RawTerminal {
+    pub fn new(fd: RawFd) -> Result<Self> {
+        let original = termios::tcgetattr(fd).context("failed to get terminal attributes")?;
+        let mut raw = original.clone();
+        termios::cfmakeraw(&mut raw);
+        termios::tcsetattr(fd, SetArg::TCSANOW, &raw).context("failed to set raw mode")?;
+        Ok(Self { fd, original })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        if let Err(err) = termios::tcsetattr(self.fd, SetArg::TCSANOW, &self.original) {
+            log::warn!("failed to restore terminal: {}", err);
+        }
+    }
+}
+
+/// Attaches the pty master of a container to the stdio of youki until the
+/// container closes its side of the pty. If stdin is a terminal, it is put
+/// into raw mode and its window size is kept in sync with the container pty.
+pub fn proxy_console(master: RawFd) -> Result<()> {
+    let _raw_terminal = if isatty(STDIN).unwrap_or(false) {
+        Some(RawTerminal::new(STDIN)?)
+    } else {
+        None
+    };
+
+    let mut mask = SigSet::empty();
+    mask.add(Signal::SIGWINCH);
+    mask.thread_block().context("failed to block SIGWINCH")?;
+    let result = proxy_loop(master, &mask);
+    if let Err(err) = mask.thread_unblock() {
+        log::warn!("failed to unblock SIGWINCH: {}", err);
+    }
+    result
+}
+
+fn proxy_loop(master: RawFd, mask: &SigSet) -> Result<()> {
+    let mut sigfd = SignalFd::with_flags(mask, SfdFlags::SFD_CLOEXEC | SfdFlags::SFD_NONBLOCK)
+        .context("failed to create signalfd for SIGWINCH")?;
+    resize_console(STDIN, master);
+
+    let mut stdin_open = true;
+    let mut buf = [0u8; 4096];
+    loop {
+        let stdin_events = if stdin_open {
+            PollFlags::POLLIN
+        } else {
+            PollFlags::empty()
+        };
+        let mut fds = [
+            PollFd::new(master, PollFlags::POLLIN),
+            PollFd::new(STDIN, stdin_events),
+            PollFd::new(sigfd.as_raw_fd(), PollFlags::POLLIN),
+        ];
+        match poll(&mut fds, -1) {
+            Ok(_) => (),
+            Err(Errno::EINTR) => continue,
+            Err(err) => return Err(err).context("failed to poll console"),
+        }
+
+        let revents = |fd: &PollFd| fd.revents().unwrap_or_else(PollFlags::empty);
+
+        if revents(&fds[0]).intersects(PollFlags::POLLIN | PollFlags::POLLHUP) {
+            match read(master, &mut buf) {
+                // reading the master fails with EIO once every process of the
+                // container has closed the pty
+                Ok(0) | Err(Errno::EIO) => return Ok(()),
+                Ok(n) => write_all(STDOUT, &buf[..n]).context("failed to write to stdout")?,
+                Err(Errno::EINTR) | Err(Errno::EAGAIN) => (),
+                Err(err) => return Err(err).context("failed to read from container console"),
+            }
+        }
+
+        if revents(&fds[1]).intersects(PollFlags::POLLIN | PollFlags::POLLHUP) {
+            match read(STDIN, &mut buf) {
+                Ok(0) => stdin_open = false,
+                Ok(n) => {
+                    write_all(master, &buf[..n]).context("failed to write to container console")?
+                }
+                Err(Errno::EINTR) | Err(Errno::EAGAIN) => (),
+                Err(err) => return Err(err).context("failed to read from stdin"),
+            }
+        }
+
+        if revents(&fds[2]).contains(PollFlags::POLLIN) {
+            while let Ok(Some(_)) = sigfd.read_signal() {}
+            resize_console(STDIN, master);
+        }
+    }
+}
+
+/// Copies the window size of the terminal to the container pty
+fn resize_console(terminal: RawFd, master: RawFd) {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(terminal, libc::TIOCGWINSZ, &mut size) } < 0 {
+        // stdin is not a terminal, so there is nothing to copy
+        return;
+    }
+    if unsafe { libc::ioctl(master, libc::TIOCSWINSZ, &size) } < 0 {
+        log::warn!("could not resize console: {}", Errno::last());
+    }
+}
+
+fn write_all(fd: RawFd, mut buf: &[u8]) -> nix::Result<()> {
+    while !buf.is_empty() {
+        match write(fd, buf) {
+            Ok(n) => buf = &buf[n..],
+            Err(Errno::EINTR) | Err(Errno::EAGAIN) => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
 fn connect_stdio(stdin: &RawFd, stdout: &RawFd, stderr: &RawFd) -> Result<()> {
     dup2(stdin.as_raw_fd(), STDIN)?;
     dup2(stdout.as_raw_fd(), STDOUT)?;
@@ -153,4 +345,56 @@ mod tests {
         let status = setup_console(&fd.unwrap());
         assert!(status.is_ok());
     }
+
+    #[test]
+    #[serial]
+    fn test_console_listener() -> Result<()> {
+        let (testdir, _, _) = setup("test_console_listener")?;
+        let listener = ConsoleListener::bind(testdir.join("youki-console"))?;
+        // the builders link the console socket into the container directory,
+        // which is their working directory
+        let fd = setup_console_socket(&testdir, listener.path(), CONSOLE_SOCKET)?;
+        assert_ne!(fd, -1);
+
+        let pty = nix::pty::openpty(None, None)?;
+        let iov = [IoSlice::new(b"/dev/ptmx")];
+        let fds = [pty.master];
+        socket::sendmsg::<UnixAddr>(
+            fd,
+            &iov,
+            &[socket::ControlMessage::ScmRights(&fds)],
+            socket::MsgFlags::empty(),
+            None,
+        )?;
+
+        let master = listener.receive()?;
+        assert!(isatty(master.as_raw_fd())?);
+
+        let path = listener.path().to_path_buf();
+        drop(listener);
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resize_console() -> Result<()> {
+        let size = libc::winsize {
+            ws_row: 24,
+            ws_col: 80,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        let host = nix::pty::openpty(Some(&size), None)?;
+        let container = nix::pty::openpty(None, None)?;
+
+        resize_console(host.slave, container.master);
+
+        let mut resized: libc::winsize = unsafe { std::mem::zeroed() };
+        assert_eq!(
+            unsafe { libc::ioctl(container.slave, libc::TIOCGWINSZ, &mut resized) },
+            0
+        );
+        assert_eq!((resized.ws_row, resized.ws_col), (24, 80));
+        Ok(())
+    }
 }
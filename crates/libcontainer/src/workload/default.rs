@@ -11,7 +11,7 @@ const EXECUTOR_NAME: &str = "default";
 pub struct DefaultExecutor {}
 
 impl Executor for DefaultExecutor {
-    fn exec(spec: &Spec) -> Result<()> {
+    fn exec(&self, spec: &Spec) -> Result<()> {
         log::debug!("Executing workload with default handler");
         let args = spec
             .process()
@@ -37,11 +37,11 @@ impl Executor for DefaultExecutor {
         unreachable!();
     }
 
-    fn can_handle(_: &Spec) -> Result<bool> {
+    fn can_handle(&self, _: &Spec) -> Result<bool> {
         Ok(true)
     }
 
-    fn name() -> &'static str {
+    fn name(&self) -> &str {
         EXECUTOR_NAME
     }
 }
@@ -1,4 +1,4 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Result};
 use oci_spec::runtime::Spec;
 
 use self::default::DefaultExecutor;
@@ -19,33 +19,168 @@ pub mod wasmtime;
 
 static EMPTY: Vec<String> = Vec::new();
 
+/// Priority of the built-in wasm executors
+pub const WASM_PRIORITY: i32 = 100;
+/// Priority of the default executor. It can handle every workload and
+/// therefore has to be tried last.
+pub const DEFAULT_PRIORITY: i32 = i32::MIN;
+
 pub trait Executor {
     /// Executes the workload
-    fn exec(spec: &Spec) -> Result<()>;
-    /// Checks if the handler is able to handle the workload
-    fn can_handle(spec: &Spec) -> Result<bool>;
+    fn exec(&self, spec: &Spec) -> Result<()>;
+    /// Checks if the handler is able to handle the workload. This is called by the
+    /// runtime before the container process is created.
+    fn can_handle(&self, spec: &Spec) -> Result<bool>;
     /// The name of the handler
-    fn name() -> &'static str;
+    fn name(&self) -> &str;
 }
-pub struct ExecutorManager {}
 
-impl ExecutorManager {
-    pub fn exec(spec: &Spec) -> Result<()> {
-        #[cfg(feature = "wasm-wasmer")]
-        if WasmerExecutor::can_handle(spec)? {
-            return WasmerExecutor::exec(spec).context("wasmer execution failed");
+/// Collection of executors ordered by priority. The executor with the highest
+/// priority that is able to handle a workload is used to execute it. Executors
+/// with the same priority are tried in the order they have been registered.
+pub struct ExecutorRegistry {
+    executors: Vec<(i32, Box<dyn Executor>)>,
+}
+
+impl ExecutorRegistry {
+    /// Creates a registry without any executors
+    pub fn empty() -> Self {
+        Self {
+            executors: Vec::new(),
         }
+    }
 
-        #[cfg(feature = "wasm-wasmedge")]
-        if WasmEdgeExecutor::can_handle(spec)? {
-            return WasmEdgeExecutor::exec(spec).context("wasmedge execution failed");
+    /// Adds an executor with the given priority
+    pub fn register(&mut self, priority: i32, executor: Box<dyn Executor>) {
+        let index = self
+            .executors
+            .iter()
+            .position(|(p, _)| *p < priority)
+            .unwrap_or(self.executors.len());
+        self.executors.insert(index, (priority, executor));
+    }
+
+    /// Returns the executor which will be used to execute the workload of the spec
+    pub fn select(&self, spec: &Spec) -> Result<&dyn Executor> {
+        for (_, executor) in &self.executors {
+            if executor.can_handle(spec)? {
+                log::debug!("workload will be executed by {}", executor.name());
+                return Ok(executor.as_ref());
+            }
         }
 
+        bail!("no executor is able to handle the workload")
+    }
+
+    /// Names of the registered executors in the order they are tried
+    pub fn names(&self) -> Vec<&str> {
+        self.executors.iter().map(|(_, e)| e.name()).collect()
+    }
+}
+
+impl Default for ExecutorRegistry {
+    /// Creates a registry containing the executors that are built into libcontainer
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        #[cfg(feature = "wasm-wasmer")]
+        registry.register(WASM_PRIORITY, Box::new(WasmerExecutor {}));
+        #[cfg(feature = "wasm-wasmedge")]
+        registry.register(WASM_PRIORITY, Box::new(WasmEdgeExecutor {}));
         #[cfg(feature = "wasm-wasmtime")]
-        if WasmtimeExecutor::can_handle(spec)? {
-            return WasmtimeExecutor::exec(spec).context("wasmtime execution failed");
+        registry.register(WASM_PRIORITY, Box::new(WasmtimeExecutor {}));
+        registry.register(DEFAULT_PRIORITY, Box::new(DefaultExecutor {}));
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oci_spec::runtime::SpecBuilder;
+    use std::collections::HashMap;
+
+    struct TestExecutor {
+        name: &'static str,
+        annotation: &'static str,
+    }
+
+    impl Executor for TestExecutor {
+        fn exec(&self, _: &Spec) -> Result<()> {
+            Ok(())
         }
 
-        DefaultExecutor::exec(spec).context("default execution failed")
+        fn can_handle(&self, spec: &Spec) -> Result<bool> {
+            Ok(spec
+                .annotations()
+                .as_ref()
+                .map(|a| a.contains_key(self.annotation))
+                .unwrap_or_default())
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+    }
+
+    fn spec_with_annotation(key: &str) -> Result<Spec> {
+        let annotations = HashMap::from([(key.to_owned(), "true".to_owned())]);
+        Ok(SpecBuilder::default().annotations(annotations).build()?)
+    }
+
+    #[test]
+    fn test_registry_order() {
+        let mut registry = ExecutorRegistry::default();
+        registry.register(
+            10,
+            Box::new(TestExecutor {
+                name: "first",
+                annotation: "a",
+            }),
+        );
+        registry.register(
+            10,
+            Box::new(TestExecutor {
+                name: "second",
+                annotation: "a",
+            }),
+        );
+        registry.register(
+            20,
+            Box::new(TestExecutor {
+                name: "high",
+                annotation: "b",
+            }),
+        );
+
+        let names = registry.names();
+        assert_eq!(
+            names[names.len() - 4..],
+            ["high", "first", "second", "default"]
+        );
+    }
+
+    #[test]
+    fn test_registry_select() -> Result<()> {
+        let mut registry = ExecutorRegistry::default();
+        registry.register(
+            10,
+            Box::new(TestExecutor {
+                name: "custom",
+                annotation: "custom",
+            }),
+        );
+
+        let spec = spec_with_annotation("custom")?;
+        assert_eq!(registry.select(&spec)?.name(), "custom");
+        let spec = spec_with_annotation("other")?;
+        assert_eq!(registry.select(&spec)?.name(), "default");
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_registry() -> Result<()> {
+        let registry = ExecutorRegistry::empty();
+        assert!(registry.select(&Spec::default()).is_err());
+        Ok(())
     }
 }
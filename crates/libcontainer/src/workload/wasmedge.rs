@@ -11,7 +11,7 @@ const EXECUTOR_NAME: &str = "wasmedge";
 
 pub struct WasmEdgeExecutor {}
 impl Executor for WasmEdgeExecutor {
-    fn exec(spec: &Spec) -> Result<()> {
+    fn exec(&self, spec: &Spec) -> Result<()> {
         // parse wasi parameters
         let args = get_args(spec);
         let mut cmd = args[0].clone();
@@ -46,7 +46,7 @@ impl Executor for WasmEdgeExecutor {
         Ok(())
     }
 
-    fn can_handle(spec: &Spec) -> Result<bool> {
+    fn can_handle(&self, spec: &Spec) -> Result<bool> {
         if let Some(annotations) = spec.annotations() {
             if let Some(handler) = annotations.get("run.oci.handler") {
                 return Ok(handler == "wasm");
@@ -60,7 +60,7 @@ impl Executor for WasmEdgeExecutor {
         Ok(false)
     }
 
-    fn name() -> &'static str {
+    fn name(&self) -> &str {
         EXECUTOR_NAME
     }
 }
@@ -10,7 +10,7 @@ const EXECUTOR_NAME: &str = "wasmer";
 pub struct WasmerExecutor {}
 
 impl Executor for WasmerExecutor {
-    fn exec(spec: &Spec) -> Result<()> {
+    fn exec(&self, spec: &Spec) -> Result<()> {
         log::debug!("Executing workload with wasmer handler");
         let process = spec.process().as_ref();
 
@@ -62,7 +62,7 @@ impl Executor for WasmerExecutor {
         Ok(())
     }
 
-    fn can_handle(spec: &Spec) -> Result<bool> {
+    fn can_handle(&self, spec: &Spec) -> Result<bool> {
         if let Some(annotations) = spec.annotations() {
             if let Some(handler) = annotations.get("run.oci.handler") {
                 return Ok(handler == "wasm");
@@ -76,7 +76,7 @@ impl Executor for WasmerExecutor {
         Ok(false)
     }
 
-    fn name() -> &'static str {
+    fn name(&self) -> &str {
         EXECUTOR_NAME
     }
 }
@@ -96,7 +96,7 @@ mod tests {
             .build()
             .context("build spec")?;
 
-        assert!(WasmerExecutor::can_handle(&spec).context("can handle")?);
+        assert!(WasmerExecutor {}.can_handle(&spec).context("can handle")?);
 
         Ok(())
     }
@@ -110,7 +110,7 @@ mod tests {
             .build()
             .context("build spec")?;
 
-        assert!(WasmerExecutor::can_handle(&spec).context("can handle")?);
+        assert!(WasmerExecutor {}.can_handle(&spec).context("can handle")?);
 
         Ok(())
     }
@@ -119,7 +119,7 @@ mod tests {
     fn test_can_handle_no_execute() -> Result<()> {
         let spec = SpecBuilder::default().build().context("build spec")?;
 
-        assert!(!WasmerExecutor::can_handle(&spec).context("can handle")?);
+        assert!(!WasmerExecutor {}.can_handle(&spec).context("can handle")?);
 
         Ok(())
     }
@@ -10,7 +10,7 @@ const EXECUTOR_NAME: &str = "wasmtime";
 pub struct WasmtimeExecutor {}
 
 impl Executor for WasmtimeExecutor {
-    fn exec(spec: &Spec) -> Result<()> {
+    fn exec(&self, spec: &Spec) -> Result<()> {
         log::info!("Executing workload with wasmtime handler");
         let process = spec.process().as_ref();
 
@@ -76,7 +76,7 @@ impl Executor for WasmtimeExecutor {
             .context("wasm module was not executed successfully")
     }
 
-    fn can_handle(spec: &Spec) -> Result<bool> {
+    fn can_handle(&self, spec: &Spec) -> Result<bool> {
         if let Some(annotations) = spec.annotations() {
             if let Some(handler) = annotations.get("run.oci.handler") {
                 return Ok(handler == "wasm");
@@ -90,7 +90,7 @@ impl Executor for WasmtimeExecutor {
         Ok(false)
     }
 
-    fn name() -> &'static str {
+    fn name(&self) -> &str {
         EXECUTOR_NAME
     }
 }
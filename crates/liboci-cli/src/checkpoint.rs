@@ -41,7 +41,7 @@ pub struct Checkpoint {
     /// Do a pre-dump
     #[clap(long)]
     pub pre_dump: bool,
-    /// Cgroups mode
+    /// Cgroups mode: soft, full, strict or ignore
     #[clap(long)]
     pub manage_cgroups_mode: Option<String>,
     /// Checkpoint a namespace, but don't save its properties
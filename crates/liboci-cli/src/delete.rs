@@ -5,7 +5,16 @@ use clap::Parser;
 pub struct Delete {
     #[clap(value_parser = clap::builder::NonEmptyStringValueParser::new(), required = true)]
     pub container_id: String,
-    /// forces deletion of the container if it is still running (using SIGKILL)
+    /// forces deletion of the container if it is still running by killing it with SIGKILL. If
+    /// --stop-signal or --stop-timeout is given, it is stopped gracefully first
     #[clap(short, long)]
     pub force: bool,
+    /// signal sent to the container processes before they are killed when forcing deletion
+    /// (default: the stop signal annotation or SIGTERM)
+    #[clap(long, requires = "force")]
+    pub stop_signal: Option<String>,
+    /// seconds the container processes have to exit after the stop signal, before they are killed
+    /// (default: the stop timeout annotation or 10)
+    #[clap(long, requires = "force")]
+    pub stop_timeout: Option<u64>,
 }
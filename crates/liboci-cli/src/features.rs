@@ -0,0 +1,5 @@
+use clap::Parser;
+
+/// Show the enabled features
+#[derive(Parser, Debug)]
+pub struct Features {}
@@ -0,0 +1,35 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Restore a container from a previous checkpoint
+/// Reference: https://github.com/opencontainers/runc/blob/main/man/runc-restore.8.md
+#[derive(Parser, Debug)]
+pub struct Restore {
+    /// Path to criu image files for restoring
+    #[clap(long, default_value = "checkpoint")]
+    pub image_path: PathBuf,
+    /// Path for saving work files and logs
+    #[clap(long)]
+    pub work_path: Option<PathBuf>,
+    /// Allow open tcp connections
+    #[clap(long)]
+    pub tcp_established: bool,
+    /// Allow external unix sockets
+    #[clap(long)]
+    pub ext_unix_sk: bool,
+    /// Allow shell jobs
+    #[clap(long)]
+    pub shell_job: bool,
+    /// Allow file locks
+    #[clap(long)]
+    pub file_locks: bool,
+    /// path to the bundle directory, containing config.json and root filesystem
+    #[clap(short, long, default_value = ".")]
+    pub bundle: PathBuf,
+    /// File to write pid of the restored container
+    #[clap(long)]
+    pub pid_file: Option<PathBuf>,
+
+    #[clap(value_parser = clap::builder::NonEmptyStringValueParser::new(), required = true)]
+    pub container_id: String,
+}
@@ -1,4 +1,5 @@
 use clap::Parser;
+use std::error::Error;
 use std::path::PathBuf;
 
 /// Update running container resource constraints
@@ -15,4 +16,107 @@ pub struct Update {
     /// Set the maximum number of processes allowed in the container
     #[clap(long)]
     pub pids_limit: Option<i64>,
+
+    /// Memory limit (in bytes, or with a unit like 512m or 1g)
+    #[clap(long, value_parser = parse_size)]
+    pub memory: Option<i64>,
+
+    /// Total memory usage (memory + swap); set to -1 to enable unlimited swap
+    #[clap(long, allow_hyphen_values = true, value_parser = parse_size)]
+    pub memory_swap: Option<i64>,
+
+    /// Memory reservation or soft limit (in bytes, or with a unit like 512m or 1g)
+    #[clap(long, value_parser = parse_size)]
+    pub memory_reservation: Option<i64>,
+
+    /// Kernel memory limit (in bytes, or with a unit like 512m or 1g)
+    #[clap(long, value_parser = parse_size)]
+    pub kernel_memory: Option<i64>,
+
+    /// CPU shares (relative weight vs. other containers)
+    #[clap(long)]
+    pub cpu_shares: Option<u64>,
+
+    /// CPU CFS hardcap limit (in usecs). Allowed cpu time in a given period
+    #[clap(long, allow_hyphen_values = true)]
+    pub cpu_quota: Option<i64>,
+
+    /// CPU CFS period to be used for hardcapping (in usecs). 0 to use system default
+    #[clap(long)]
+    pub cpu_period: Option<u64>,
+
+    /// CPU(s) to use
+    #[clap(long)]
+    pub cpuset_cpus: Option<String>,
+
+    /// Memory node(s) to use
+    #[clap(long)]
+    pub cpuset_mems: Option<String>,
+
+    /// Specifies per cgroup weight, range is from 10 to 1000
+    #[clap(long)]
+    pub blkio_weight: Option<u16>,
+
+    /// The string of Intel RDT/CAT L3 cache schema
+    #[clap(long)]
+    pub l3_cache_schema: Option<String>,
+}
+
+/// Parses a size in bytes like runc does. The size can have a binary unit
+/// (k, m, g, t or p, optionally followed by i and b) and -1 means unlimited.
+fn parse_size(s: &str) -> Result<i64, Box<dyn Error + Send + Sync + 'static>> {
+    if s == "-1" {
+        return Ok(-1);
+    }
+
+    let lower = s.to_ascii_lowercase();
+    let number_end = lower
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(lower.len());
+    let (number, unit) = lower.split_at(number_end);
+    let unit = unit.strip_prefix(' ').unwrap_or(unit);
+    let unit = unit.strip_suffix('b').unwrap_or(unit);
+    let unit = unit.strip_suffix('i').unwrap_or(unit);
+    let shift = match unit {
+        "" => 0,
+        "k" => 10,
+        "m" => 20,
+        "g" => 30,
+        "t" => 40,
+        "p" => 50,
+        _ => return Err(format!("invalid size `{s}`: unknown unit").into()),
+    };
+
+    if number.contains('.') {
+        let size = number.parse::<f64>()? * (1u64 << shift) as f64;
+        if !size.is_finite() || size >= i64::MAX as f64 {
+            return Err(format!("invalid size `{s}`: too large").into());
+        }
+        return Ok(size as i64);
+    }
+
+    number
+        .parse::<i64>()?
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("invalid size `{s}`: too large").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("-1").unwrap(), -1);
+        assert_eq!(parse_size("512m").unwrap(), 512 << 20);
+        assert_eq!(parse_size("1g").unwrap(), 1 << 30);
+        assert_eq!(parse_size("1GiB").unwrap(), 1 << 30);
+        assert_eq!(parse_size("1.5k").unwrap(), 1536);
+        assert_eq!(parse_size("64 KB").unwrap(), 64 << 10);
+        assert!(parse_size("1x").is_err());
+        assert!(parse_size("m").is_err());
+        assert!(parse_size("-2").is_err());
+        assert!(parse_size("8388608p").is_err());
+    }
 }
@@ -343,6 +343,14 @@ impl Backend for CliBackend {
         self.invoke(backargs)
     }
 
+    fn features(&self, _args: liboci_cli::Features) -> Result<()> {
+        let mut backargs = Vec::<OsString>::new();
+
+        backargs.push("features".into());
+
+        self.invoke(backargs)
+    }
+
     fn list(&self, args: liboci_cli::List) -> Result<()> {
         let mut backargs = Vec::<OsString>::new();
 
@@ -374,6 +382,45 @@ impl Backend for CliBackend {
 
         self.invoke(backargs)
     }
+    fn restore(&self, args: liboci_cli::Restore) -> Result<()> {
+        // See https://github.com/opencontainers/runc/blob/main/man/runc-restore.8.md
+        let mut backargs = Vec::<OsString>::new();
+
+        backargs.push("restore".into());
+
+        if args.image_path.as_os_str() != "checkpoint" {
+            backargs.push("--image-path".into());
+            backargs.push(args.image_path.into_os_string())
+        }
+        if let Some(work_path) = args.work_path {
+            backargs.push("--work-path".into());
+            backargs.push(work_path.into_os_string())
+        }
+        if args.tcp_established {
+            backargs.push("--tcp-established".into())
+        }
+        if args.ext_unix_sk {
+            backargs.push("--ext-unix-sk".into())
+        }
+        if args.shell_job {
+            backargs.push("--shell-job".into())
+        }
+        if args.file_locks {
+            backargs.push("--file-locks".into())
+        }
+        if args.bundle.as_os_str() != "." {
+            backargs.push("--bundle".into());
+            backargs.push(args.bundle.into_os_string())
+        }
+        if let Some(pid_file) = args.pid_file {
+            backargs.push("--pid-file".into());
+            backargs.push(pid_file.into_os_string())
+        }
+
+        backargs.push(args.container_id.into());
+
+        self.invoke(backargs)
+    }
     fn resume(&self, args: liboci_cli::Resume) -> Result<()> {
         let mut backargs = Vec::<OsString>::new();
 
@@ -392,6 +439,34 @@ impl Backend for CliBackend {
         let mut backargs = Vec::<OsString>::new();
 
         backargs.push("update".into());
+        if let Some(resources) = args.resources {
+            backargs.push("--resources".into());
+            backargs.push(resources.into_os_string());
+        }
+        let limits = [
+            ("--pids-limit", args.pids_limit.map(|v| v.to_string())),
+            ("--memory", args.memory.map(|v| v.to_string())),
+            ("--memory-swap", args.memory_swap.map(|v| v.to_string())),
+            (
+                "--memory-reservation",
+                args.memory_reservation.map(|v| v.to_string()),
+            ),
+            ("--kernel-memory", args.kernel_memory.map(|v| v.to_string())),
+            ("--cpu-shares", args.cpu_shares.map(|v| v.to_string())),
+            ("--cpu-quota", args.cpu_quota.map(|v| v.to_string())),
+            ("--cpu-period", args.cpu_period.map(|v| v.to_string())),
+            ("--cpuset-cpus", args.cpuset_cpus),
+            ("--cpuset-mems", args.cpuset_mems),
+            ("--blkio-weight", args.blkio_weight.map(|v| v.to_string())),
+            ("--l3-cache-schema", args.l3_cache_schema),
+        ];
+        for (flag, value) in limits {
+            if let Some(value) = value {
+                backargs.push(flag.into());
+                backargs.push(value.into());
+            }
+        }
+        backargs.push(args.container_id.into());
 
         self.invoke(backargs)
     }
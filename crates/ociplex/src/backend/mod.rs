@@ -39,9 +39,11 @@ pub trait Backend: Debug {
             CommonCmd::Checkpointt(args) => self.checkpoint(args),
             CommonCmd::Events(args) => self.events(args),
             CommonCmd::Exec(args) => self.exec(args),
+            CommonCmd::Features(args) => self.features(args),
             CommonCmd::List(args) => self.list(args),
             CommonCmd::Pause(args) => self.pause(args),
             CommonCmd::Ps(args) => self.ps(args),
+            CommonCmd::Restore(args) => self.restore(args),
             CommonCmd::Resume(args) => self.resume(args),
             CommonCmd::Run(args) => self.run(args),
             CommonCmd::Update(args) => self.update(args),
@@ -66,6 +68,9 @@ pub trait Backend: Debug {
     fn exec(&self, args: liboci_cli::Exec) -> Result<()> {
         Err(anyhow!("exec subcommand unimplemented: {:?}", args))
     }
+    fn features(&self, args: liboci_cli::Features) -> Result<()> {
+        Err(anyhow!("features subcommand unimplemented: {:?}", args))
+    }
     fn list(&self, args: liboci_cli::List) -> Result<()> {
         Err(anyhow!("list subcommand unimplemented: {:?}", args))
     }
@@ -75,6 +80,9 @@ pub trait Backend: Debug {
     fn ps(&self, args: liboci_cli::Ps) -> Result<()> {
         Err(anyhow!("ps subcommand unimplemented: {:?}", args))
     }
+    fn restore(&self, args: liboci_cli::Restore) -> Result<()> {
+        Err(anyhow!("restore subcommand unimplemented: {:?}", args))
+    }
     fn resume(&self, args: liboci_cli::Resume) -> Result<()> {
         Err(anyhow!("resume subcommand unimplemented: {:?}", args))
     }
@@ -49,6 +49,9 @@ impl Backend for TrivialBackend {
     fn exec(&self, args: liboci_cli::Exec) -> Result<()> {
         Err(anyhow!("trivial: {:?}", args))
     }
+    fn features(&self, args: liboci_cli::Features) -> Result<()> {
+        Err(anyhow!("trivial: {:?}", args))
+    }
     fn list(&self, args: liboci_cli::List) -> Result<()> {
         Err(anyhow!("trivial: {:?}", args))
     }
@@ -58,6 +61,9 @@ impl Backend for TrivialBackend {
     fn ps(&self, args: liboci_cli::Ps) -> Result<()> {
         Err(anyhow!("trivial: {:?}", args))
     }
+    fn restore(&self, args: liboci_cli::Restore) -> Result<()> {
+        Err(anyhow!("trivial: {:?}", args))
+    }
     fn resume(&self, args: liboci_cli::Resume) -> Result<()> {
         Err(anyhow!("trivial: {:?}", args))
     }
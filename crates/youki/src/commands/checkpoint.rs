@@ -4,17 +4,36 @@ use std::path::PathBuf;
 
 use anyhow::{Context, Result};
 
+use libcontainer::container::ManageCgroupsMode;
 use liboci_cli::Checkpoint;
 
 pub fn checkpoint(args: Checkpoint, root_path: PathBuf) -> Result<()> {
     log::debug!("start checkpointing container {}", args.container_id);
     let mut container = load_container(root_path, &args.container_id)?;
+    let manage_cgroups_mode = args
+        .manage_cgroups_mode
+        .as_deref()
+        .map(str::parse::<ManageCgroupsMode>)
+        .transpose()?;
+    let status_fd = args
+        .status_fd
+        .map(i32::try_from)
+        .transpose()
+        .context("invalid status fd")?;
     let opts = libcontainer::container::CheckpointOptions {
+        auto_dedup: args.auto_dedup,
+        empty_ns: args.empty_ns,
         ext_unix_sk: args.ext_unix_sk,
         file_locks: args.file_locks,
         image_path: args.image_path,
+        lazy_pages: args.lazy_pages,
         leave_running: args.leave_running,
+        manage_cgroups_mode,
+        page_server: args.page_server,
+        parent_path: args.parent_path,
+        pre_dump: args.pre_dump,
         shell_job: args.shell_job,
+        status_fd,
         tcp_established: args.tcp_established,
         work_path: args.work_path,
     };
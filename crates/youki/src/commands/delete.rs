@@ -1,7 +1,10 @@
-use crate::commands::{container_exists, load_container};
+use crate::commands::{construct_container_root, container_exists, load_container};
 use anyhow::{Context, Result};
+use std::convert::TryFrom;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use libcontainer::{container::Container, signal::Signal};
 use liboci_cli::Delete;
 
 pub fn delete(args: Delete, root_path: PathBuf) -> Result<()> {
@@ -10,7 +13,30 @@ pub fn delete(args: Delete, root_path: PathBuf) -> Result<()> {
         return Ok(());
     }
 
-    let mut container = load_container(root_path, &args.container_id)?;
+    let mut container = match load_container(&root_path, &args.container_id) {
+        Ok(container) => container,
+        // the state of the container is broken, but it can still be cleaned up
+        Err(err) if args.force => {
+            log::warn!("{:#}, trying to recover", err);
+            Container::recover(construct_container_root(&root_path, &args.container_id)?)?
+        }
+        Err(err) => return Err(err),
+    };
+    // without a stop signal or timeout, deleting kills the container right away
+    let graceful = args.stop_signal.is_some() || args.stop_timeout.is_some();
+    if args.force && graceful && container.can_kill() {
+        let signal = args
+            .stop_signal
+            .as_deref()
+            .map(Signal::try_from)
+            .transpose()?;
+        let timeout = args.stop_timeout.map(Duration::from_secs);
+        // processes which have to be killed after the timeout are logged by stop
+        container
+            .stop(signal, timeout)
+            .with_context(|| format!("failed to stop container {}", args.container_id))?;
+    }
+
     container
         .delete(args.force)
         .with_context(|| format!("failed to delete container {}", args.container_id))
@@ -1,19 +1,39 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use nix::sys::wait::{waitpid, WaitStatus};
-use std::path::PathBuf;
+use oci_spec::runtime::Process;
+use std::fs::File;
+use std::io::BufReader;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
 
-use libcontainer::{container::builder::ContainerBuilder, syscall::syscall::create_syscall};
+use libcontainer::{container::builder::ContainerBuilder, syscall::syscall::create_syscall, tty};
 use liboci_cli::Exec;
 
 pub fn exec(args: Exec, root_path: PathBuf) -> Result<i32> {
+    // a detached process has nothing to attach its terminal to
+    let console = if args.detach {
+        None
+    } else {
+        let terminal = match &args.process {
+            Some(process) => process_terminal(process)?,
+            None => args.tty,
+        };
+        super::console_listener(&root_path, terminal, args.console_socket.as_ref())?
+    };
+    let console_socket = console
+        .as_ref()
+        .map(|c| c.path().to_path_buf())
+        .or_else(|| args.console_socket.clone());
+
     let syscall = create_syscall();
     let pid = ContainerBuilder::new(args.container_id.clone(), syscall.as_ref())
         .with_root_path(root_path)?
-        .with_console_socket(args.console_socket.as_ref())
+        .with_console_socket(console_socket.as_ref())
         .with_pid_file(args.pid_file.as_ref())?
         .validate_id()?
         .as_tenant()
         .with_detach(args.detach)
+        .with_tty(args.tty)
         .with_cwd(args.cwd.as_ref())
         .with_env(args.env.clone().into_iter().collect())
         .with_process(args.process.as_ref())
@@ -29,9 +49,24 @@ pub fn exec(args: Exec, root_path: PathBuf) -> Result<i32> {
         return Ok(0);
     }
 
+    if let Some(console) = console {
+        let master = console.receive()?;
+        tty::proxy_console(master.as_raw_fd())?;
+    }
+
     match waitpid(pid, None)? {
         WaitStatus::Exited(_, status) => Ok(status),
         WaitStatus::Signaled(_, sig, _) => Ok(sig as i32),
         _ => Ok(0),
     }
 }
+
+/// Returns whether the process given as process.json asks for a terminal,
+/// which overrides the --tty flag
+fn process_terminal(process: &Path) -> Result<bool> {
+    let file =
+        File::open(process).with_context(|| format!("failed to open {}", process.display()))?;
+    let process: Process = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("failed to parse {}", process.display()))?;
+    Ok(process.terminal().unwrap_or(false))
+}
@@ -0,0 +1,213 @@
+//! Contains functions related to reporting the features supported by youki in the format defined
+//! by the [OCI runtime-spec](https://github.com/opencontainers/runtime-spec/blob/main/features.md)
+use std::collections::HashMap;
+
+use anyhow::Result;
+use libcgroups::common::CgroupSetup;
+use libcontainer::{apparmor, rootfs::utils::KNOWN_MOUNT_OPTIONS, seccomp};
+use liboci_cli::Features;
+use oci_spec::runtime::{Arch, LinuxSeccompAction, LinuxSeccompFilterFlag, LinuxSeccompOperator};
+use serde::Serialize;
+
+use super::info;
+
+const OCI_VERSION_MIN: &str = "1.0.0";
+const OCI_VERSION_MAX: &str = "1.1.0";
+
+const HOOKS: &[&str] = &[
+    "prestart",
+    "createRuntime",
+    "createContainer",
+    "startContainer",
+    "poststart",
+    "poststop",
+];
+
+/// Namespaces youki is able to create or join, reported if the kernel config
+/// cannot be read
+const NAMESPACES: &[&str] = &[
+    "cgroup", "ipc", "mount", "network", "pid", "user", "uts", "time",
+];
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct FeaturesInfo {
+    oci_version_min: &'static str,
+    oci_version_max: &'static str,
+    hooks: &'static [&'static str],
+    mount_options: &'static [&'static str],
+    linux: LinuxFeatures,
+    annotations: HashMap<String, String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LinuxFeatures {
+    namespaces: Vec<&'static str>,
+    capabilities: Vec<String>,
+    cgroup: CgroupFeatures,
+    seccomp: SeccompFeatures,
+    apparmor: Enabled,
+    selinux: Enabled,
+    intel_rdt: Enabled,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CgroupFeatures {
+    v1: bool,
+    v2: bool,
+    systemd: bool,
+    systemd_user: bool,
+    rdma: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SeccompFeatures {
+    enabled: bool,
+    actions: &'static [LinuxSeccompAction],
+    operators: &'static [LinuxSeccompOperator],
+    archs: &'static [Arch],
+    known_flags: &'static [LinuxSeccompFilterFlag],
+    supported_flags: &'static [LinuxSeccompFilterFlag],
+}
+
+#[derive(Serialize, Debug)]
+struct Enabled {
+    enabled: bool,
+}
+
+/// Print the features supported by youki as JSON
+pub fn features(_: Features) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&features_info())?);
+    Ok(())
+}
+
+fn features_info() -> FeaturesInfo {
+    let namespaces = info::enabled_namespaces().unwrap_or_else(|| NAMESPACES.to_vec());
+
+    // the capabilities youki knows, whether the host grants them is up to the
+    // bounding set at runtime
+    let mut capabilities: Vec<String> =
+        caps::all().into_iter().map(|cap| cap.to_string()).collect();
+    capabilities.sort();
+
+    let annotations = HashMap::from([
+        (
+            "io.github.containers.youki.version".to_owned(),
+            env!("CARGO_PKG_VERSION").to_owned(),
+        ),
+        (
+            "io.github.containers.youki.commit".to_owned(),
+            env!("VERGEN_GIT_SHA_SHORT").to_owned(),
+        ),
+    ]);
+
+    FeaturesInfo {
+        oci_version_min: OCI_VERSION_MIN,
+        oci_version_max: OCI_VERSION_MAX,
+        hooks: HOOKS,
+        mount_options: KNOWN_MOUNT_OPTIONS,
+        linux: LinuxFeatures {
+            namespaces,
+            capabilities,
+            cgroup: cgroup_features(),
+            seccomp: SeccompFeatures {
+                enabled: true,
+                actions: seccomp::SUPPORTED_ACTIONS,
+                operators: seccomp::SUPPORTED_OPERATORS,
+                archs: seccomp::SUPPORTED_ARCHS,
+                known_flags: seccomp::SUPPORTED_FLAGS,
+                supported_flags: seccomp::SUPPORTED_FLAGS,
+            },
+            apparmor: Enabled {
+                enabled: apparmor::is_enabled().unwrap_or(false),
+            },
+            // youki does not apply SELinux labels, so SELinux is never reported
+            // as enabled even if the host supports it
+            selinux: Enabled { enabled: false },
+            // Intel RDT is not supported yet
+            intel_rdt: Enabled { enabled: false },
+        },
+        annotations,
+    }
+}
+
+/// Reports the cgroup versions that youki has been built for and which match
+/// the cgroup setup of the host
+fn cgroup_features() -> CgroupFeatures {
+    let setup = libcgroups::common::get_cgroup_setup().ok();
+    let v1 =
+        cfg!(feature = "v1") && matches!(setup, Some(CgroupSetup::Legacy | CgroupSetup::Hybrid));
+    let v2 = cfg!(feature = "v2") && matches!(setup, Some(CgroupSetup::Unified));
+    #[cfg(feature = "systemd")]
+    let systemd = libcgroups::systemd::booted();
+    #[cfg(not(feature = "systemd"))]
+    let systemd = false;
+
+    CgroupFeatures {
+        v1,
+        v2,
+        systemd,
+        // rootless containers only use systemd on cgroup v2
+        systemd_user: systemd && v2,
+        rdma: rdma_available(setup),
+    }
+}
+
+/// Checks whether the rdma controller is available in the cgroup setup
+fn rdma_available(setup: Option<CgroupSetup>) -> bool {
+    match setup {
+        #[cfg(feature = "v1")]
+        Some(CgroupSetup::Legacy | CgroupSetup::Hybrid) => {
+            libcgroups::v1::util::get_subsystem_mount_point(&libcgroups::v1::ControllerType::Rdma)
+                .is_ok()
+        }
+        #[cfg(feature = "v2")]
+        Some(CgroupSetup::Unified) => libcgroups::v2::util::get_unified_mount_point()
+            .and_then(libcgroups::v2::util::get_available_controllers)
+            .map_or(false, |controllers| {
+                controllers.contains(&libcgroups::v2::controller_type::ControllerType::Rdma)
+            }),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features_json() -> Result<()> {
+        let value = serde_json::to_value(features_info())?;
+
+        assert_eq!(value["ociVersionMin"], OCI_VERSION_MIN);
+        assert_eq!(value["ociVersionMax"], "1.1.0");
+        assert_eq!(value["hooks"].as_array().unwrap().len(), HOOKS.len());
+        assert!(value["mountOptions"]
+            .as_array()
+            .unwrap()
+            .contains(&"rbind".into()));
+        assert!(value["linux"]["namespaces"].is_array());
+        assert!(value["linux"]["cgroup"]["systemdUser"].is_boolean());
+        assert!(value["linux"]["capabilities"]
+            .as_array()
+            .unwrap()
+            .contains(&"CAP_SYS_ADMIN".into()));
+        assert!(value["linux"]["seccomp"]["actions"]
+            .as_array()
+            .unwrap()
+            .contains(&"SCMP_ACT_ERRNO".into()));
+        assert!(value["linux"]["seccomp"]["archs"]
+            .as_array()
+            .unwrap()
+            .contains(&"SCMP_ARCH_X86_64".into()));
+        assert!(value["linux"]["seccomp"]["knownFlags"]
+            .as_array()
+            .unwrap()
+            .contains(&"SECCOMP_FILTER_FLAG_LOG".into()));
+        assert_eq!(value["linux"]["intelRdt"]["enabled"], false);
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+//! Contains functionality of the gc command, which cleans up after containers
+//! whose creation or deletion did not complete
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use libcontainer::container::{Container, ContainerStatus, State};
+
+/// State directories younger than this might belong to a container that is
+/// still being created
+const GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+/// Parent of the cgroups that are managed by youki when cgroupfs is used
+const CGROUPFS_PARENT: &str = "youki";
+
+/// Prefix of the scopes that youki creates when systemd manages the cgroups
+#[cfg(feature = "systemd")]
+const SYSTEMD_SCOPE_PREFIX: &str = "youki-";
+
+/// Remove state directories and cgroups of containers whose creation or
+/// deletion did not complete
+#[derive(Parser, Debug)]
+pub struct Gc {
+    /// Only print the containers that would be removed
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+/// A cgroup of youki for which no state directory exists
+#[derive(Debug, PartialEq, Eq)]
+struct OrphanedCgroup {
+    container_id: String,
+    cgroups_path: PathBuf,
+    systemd: bool,
+}
+
+pub fn gc(args: Gc, root_path: PathBuf) -> Result<()> {
+    let root_path = fs::canonicalize(root_path)?;
+    let mut failed = Vec::new();
+    for entry in fs::read_dir(&root_path)? {
+        let container_root = entry?.path();
+        if let Err(err) = gc_container(&args, &container_root) {
+            log::error!("failed to clean up {:?}: {:?}", container_root, err);
+            failed.push(container_root.display().to_string());
+        }
+    }
+
+    for cgroup in orphaned_cgroups(&root_path) {
+        if let Err(err) = gc_cgroup(&args, &cgroup) {
+            log::error!(
+                "failed to clean up cgroup {:?}: {:?}",
+                cgroup.cgroups_path,
+                err
+            );
+            failed.push(cgroup.cgroups_path.display().to_string());
+        }
+    }
+
+    if !failed.is_empty() {
+        bail!("failed to clean up {}", failed.join(", "));
+    }
+
+    Ok(())
+}
+
+fn gc_container(args: &Gc, container_root: &Path) -> Result<()> {
+    if !container_root.is_dir() || !is_orphaned(container_root)? {
+        return Ok(());
+    }
+
+    let mut container = Container::recover(container_root.to_path_buf())?;
+    let remaining = container.remaining_processes()?;
+    if !remaining.is_empty() {
+        log::warn!(
+            "processes {:?} of container {} are still alive, use delete --force to remove it",
+            remaining,
+            container.id()
+        );
+        return Ok(());
+    }
+
+    if args.dry_run {
+        println!("{}", container.id());
+        return Ok(());
+    }
+
+    container
+        .delete(false)
+        .with_context(|| format!("failed to remove container {}", container.id()))?;
+    println!("{}", container.id());
+    Ok(())
+}
+
+fn gc_cgroup(args: &Gc, cgroup: &OrphanedCgroup) -> Result<()> {
+    let cmanager = libcgroups::common::create_cgroup_manager(
+        &cgroup.cgroups_path,
+        cgroup.systemd,
+        &cgroup.container_id,
+    )?;
+    if !cmanager.get_all_pids()?.is_empty() {
+        return Ok(());
+    }
+
+    if args.dry_run {
+        println!("{}", cgroup.container_id);
+        return Ok(());
+    }
+
+    cmanager
+        .remove()
+        .with_context(|| format!("failed to remove cgroup {:?}", cgroup.cgroups_path))?;
+    println!("{}", cgroup.container_id);
+    Ok(())
+}
+
+/// A container is orphaned if its state cannot be loaded or if it is still
+/// being created although its init process is gone. Deleting removes the
+/// state directory last, so the cgroups that are left over by an interrupted
+/// delete are removed together with the state directory. Cgroups for which no
+/// state directory exists at all are found by [`orphaned_cgroups`].
+fn is_orphaned(container_root: &Path) -> Result<bool> {
+    let modified = fs::metadata(container_root)?.modified()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    if age < GRACE_PERIOD {
+        return Ok(false);
+    }
+
+    match State::load(container_root) {
+        Ok(state) if state.status == ContainerStatus::Creating => {
+            let container = Container::recover(container_root.to_path_buf())?;
+            Ok(container.status() == ContainerStatus::Stopped)
+        }
+        Ok(_) => Ok(false),
+        Err(_) => Ok(true),
+    }
+}
+
+/// Returns the leaf cgroups below the parents that youki uses for its
+/// containers, which do not belong to a container in the root path. Only
+/// the youki parent of cgroupfs and the youki scopes in the default systemd
+/// slice are considered, as cgroups elsewhere might not have been created by
+/// youki. Whether these cgroups are empty is checked before removing them.
+fn orphaned_cgroups(root_path: &Path) -> Vec<OrphanedCgroup> {
+    // a container has a cgroup in each hierarchy, it can only be removed if
+    // it is a leaf in all of them
+    let mut candidates = BTreeMap::new();
+    for hierarchy in cgroup_hierarchies() {
+        for (dir, cgroup) in youki_cgroups(&hierarchy) {
+            if root_path.join(&cgroup.container_id).exists() {
+                continue;
+            }
+
+            let leaf = is_leaf(&dir);
+            candidates
+                .entry(cgroup.cgroups_path.clone())
+                .and_modify(|(_, is_leaf)| *is_leaf &= leaf)
+                .or_insert((cgroup, leaf));
+        }
+    }
+
+    candidates
+        .into_values()
+        .filter(|(_, leaf)| *leaf)
+        .map(|(cgroup, _)| cgroup)
+        .collect()
+}
+
+/// Returns the mount points of all cgroup hierarchies
+fn cgroup_hierarchies() -> Vec<PathBuf> {
+    #[allow(unused_mut)]
+    let mut hierarchies = Vec::new();
+    #[cfg(feature = "v1")]
+    if let Ok(mount_points) = libcgroups::v1::util::list_subsystem_mount_points() {
+        hierarchies.extend(mount_points);
+    }
+
+    #[cfg(feature = "v2")]
+    if let Ok(mount_point) = libcgroups::v2::util::get_unified_mount_point() {
+        hierarchies.push(mount_point);
+    }
+
+    hierarchies
+}
+
+/// Returns the directories of the cgroups that youki may have created in the
+/// hierarchy together with the container they belong to
+fn youki_cgroups(hierarchy: &Path) -> Vec<(PathBuf, OrphanedCgroup)> {
+    #[allow(unused_mut)]
+    let mut cgroups: Vec<_> = sub_directories(&hierarchy.join(CGROUPFS_PARENT))
+        .into_iter()
+        .map(|(dir, name)| {
+            let cgroup = OrphanedCgroup {
+                cgroups_path: Path::new("/").join(CGROUPFS_PARENT).join(&name),
+                container_id: name,
+                systemd: false,
+            };
+            (dir, cgroup)
+        })
+        .collect();
+
+    #[cfg(feature = "systemd")]
+    cgroups.extend(
+        sub_directories(&hierarchy.join(default_slice_path()))
+            .into_iter()
+            .filter_map(|(dir, name)| {
+                let container_id = name
+                    .strip_prefix(SYSTEMD_SCOPE_PREFIX)?
+                    .strip_suffix(".scope")?
+                    .to_owned();
+                let cgroup = OrphanedCgroup {
+                    cgroups_path: PathBuf::from(format!(":youki:{container_id}")),
+                    container_id,
+                    systemd: true,
+                };
+                Some((dir, cgroup))
+            }),
+    );
+
+    cgroups
+}
+
+/// Path of the slice, relative to the hierarchy, in which the systemd cgroup
+/// manager places containers without an explicit slice
+#[cfg(feature = "systemd")]
+fn default_slice_path() -> PathBuf {
+    let uid = nix::unistd::geteuid();
+    if uid.is_root() {
+        PathBuf::from("system.slice")
+    } else {
+        PathBuf::from(format!(
+            "user.slice/user-{uid}.slice/user@{uid}.service/user.slice"
+        ))
+    }
+}
+
+/// Returns the sub directories of the directory together with their names.
+/// A directory that does not exist has none.
+fn sub_directories(dir: &Path) -> Vec<(PathBuf, String)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| Some((entry.path(), entry.file_name().into_string().ok()?)))
+        .collect()
+}
+
+/// A cgroup is a leaf if it has no child cgroups. Cgroups that cannot be
+/// read are not considered to be leaves, so that they are kept.
+fn is_leaf(dir: &Path) -> bool {
+    match fs::read_dir(dir) {
+        Ok(mut entries) => !entries.any(|entry| entry.map_or(true, |e| e.path().is_dir())),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libcontainer::utils::create_temp_dir;
+
+    #[test]
+    fn test_is_leaf() -> Result<()> {
+        let tmp = create_temp_dir("test_is_leaf")?;
+        let cgroup = tmp.join("cgroup");
+        fs::create_dir(&cgroup)?;
+        fs::write(cgroup.join("cgroup.procs"), "")?;
+        assert!(is_leaf(&cgroup));
+
+        fs::create_dir(cgroup.join("child"))?;
+        assert!(!is_leaf(&cgroup));
+        assert!(!is_leaf(&tmp.join("missing")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_youki_cgroups() -> Result<()> {
+        let tmp = create_temp_dir("test_youki_cgroups")?;
+        fs::create_dir_all(tmp.join(CGROUPFS_PARENT).join("sample"))?;
+        fs::write(tmp.join(CGROUPFS_PARENT).join("cgroup.procs"), "")?;
+
+        let cgroups = youki_cgroups(&tmp);
+        assert_eq!(
+            cgroups,
+            vec![(
+                tmp.join(CGROUPFS_PARENT).join("sample"),
+                OrphanedCgroup {
+                    container_id: "sample".to_owned(),
+                    cgroups_path: PathBuf::from("/youki/sample"),
+                    systemd: false,
+                }
+            )]
+        );
+        Ok(())
+    }
+}
@@ -195,6 +195,21 @@ fn read_kernel_config() -> Option<String> {
     fs::read_to_string(kernel_config).ok()
 }
 
+/// Namespaces that can be disabled in the kernel config together with the
+/// option that enables them. The mount namespace is always available if
+/// namespaces are enabled at all.
+// While the CONFIG_CGROUP_NS kernel feature exists, it is obsolete and should not be used. CGroup namespaces
+// are instead enabled with CONFIG_CGROUPS.
+const NAMESPACE_FEATURES: &[(&str, &str)] = &[
+    ("uts", "CONFIG_UTS_NS"),
+    ("ipc", "CONFIG_IPC_NS"),
+    ("user", "CONFIG_USER_NS"),
+    ("pid", "CONFIG_PID_NS"),
+    ("network", "CONFIG_NET_NS"),
+    ("cgroup", "CONFIG_CGROUPS"),
+    ("time", "CONFIG_TIME_NS"),
+];
+
 pub fn print_namespaces() {
     if let Some(content) = read_kernel_config() {
         if let Some(ns_enabled) = find_parameter(&content, "CONFIG_NAMESPACES") {
@@ -208,22 +223,37 @@ pub fn print_namespaces() {
 
         // mount namespace is always enabled if namespaces are enabled
         println!("  {:<16}enabled", "mount");
-        print_feature_status(&content, "CONFIG_UTS_NS", FeatureDisplay::new("uts"));
-        print_feature_status(&content, "CONFIG_IPC_NS", FeatureDisplay::new("ipc"));
-
-        let user_display = match rootless::unprivileged_user_ns_enabled() {
-            Ok(false) => FeatureDisplay::with_status("user", "enabled (root only)", "disabled"),
-            _ => FeatureDisplay::new("user"),
-        };
-        print_feature_status(&content, "CONFIG_USER_NS", user_display);
-        print_feature_status(&content, "CONFIG_PID_NS", FeatureDisplay::new("pid"));
-        print_feature_status(&content, "CONFIG_NET_NS", FeatureDisplay::new("network"));
-        // While the CONFIG_CGROUP_NS kernel feature exists, it is obsolete and should not be used. CGroup namespaces
-        // are instead enabled with CONFIG_CGROUPS.
-        print_feature_status(&content, "CONFIG_CGROUPS", FeatureDisplay::new("cgroup"))
+        for (name, feature) in NAMESPACE_FEATURES {
+            let display = if *name == "user"
+                && matches!(rootless::unprivileged_user_ns_enabled(), Ok(false))
+            {
+                FeatureDisplay::with_status(name, "enabled (root only)", "disabled")
+            } else {
+                FeatureDisplay::new(name)
+            };
+            print_feature_status(&content, feature, display);
+        }
     }
 }
 
+/// Returns the namespaces that are enabled in the kernel config or None if
+/// the kernel config is not available
+pub fn enabled_namespaces() -> Option<Vec<&'static str>> {
+    let content = read_kernel_config()?;
+    if matches!(find_parameter(&content, "CONFIG_NAMESPACES"), Some(enabled) if enabled != "y") {
+        return Some(Vec::new());
+    }
+
+    let mut namespaces = vec!["mount"];
+    namespaces.extend(
+        NAMESPACE_FEATURES
+            .iter()
+            .filter(|(_, feature)| is_feature_enabled(&content, feature))
+            .map(|(name, _)| *name),
+    );
+    Some(namespaces)
+}
+
 #[inline]
 fn is_cap_available(caps: &caps::CapsHashSet, cap: caps::Capability) -> &'static str {
     if caps.contains(&cap) {
@@ -256,18 +286,18 @@ pub fn print_capabilities() {
     }
 }
 
+fn is_feature_enabled(config: &str, feature: &str) -> bool {
+    find_parameter(config, feature) == Some("y")
+}
+
 fn print_feature_status(config: &str, feature: &str, display: FeatureDisplay) {
-    if let Some(status_flag) = find_parameter(config, feature) {
-        let status = if status_flag == "y" {
-            display.enabled
-        } else {
-            display.disabled
-        };
-
-        println!("  {:<16}{}", display.name, status);
+    let status = if is_feature_enabled(config, feature) {
+        display.enabled
     } else {
-        println!("  {:<16}{}", display.name, display.disabled);
-    }
+        display.disabled
+    };
+
+    println!("  {:<16}{}", display.name, status);
 }
 
 struct FeatureDisplay<'a> {
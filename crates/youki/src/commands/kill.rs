@@ -15,9 +15,9 @@ pub fn kill(args: Kill, root_path: PathBuf) -> Result<()> {
         Err(e) => {
             // see https://github.com/containers/youki/issues/1314
             if container.status() == ContainerStatus::Stopped {
-                return Err(e.context("container not running"));
+                return Err(anyhow::Error::from(e).context("container not running"));
             }
-            Err(e)
+            Err(e.into())
         }
     }
 }
@@ -9,7 +9,7 @@ use anyhow::Result;
 use chrono::{DateTime, Local};
 use tabwriter::TabWriter;
 
-use libcontainer::container::{state::State, Container};
+use libcontainer::container::{state::State, Container, ExitStatus};
 use liboci_cli::List;
 
 /// lists all existing containers
@@ -43,10 +43,11 @@ pub fn list(_: List, root_path: PathBuf) -> Result<()> {
 
         let _ = writeln!(
             content,
-            "{}\t{}\t{}\t{}\t{}\t{}",
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
             container.id(),
             pid,
             container.status(),
+            describe_exit(&container),
             container.bundle().display(),
             created,
             user_name.to_string_lossy()
@@ -54,9 +55,36 @@ pub fn list(_: List, root_path: PathBuf) -> Result<()> {
     }
 
     let mut tab_writer = TabWriter::new(io::stdout());
-    writeln!(&mut tab_writer, "ID\tPID\tSTATUS\tBUNDLE\tCREATED\tCREATOR")?;
+    writeln!(
+        &mut tab_writer,
+        "ID\tPID\tSTATUS\tEXIT\tBUNDLE\tCREATED\tCREATOR"
+    )?;
     write!(&mut tab_writer, "{content}")?;
     tab_writer.flush()?;
 
     Ok(())
 }
+
+/// Describes how the container exited, e.g. `137 (SIGKILL, oom killed)`.
+/// Empty if the container has not exited yet.
+fn describe_exit(container: &Container) -> String {
+    if container.finished().is_none() {
+        return String::new();
+    }
+
+    let exit_status = container.exit_status();
+    let mut reasons = Vec::new();
+    if let Some(ExitStatus::Signaled(signal)) = exit_status {
+        reasons.push(signal.as_str());
+    }
+    if container.oom_killed() == Some(true) {
+        reasons.push("oom killed");
+    }
+
+    let code = exit_status.map_or_else(|| "?".to_owned(), |status| status.code().to_string());
+    if reasons.is_empty() {
+        code
+    } else {
+        format!("{} ({})", code, reasons.join(", "))
+    }
+}
@@ -5,7 +5,7 @@ use std::{
 };
 
 use libcgroups::common::CgroupManager;
-use libcontainer::container::Container;
+use libcontainer::{container::Container, tty::ConsoleListener};
 
 pub mod checkpoint;
 pub mod completion;
@@ -13,11 +13,14 @@ pub mod create;
 pub mod delete;
 pub mod events;
 pub mod exec;
+pub mod features;
+pub mod gc;
 pub mod info;
 pub mod kill;
 pub mod list;
 pub mod pause;
 pub mod ps;
+pub mod restore;
 pub mod resume;
 pub mod run;
 pub mod spec_json;
@@ -63,5 +66,29 @@ fn create_cgroup_manager<P: AsRef<Path>>(
         .systemd()
         .context("could not determine cgroup manager")?;
 
-    libcgroups::common::create_cgroup_manager(cgroups_path, systemd_cgroup, container.id())
+    Ok(libcgroups::common::create_cgroup_manager(
+        cgroups_path,
+        systemd_cgroup,
+        container.id(),
+    )?)
+}
+
+/// Creates the socket on which youki receives the pty of a container it is
+/// attached to in the foreground, if the container needs a terminal and no
+/// console socket has been given
+fn console_listener<P: AsRef<Path>>(
+    root_path: P,
+    terminal: bool,
+    console_socket: Option<&PathBuf>,
+) -> Result<Option<ConsoleListener>> {
+    if !terminal || console_socket.is_some() {
+        return Ok(None);
+    }
+
+    // the socket is linked into the container directory, so its path has to
+    // be absolute
+    let root_path = fs::canonicalize(root_path.as_ref())
+        .with_context(|| format!("failed to canonicalize {}", root_path.as_ref().display()))?;
+    let path = root_path.join(format!(".console-{}.sock", std::process::id()));
+    ConsoleListener::bind(path).map(Some)
 }
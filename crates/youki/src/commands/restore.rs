@@ -0,0 +1,25 @@
+//! Contains functionality of restore container command
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use libcontainer::container::{Container, RestoreOptions};
+
+use liboci_cli::Restore;
+
+pub fn restore(args: Restore, root_path: PathBuf, systemd_cgroup: bool) -> Result<()> {
+    log::debug!("start restoring container {}", args.container_id);
+    let opts = RestoreOptions {
+        bundle: args.bundle,
+        ext_unix_sk: args.ext_unix_sk,
+        file_locks: args.file_locks,
+        image_path: args.image_path,
+        pid_file: args.pid_file,
+        shell_job: args.shell_job,
+        tcp_established: args.tcp_established,
+        use_systemd: systemd_cgroup,
+        work_path: args.work_path,
+    };
+    Container::restore(&root_path, &args.container_id, &opts)
+        .with_context(|| format!("failed to restore container {}", args.container_id))?;
+    Ok(())
+}
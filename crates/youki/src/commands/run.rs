@@ -1,14 +1,38 @@
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
-use libcontainer::{container::builder::ContainerBuilder, syscall::syscall::create_syscall};
+use libcontainer::{container::builder::ContainerBuilder, syscall::syscall::create_syscall, tty};
 use liboci_cli::Run;
+use nix::sys::wait::{waitpid, WaitStatus};
+use oci_spec::runtime::Spec;
+use std::os::unix::io::AsRawFd;
+
+/// Creates and starts the container. If youki attaches to its console, it
+/// waits for the container to exit and returns its exit code, otherwise 0.
+pub fn run(args: Run, root_path: PathBuf, systemd_cgroup: bool) -> Result<i32> {
+    let spec = Spec::load(args.bundle.join("config.json")).context("failed to load spec")?;
+    let terminal = spec
+        .process()
+        .as_ref()
+        .and_then(|p| p.terminal())
+        .unwrap_or(false);
+    let console = super::console_listener(&root_path, terminal, args.console_socket.as_ref())?;
+    let console_socket = console
+        .as_ref()
+        .map(|c| c.path().to_path_buf())
+        .or(args.console_socket);
+    if console.is_some() {
+        // the init process is reparented to youki, so that its exit status
+        // can be collected once the console is closed
+        prctl::set_child_subreaper(true)
+            .map_err(nix::errno::Errno::from_i32)
+            .context("failed to become child subreaper")?;
+    }
 
-pub fn run(args: Run, root_path: PathBuf, systemd_cgroup: bool) -> Result<()> {
     let syscall = create_syscall();
     let mut container = ContainerBuilder::new(args.container_id.clone(), syscall.as_ref())
         .with_pid_file(args.pid_file.as_ref())?
-        .with_console_socket(args.console_socket.as_ref())
+        .with_console_socket(console_socket.as_ref())
         .with_root_path(root_path)?
         .with_preserved_fds(args.preserve_fds)
         .validate_id()?
@@ -16,7 +40,21 @@ pub fn run(args: Run, root_path: PathBuf, systemd_cgroup: bool) -> Result<()> {
         .with_systemd(systemd_cgroup)
         .build()?;
 
+    let master = console.map(|c| c.receive()).transpose()?;
+
     container
         .start()
-        .with_context(|| format!("failed to start container {}", args.container_id))
+        .with_context(|| format!("failed to start container {}", args.container_id))?;
+
+    if let Some(master) = master {
+        tty::proxy_console(master.as_raw_fd())?;
+        let pid = container.pid().context("container has no init process")?;
+        return match waitpid(pid, None)? {
+            WaitStatus::Exited(_, status) => Ok(status),
+            WaitStatus::Signaled(_, sig, _) => Ok(sig as i32),
+            _ => Ok(0),
+        };
+    }
+
+    Ok(0)
 }
@@ -2,37 +2,83 @@ use std::fs;
 use std::io;
 use std::path::PathBuf;
 
-use crate::commands::create_cgroup_manager;
-use anyhow::Result;
-use libcgroups::{self, common::ControllerOpt};
+use crate::commands::load_container;
+use anyhow::{bail, Context, Result};
 use liboci_cli::Update;
-use oci_spec::runtime::{LinuxPidsBuilder, LinuxResources, LinuxResourcesBuilder};
+use oci_spec::runtime::{
+    LinuxBlockIoBuilder, LinuxCpuBuilder, LinuxMemoryBuilder, LinuxPidsBuilder, LinuxResources,
+    LinuxResourcesBuilder,
+};
 
 pub fn update(args: Update, root_path: PathBuf) -> Result<()> {
-    let cmanager = create_cgroup_manager(root_path, &args.container_id)?;
+    let mut container = load_container(root_path, &args.container_id)?;
 
-    let linux_res: LinuxResources;
-    if let Some(resources_path) = args.resources {
-        linux_res = if resources_path.to_string_lossy() == "-" {
+    let linux_res: LinuxResources = if let Some(resources_path) = args.resources {
+        if resources_path.to_string_lossy() == "-" {
             serde_json::from_reader(io::stdin())?
         } else {
             let file = fs::File::open(resources_path)?;
             let reader = io::BufReader::new(file);
             serde_json::from_reader(reader)?
-        };
+        }
     } else {
-        let mut builder = LinuxResourcesBuilder::default();
-        if let Some(new_pids_limit) = args.pids_limit {
-            builder = builder.pids(LinuxPidsBuilder::default().limit(new_pids_limit).build()?);
+        resources_from_args(&args)?
+    };
+
+    container
+        .update(&linux_res)
+        .with_context(|| format!("failed to update container {}", args.container_id))
+}
+
+fn resources_from_args(args: &Update) -> Result<LinuxResources> {
+    if args.l3_cache_schema.is_some() {
+        bail!("intel RDT is not supported, --l3-cache-schema can not be used");
+    }
+
+    let mut builder = LinuxResourcesBuilder::default();
+    if let Some(new_pids_limit) = args.pids_limit {
+        builder = builder.pids(LinuxPidsBuilder::default().limit(new_pids_limit).build()?);
+    }
+
+    if args.memory.is_some()
+        || args.memory_swap.is_some()
+        || args.memory_reservation.is_some()
+        || args.kernel_memory.is_some()
+    {
+        let mut memory = LinuxMemoryBuilder::default();
+        if let Some(limit) = args.memory {
+            memory = memory.limit(limit);
         }
-        linux_res = builder.build()?;
+        if let Some(swap) = args.memory_swap {
+            memory = memory.swap(swap);
+        }
+        if let Some(reservation) = args.memory_reservation {
+            memory = memory.reservation(reservation);
+        }
+        if let Some(kernel) = args.kernel_memory {
+            memory = memory.kernel(kernel);
+        }
+        builder = builder.memory(memory.build()?);
+    }
+
+    if args.cpu_shares.is_some()
+        || args.cpu_quota.is_some()
+        || args.cpu_period.is_some()
+        || args.cpuset_cpus.is_some()
+        || args.cpuset_mems.is_some()
+    {
+        let mut cpu = LinuxCpuBuilder::default().build()?;
+        cpu.set_shares(args.cpu_shares)
+            .set_quota(args.cpu_quota)
+            .set_period(args.cpu_period)
+            .set_cpus(args.cpuset_cpus.clone())
+            .set_mems(args.cpuset_mems.clone());
+        builder = builder.cpu(cpu);
+    }
+
+    if let Some(weight) = args.blkio_weight {
+        builder = builder.block_io(LinuxBlockIoBuilder::default().weight(weight).build()?);
     }
 
-    cmanager.apply(&ControllerOpt {
-        resources: &linux_res,
-        disable_oom_killer: false,
-        oom_score_adj: None,
-        freezer_state: None,
-    })?;
-    Ok(())
+    Ok(builder.build()?)
 }
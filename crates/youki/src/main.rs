@@ -46,6 +46,7 @@ enum SubCommand {
 
     // Youki specific extensions
     Info(info::Info),
+    Gc(commands::gc::Gc),
     Completion(commands::completion::Completion),
 }
 
@@ -119,16 +120,24 @@ fn main() -> Result<()> {
                     std::process::exit(-1);
                 }
             },
+            CommonCmd::Features(features) => commands::features::features(features),
             CommonCmd::List(list) => commands::list::list(list, root_path),
             CommonCmd::Pause(pause) => commands::pause::pause(pause, root_path),
             CommonCmd::Ps(ps) => commands::ps::ps(ps, root_path),
+            CommonCmd::Restore(restore) => {
+                commands::restore::restore(restore, root_path, systemd_cgroup)
+            }
             CommonCmd::Resume(resume) => commands::resume::resume(resume, root_path),
-            CommonCmd::Run(run) => commands::run::run(run, root_path, systemd_cgroup),
+            CommonCmd::Run(run) => match commands::run::run(run, root_path, systemd_cgroup) {
+                Ok(exit_code) => std::process::exit(exit_code),
+                Err(e) => Err(e),
+            },
             CommonCmd::Spec(spec) => commands::spec_json::spec(spec),
             CommonCmd::Update(update) => commands::update::update(update, root_path),
         },
 
         SubCommand::Info(info) => commands::info::info(info),
+        SubCommand::Gc(gc) => commands::gc::gc(gc, root_path),
         SubCommand::Completion(completion) => {
             commands::completion::completion(completion, &mut app)
         }
@@ -3,6 +3,7 @@ use oci_spec::runtime::{Hook, HookBuilder, HooksBuilder, ProcessBuilder, Spec, S
 use std::{fs::File, io::Read};
 use test_framework::{Test, TestGroup, TestResult};
 
+use super::state::get_hooks_state_test;
 use crate::utils::{
     create_container, delete_container, generate_uuid, prepare_bundle, set_config,
     test_utils::start_container,
@@ -95,6 +96,9 @@ fn get_test(test_name: &'static str) -> Test {
 
 pub fn get_hooks_tests() -> TestGroup {
     let mut tg = TestGroup::new("hooks");
-    tg.add(vec![Box::new(get_test("hooks"))]);
+    tg.add(vec![
+        Box::new(get_test("hooks")),
+        Box::new(get_hooks_state_test()),
+    ]);
     tg
 }
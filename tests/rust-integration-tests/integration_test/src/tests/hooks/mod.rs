@@ -1,2 +1,3 @@
 mod invoke;
+mod state;
 pub use invoke::get_hooks_tests;
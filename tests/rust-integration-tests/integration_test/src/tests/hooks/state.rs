@@ -0,0 +1,138 @@
+use anyhow::{anyhow, bail, Context, Result};
+use oci_spec::runtime::{Hook, HookBuilder, HooksBuilder, ProcessBuilder, Spec, SpecBuilder};
+use std::path::Path;
+use test_framework::{Test, TestResult};
+
+use crate::utils::{
+    create_container, delete_container, generate_uuid, get_state, prepare_bundle, set_config,
+    test_utils::{start_container, State},
+};
+
+const HOOK_LOG_FILE: &str = "hooks.log";
+
+/// Creates a hook that appends the name of the phase and the state it
+/// received on stdin as a single line to the log
+fn state_log_hook(phase: &str, log: &Path) -> Hook {
+    HookBuilder::default()
+        .path("/bin/sh")
+        .args(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!(
+                "{{ printf '{phase} '; cat; echo; }} >> {}",
+                log.to_str().unwrap()
+            ),
+        ])
+        .build()
+        .expect("could not build hook")
+}
+
+fn get_spec(log: &Path) -> Spec {
+    SpecBuilder::default()
+        .process(
+            ProcessBuilder::default()
+                .args(vec!["true".to_string()])
+                .build()
+                .unwrap(),
+        )
+        .hooks(
+            HooksBuilder::default()
+                .prestart(vec![state_log_hook("prestart", log)])
+                .create_runtime(vec![state_log_hook("createRuntime", log)])
+                .create_container(vec![state_log_hook("createContainer", log)])
+                .poststart(vec![state_log_hook("poststart", log)])
+                .poststop(vec![state_log_hook("poststop", log)])
+                .build()
+                .expect("could not build hooks"),
+        )
+        .build()
+        .unwrap()
+}
+
+fn parse_log(log: &str) -> Result<Vec<(String, State)>> {
+    log.lines()
+        .map(|line| {
+            let (phase, state) = line
+                .split_once(' ')
+                .with_context(|| format!("invalid hook log line {line:?}"))?;
+            let state = serde_json::from_str(state)
+                .with_context(|| format!("invalid state passed to {phase} hook"))?;
+            Ok((phase.to_owned(), state))
+        })
+        .collect()
+}
+
+fn check_log(log: &str, id: &str, init_pid: i32) -> Result<()> {
+    let entries = parse_log(log)?;
+    let phases: Vec<&str> = entries.iter().map(|(phase, _)| phase.as_str()).collect();
+    let expected = [
+        "prestart",
+        "createRuntime",
+        "createContainer",
+        "poststart",
+        "poststop",
+    ];
+    if phases != expected {
+        bail!("hooks must be called in the order {expected:?}, but were called in the order {phases:?}");
+    }
+
+    for (phase, state) in &entries {
+        if state.id != id {
+            bail!("{phase} hook received state of container {}", state.id);
+        }
+    }
+
+    // the runtime hooks are run once the init process exists, so they must
+    // receive its pid while the container is still being created
+    for (phase, state) in entries.iter().take(3) {
+        if state.status != "creating" {
+            bail!("{phase} hook received status {}", state.status);
+        }
+        if state.pid != Some(init_pid) {
+            bail!(
+                "{phase} hook received pid {:?}, but the init process is {init_pid}",
+                state.pid
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn get_test(test_name: &'static str) -> Test {
+    Test::new(
+        test_name,
+        Box::new(move || {
+            let id = generate_uuid();
+            let id_str = id.to_string();
+            let bundle = prepare_bundle(&id).unwrap();
+            let log_path = bundle.join(HOOK_LOG_FILE);
+            std::fs::File::create(&log_path).expect("fail to create hook log");
+            set_config(&bundle, &get_spec(&log_path)).unwrap();
+
+            create_container(&id_str, &bundle).unwrap().wait().unwrap();
+            let (out, err) = get_state(&id_str, &bundle).unwrap();
+            let init_pid = match serde_json::from_str::<State>(&out) {
+                Ok(State { pid: Some(pid), .. }) => pid,
+                _ => {
+                    delete_container(&id_str, &bundle).unwrap().wait().unwrap();
+                    return TestResult::Failed(anyhow!(
+                        "error : could not get pid of created container: {err}"
+                    ));
+                }
+            };
+            start_container(&id_str, &bundle).unwrap().wait().unwrap();
+            delete_container(&id_str, &bundle).unwrap().wait().unwrap();
+
+            let log = std::fs::read_to_string(&log_path).expect("fail to read hook log");
+            match check_log(&log, &id_str, init_pid) {
+                Ok(()) => TestResult::Passed,
+                Err(err) => TestResult::Failed(anyhow!("error : {err:#}")),
+            }
+        }),
+    )
+}
+
+pub fn get_hooks_state_test() -> Test {
+    get_test("hooks_state")
+}